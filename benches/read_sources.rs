@@ -0,0 +1,44 @@
+//! Sequential vs concurrent reads of the files `index_workspace` indexes.
+//!
+//! Run with `cargo bench --bench read_sources`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use futures::StreamExt;
+use rust_mcp_server::tools::index_workspace::load_sources;
+use rust_mcp_server::AstCache;
+use tempfile::TempDir;
+
+const FILE_COUNT: usize = 500;
+
+fn workspace() -> (TempDir, Vec<String>) {
+    let dir = TempDir::new().unwrap();
+    let paths = (0..FILE_COUNT)
+        .map(|i| {
+            let path = dir.path().join(format!("module_{}.rs", i));
+            let code = (0..50).map(|j| format!("pub fn f{}_{}() -> usize {{ {} }}\n", i, j, j)).collect::<String>();
+            std::fs::write(&path, code).unwrap();
+            path.to_string_lossy().to_string()
+        })
+        .collect();
+    (dir, paths)
+}
+
+fn read_sources(c: &mut Criterion) {
+    let (_dir, paths) = workspace();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // An empty cache, so every iteration reads from disk
+    let cache = AstCache::new();
+
+    let mut group = c.benchmark_group("read_sources");
+    for concurrency in [1, 4, 16, 64] {
+        group.bench_with_input(BenchmarkId::from_parameter(concurrency), &concurrency, |b, &concurrency| {
+            b.iter(|| {
+                runtime.block_on(load_sources(&cache, paths.clone(), u64::MAX, concurrency).count())
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read_sources);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use syn::visit::Visit;
+use crate::models::*;
+use crate::visitors::{SymbolCollector, CallGraphCollector, TypeUsageCollector, ModuleDependencyCollector};
+
+/// Parses one file and runs every collector over it, with default position options.
+pub fn analyze_source(path: &str, code: &str) -> Result<FileAnalysis, syn::Error> {
+    analyze_source_with_options(path, code, PositionOptions::default())
+}
+
+/// Options for `analyze_source_with_options`. A bare `PositionOptions` converts into this.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AnalysisOptions {
+    pub position_options: PositionOptions,
+    /// Drop items annotated `#[cfg(test)]` (including whole test modules) before analyzing.
+    pub ignore_cfg_test: bool,
+}
+
+impl From<PositionOptions> for AnalysisOptions {
+    fn from(position_options: PositionOptions) -> Self {
+        Self { position_options, ..Default::default() }
+    }
+}
+
+/// Parses one file and runs every collector over it, encoding ranges per the position options.
+///
+/// This is pure: it touches neither the cache nor the filesystem, so workspace-level tools
+/// only need to aggregate the per-file results.
+pub fn analyze_source_with_options(
+    path: &str,
+    code: &str,
+    options: impl Into<AnalysisOptions>,
+) -> Result<FileAnalysis, syn::Error> {
+    let AnalysisOptions { position_options, ignore_cfg_test } = options.into();
+    let mut ast = syn::parse_file(code)?;
+    if ignore_cfg_test {
+        strip_cfg_test_items(&mut ast.items);
+    }
+    let line_index = position_options.line_index(code);
+
+    // Collect symbols
+    let mut symbol_collector = SymbolCollector::new(path.to_string(), line_index.as_ref());
+    symbol_collector.visit_file(&ast);
+
+    // Collect call graph and function info
+    let mut call_collector = CallGraphCollector {
+        file: path.to_string(),
+        line_index: line_index.as_ref(),
+        current_function: None,
+        module_path: Vec::new(),
+        current_impl: None,
+        nesting_depth: 0,
+        max_nesting_depth: 0,
+        calls: HashMap::new(),
+        function_info: HashMap::new(),
+        call_sites: Vec::new(),
+        tail_calls: HashMap::new(),
+    };
+    call_collector.visit_file(&ast);
+
+    // Collect type usage and struct/enum info
+    let mut type_collector = TypeUsageCollector {
+        file: path.to_string(),
+        line_index: line_index.as_ref(),
+        definitions: HashMap::new(),
+        usages: HashMap::new(),
+        struct_info: HashMap::new(),
+        enum_info: HashMap::new(),
+    };
+    type_collector.visit_file(&ast);
+
+    // Collect module dependencies
+    let mut mod_collector = ModuleDependencyCollector {
+        file: path.to_string(),
+        dependencies: HashMap::new(),
+        use_paths: Vec::new(),
+    };
+    mod_collector.visit_file(&ast);
+
+    Ok(FileAnalysis {
+        file: path.to_string(),
+        symbols: symbol_collector.out,
+        functions: call_collector.function_info.into_values().collect(),
+        structs: type_collector.struct_info.into_values().collect(),
+        enums: type_collector.enum_info.into_values().collect(),
+        calls: call_collector.calls,
+        type_definitions: type_collector.definitions,
+        type_usages: type_collector.usages,
+        call_sites: call_collector.call_sites,
+        tail_calls: call_collector.tail_calls,
+        module_dependencies: mod_collector.dependencies.into_values().flatten().collect(),
+        use_paths: mod_collector.use_paths,
+        mod_declarations: crate::modules::mod_declarations(path, &ast),
+    })
+}
+
+/// Removes `#[cfg(test)]` items, recursing into inline modules and impl blocks.
+pub fn strip_cfg_test_items(items: &mut Vec<syn::Item>) {
+    items.retain(|item| !is_cfg_test(item_attrs(item)));
+    for item in items {
+        match item {
+            syn::Item::Mod(syn::ItemMod { content: Some((_, items)), .. }) => strip_cfg_test_items(items),
+            syn::Item::Impl(item_impl) => item_impl.items.retain(|item| !is_cfg_test(impl_item_attrs(item))),
+            _ => {}
+        }
+    }
+}
+
+/// Whether the attributes compile the item only for tests: `#[cfg(test)]`, or an `all(..)`
+/// predicate that requires `test`. `not(test)` and `any(test, ..)` don't count.
+pub fn is_cfg_test(attrs: &[syn::Attribute]) -> bool {
+    fn requires_test(predicate: &syn::Meta) -> bool {
+        match predicate {
+            syn::Meta::Path(path) => path.is_ident("test"),
+            syn::Meta::List(list) if list.path.is_ident("all") => list
+                .parse_args_with(syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+                .is_ok_and(|predicates| predicates.iter().any(requires_test)),
+            _ => false,
+        }
+    }
+
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .any(|attr| attr.parse_args::<syn::Meta>().is_ok_and(|predicate| requires_test(&predicate)))
+}
+
+fn item_attrs(item: &syn::Item) -> &[syn::Attribute] {
+    match item {
+        syn::Item::Const(item) => &item.attrs,
+        syn::Item::Enum(item) => &item.attrs,
+        syn::Item::ExternCrate(item) => &item.attrs,
+        syn::Item::Fn(item) => &item.attrs,
+        syn::Item::ForeignMod(item) => &item.attrs,
+        syn::Item::Impl(item) => &item.attrs,
+        syn::Item::Macro(item) => &item.attrs,
+        syn::Item::Mod(item) => &item.attrs,
+        syn::Item::Static(item) => &item.attrs,
+        syn::Item::Struct(item) => &item.attrs,
+        syn::Item::Trait(item) => &item.attrs,
+        syn::Item::TraitAlias(item) => &item.attrs,
+        syn::Item::Type(item) => &item.attrs,
+        syn::Item::Union(item) => &item.attrs,
+        syn::Item::Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+fn impl_item_attrs(item: &syn::ImplItem) -> &[syn::Attribute] {
+    match item {
+        syn::ImplItem::Const(item) => &item.attrs,
+        syn::ImplItem::Fn(item) => &item.attrs,
+        syn::ImplItem::Type(item) => &item.attrs,
+        syn::ImplItem::Macro(item) => &item.attrs,
+        _ => &[],
+    }
+}
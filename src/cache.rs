@@ -1,52 +1,443 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+use std::time::Instant;
 use rmcp::{
     model::*,
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext},
+    service::RequestContext,
+    ErrorData as McpError,
+    RoleServer,
     ServerHandler,
 };
+use sha2::{Digest, Sha256};
+use crate::analysis::{analyze_source_with_options, AnalysisOptions};
+use crate::models::{FileAnalysis, SymbolKind, Thresholds};
+use crate::tools::index_workspace::FileFilter;
+
+/// Boxed future returned by `CacheBackend` methods, keeping the trait object-safe.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Storage behind `AstCache`, mapping file paths to their source code.
+///
+/// Implement this to back the cache with an external store (Redis, sled, ...) shared
+/// between several server processes.
+pub trait CacheBackend: Send + Sync {
+    fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>>;
+    fn insert(&self, path: String, code: String) -> BoxFuture<'_, ()>;
+    fn get_all(&self) -> BoxFuture<'_, HashMap<String, String>>;
+    fn remove<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>>;
+
+    /// Inserts like `insert`, returning the paths evicted to make room (including `path` if it
+    /// wasn't kept). Backends that never evict can keep this default.
+    fn insert_evicting(&self, path: String, code: String) -> BoxFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            self.insert(path, code).await;
+            Vec::new()
+        })
+    }
+
+    fn contains<'a>(&'a self, path: &'a str) -> BoxFuture<'a, bool> {
+        Box::pin(async move { self.get(path).await.is_some() })
+    }
+
+    fn len(&self) -> BoxFuture<'_, usize> {
+        Box::pin(async move { self.get_all().await.len() })
+    }
+
+    fn is_empty(&self) -> BoxFuture<'_, bool> {
+        Box::pin(async move { self.len().await == 0 })
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            for path in self.get_all().await.keys() {
+                self.remove(path).await;
+            }
+        })
+    }
+}
+
+/// Default backend keeping every file in a process-local `HashMap`.
+#[derive(Default)]
+pub struct InMemoryBackend {
+    map: RwLock<HashMap<String, String>>,
+}
+
+impl CacheBackend for InMemoryBackend {
+    fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { self.map.read().await.get(path).cloned() })
+    }
+
+    fn insert(&self, path: String, code: String) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.map.write().await.insert(path, code);
+        })
+    }
+
+    fn get_all(&self) -> BoxFuture<'_, HashMap<String, String>> {
+        Box::pin(async move { self.map.read().await.clone() })
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { self.map.write().await.remove(path) })
+    }
+
+    fn contains<'a>(&'a self, path: &'a str) -> BoxFuture<'a, bool> {
+        Box::pin(async move { self.map.read().await.contains_key(path) })
+    }
+
+    fn len(&self) -> BoxFuture<'_, usize> {
+        Box::pin(async move { self.map.read().await.len() })
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move { self.map.write().await.clear() })
+    }
+}
+
+/// How much an `LruBackend` holds before it evicts the least-recently-used files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheCapacity {
+    /// At most this many files.
+    Entries(usize),
+    /// At most this many bytes of source code in total.
+    Bytes(usize),
+}
+
+/// Capacity-bounded backend for large workspaces, evicting the files least recently
+/// inserted or read once the capacity is exceeded.
+pub struct LruBackend {
+    capacity: CacheCapacity,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    /// Source code and last use of each file.
+    entries: HashMap<String, (String, u64)>,
+    /// Files by last use, oldest first.
+    order: BTreeMap<u64, String>,
+    clock: u64,
+    bytes: usize,
+}
+
+impl LruState {
+    fn touch(&mut self, path: &str) -> Option<String> {
+        self.clock += 1;
+        let (code, last_use) = self.entries.get_mut(path)?;
+        let path = self.order.remove(last_use).unwrap_or_else(|| path.to_string());
+        *last_use = self.clock;
+        let code = code.clone();
+        self.order.insert(self.clock, path);
+        Some(code)
+    }
+
+    fn remove(&mut self, path: &str) -> Option<String> {
+        let (code, last_use) = self.entries.remove(path)?;
+        self.order.remove(&last_use);
+        self.bytes -= code.len();
+        Some(code)
+    }
+
+    fn over(&self, capacity: CacheCapacity) -> bool {
+        match capacity {
+            CacheCapacity::Entries(max) => self.entries.len() > max,
+            CacheCapacity::Bytes(max) => self.bytes > max,
+        }
+    }
+}
+
+impl LruBackend {
+    pub fn new(capacity: CacheCapacity) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(LruState::default()),
+        }
+    }
+}
+
+impl CacheBackend for LruBackend {
+    fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { self.state.lock().await.touch(path) })
+    }
+
+    fn insert(&self, path: String, code: String) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            self.insert_evicting(path, code).await;
+        })
+    }
+
+    fn insert_evicting(&self, path: String, code: String) -> BoxFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let mut state = self.state.lock().await;
+            state.remove(&path);
+            // A file that can't fit even on its own isn't worth evicting everything else for
+            if matches!(self.capacity, CacheCapacity::Bytes(max) if code.len() > max) {
+                return vec![path];
+            }
+
+            state.clock += 1;
+            let clock = state.clock;
+            state.bytes += code.len();
+            state.order.insert(clock, path.clone());
+            state.entries.insert(path, (code, clock));
+            let mut evicted = Vec::new();
+            while state.over(self.capacity) {
+                let Some((_, oldest)) = state.order.pop_first() else { break };
+                if let Some((code, _)) = state.entries.remove(&oldest) {
+                    state.bytes -= code.len();
+                }
+                evicted.push(oldest);
+            }
+            evicted
+        })
+    }
+
+    fn get_all(&self) -> BoxFuture<'_, HashMap<String, String>> {
+        Box::pin(async move {
+            let state = self.state.lock().await;
+            state.entries.iter().map(|(path, (code, _))| (path.clone(), code.clone())).collect()
+        })
+    }
+
+    fn remove<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>> {
+        Box::pin(async move { self.state.lock().await.remove(path) })
+    }
+
+    fn contains<'a>(&'a self, path: &'a str) -> BoxFuture<'a, bool> {
+        Box::pin(async move { self.state.lock().await.entries.contains_key(path) })
+    }
+
+    fn len(&self) -> BoxFuture<'_, usize> {
+        Box::pin(async move { self.state.lock().await.entries.len() })
+    }
+
+    fn clear(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            *self.state.lock().await = LruState::default();
+        })
+    }
+}
+
+/// SHA-256 of a file's source code.
+pub type ContentHash = [u8; 32];
+
+/// Analyses shared between files with identical content, see `AstCache::analyze`. Only
+/// content that some cached file still has is kept, so the analyses shrink with the cache.
+#[derive(Default)]
+pub(crate) struct SharedAnalyses {
+    /// Analyses by the hash of the source they came from and the options used.
+    pub(crate) analyses: HashMap<ContentHash, HashMap<AnalysisOptions, FileAnalysis>>,
+    /// Hash of each cached file's content.
+    hashes: HashMap<String, ContentHash>,
+    /// How many cached files have each content.
+    users: HashMap<ContentHash, usize>,
+}
+
+impl SharedAnalyses {
+    /// Records that `path` now has the content hashed as `hash`, or left the cache for `None`.
+    fn set(&mut self, path: &str, hash: Option<ContentHash>) {
+        let old = match hash {
+            Some(hash) => self.hashes.insert(path.to_string(), hash),
+            None => self.hashes.remove(path),
+        };
+        if old == hash {
+            return;
+        }
+        if let Some(hash) = hash {
+            *self.users.entry(hash).or_default() += 1;
+        }
+        let Some(old) = old else { return };
+        if let Some(users) = self.users.get_mut(&old) {
+            *users -= 1;
+            if *users == 0 {
+                self.users.remove(&old);
+                self.analyses.remove(&old);
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct AstCache {
-    map: Arc<RwLock<HashMap<String, String>>>,
+    backend: Arc<dyn CacheBackend>,
+    /// Lets files with identical content (generated code, copied boilerplate) be parsed once.
+    pub(crate) shared: Arc<RwLock<SharedAnalyses>>,
 }
 
 impl AstCache {
     pub fn new() -> Self {
+        Self::with_backend(InMemoryBackend::default())
+    }
+
+    /// A cache that evicts least-recently-used files beyond `capacity`; `new` is unbounded.
+    pub fn with_capacity(capacity: CacheCapacity) -> Self {
+        Self::with_backend(LruBackend::new(capacity))
+    }
+
+    pub fn with_backend(backend: impl CacheBackend + 'static) -> Self {
         Self {
-            map: Arc::new(RwLock::new(HashMap::new())),
+            backend: Arc::new(backend),
+            shared: Arc::new(RwLock::new(SharedAnalyses::default())),
         }
     }
 
     pub async fn insert(&self, path: String, code: String) {
-        let mut map = self.map.write().await;
-        map.insert(path, code);
+        let hash = content_hash(&code);
+        let evicted = self.backend.insert_evicting(path.clone(), code).await;
+        let mut shared = self.shared.write().await;
+        shared.set(&path, Some(hash));
+        for path in evicted {
+            shared.set(&path, None);
+        }
     }
 
     pub async fn get(&self, path: &str) -> Option<String> {
-        let map = self.map.read().await;
-        map.get(path).cloned()
+        self.backend.get(path).await
     }
 
     pub async fn get_all(&self) -> HashMap<String, String> {
-        let map = self.map.read().await;
-        map.clone()
+        self.backend.get_all().await
+    }
+
+    /// Evicts a single file, returning its cached source if it was present.
+    pub async fn remove(&self, path: &str) -> Option<String> {
+        let code = self.backend.remove(path).await;
+        self.shared.write().await.set(path, None);
+        code
+    }
+
+    pub async fn contains(&self, path: &str) -> bool {
+        self.backend.contains(path).await
+    }
+
+    pub async fn len(&self) -> usize {
+        self.backend.len().await
     }
+
+    pub async fn is_empty(&self) -> bool {
+        self.backend.is_empty().await
+    }
+
+    pub async fn clear(&self) {
+        self.backend.clear().await;
+        *self.shared.write().await = SharedAnalyses::default();
+    }
+
+    /// Analyzes `code` as the file at `path`, reusing the analysis of any earlier file with
+    /// the same content and options. The analysis is only kept while a file inserted into the
+    /// cache has that content. Files declaring out-of-line modules are always analyzed afresh,
+    /// since where `mod foo;` loads from depends on the declaring file's path.
+    pub async fn analyze(&self, path: &str, code: &str, options: AnalysisOptions) -> Result<FileAnalysis, syn::Error> {
+        let hash = content_hash(code);
+        if let Some(analysis) = self.shared.read().await.analyses.get(&hash).and_then(|analyses| analyses.get(&options)) {
+            return Ok(relocate(analysis.clone(), path));
+        }
+
+        let analysis = analyze_source_with_options(path, code, options)?;
+        let mut shared = self.shared.write().await;
+        if analysis.mod_declarations.is_empty() && shared.users.contains_key(&hash) {
+            shared.analyses.entry(hash).or_default().insert(options, analysis.clone());
+        }
+        Ok(analysis)
+    }
+}
+
+pub fn content_hash(code: &str) -> ContentHash {
+    Sha256::digest(code.as_bytes()).into()
+}
+
+/// Points every location in an analysis at `path` instead of the file it was made from.
+fn relocate(mut analysis: FileAnalysis, path: &str) -> FileAnalysis {
+    analysis.file = path.to_string();
+    for symbol in &mut analysis.symbols {
+        symbol.file = path.to_string();
+    }
+    for function in &mut analysis.functions {
+        function.file = path.to_string();
+    }
+    for info in &mut analysis.structs {
+        info.file = path.to_string();
+    }
+    for info in &mut analysis.enums {
+        info.file = path.to_string();
+    }
+    for location in analysis.type_definitions.values_mut().chain(analysis.type_usages.values_mut()).flatten() {
+        location.file = path.to_string();
+    }
+    for call_site in &mut analysis.call_sites {
+        call_site.file = path.to_string();
+    }
+    analysis
+}
+
+impl Default for AstCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-file analyses from the last `index_workspace` run, kept so `reindex_changed` only
+/// has to reanalyze the files that changed.
+#[derive(Clone, Debug)]
+pub struct WorkspaceIndex {
+    pub analyses: BTreeMap<String, FileAnalysis>,
+    pub options: AnalysisOptions,
+    pub thresholds: Thresholds,
+    pub max_file_bytes: u64,
+    /// Files over `max_file_bytes`, with the reason they were skipped.
+    pub skipped_files: BTreeMap<String, String>,
+    pub file_filter: FileFilter,
+    /// Symbol kinds the graphs are limited to, if any.
+    pub kinds: Option<HashSet<SymbolKind>>,
 }
 
 #[derive(Clone)]
 pub struct MyServer {
     pub cache: AstCache,
+    pub(crate) workspace_index: Arc<RwLock<Option<WorkspaceIndex>>>,
+    pub(crate) started_at: Instant,
+    pub(crate) tool_router: ToolRouter<MyServer>,
 }
 
 impl MyServer {
     pub fn new() -> Self {
+        Self::with_cache(AstCache::new())
+    }
+
+    /// Creates a server on top of an existing cache, e.g. one using a custom `CacheBackend`.
+    pub fn with_cache(cache: AstCache) -> Self {
         Self {
-            cache: AstCache::new(),
+            cache,
+            workspace_index: Arc::new(RwLock::new(None)),
+            started_at: Instant::now(),
+            tool_router: Self::tool_router(),
         }
     }
 }
 
+impl Default for MyServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// This server's name and version; `Implementation::from_build_env` alone would report rmcp's.
+pub(crate) fn server_implementation() -> Implementation {
+    Implementation {
+        name: env!("CARGO_PKG_NAME").to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        ..Implementation::from_build_env()
+    }
+}
+
+/// Arguments that name what a tool works on, logged with each call.
+const TARGET_ARGUMENTS: [&str; 4] = ["path", "root", "manifest_dir", "manifest_path"];
+
+// Written out instead of using `#[tool_handler]` so every tool call is logged in one place
 impl ServerHandler for MyServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
@@ -54,8 +445,40 @@ impl ServerHandler for MyServer {
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
                 .build(),
-            server_info: Implementation::from_build_env(),
+            server_info: server_implementation(),
             instructions: Some("This server provides Rust code analysis tools.".to_string()),
         }
     }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let tool = request.name.clone();
+        let target = request.arguments.as_ref().and_then(|arguments| {
+            TARGET_ARGUMENTS.iter().find_map(|key| arguments.get(*key)?.as_str().map(str::to_string))
+        });
+        tracing::debug!(tool = %tool, arguments = ?request.arguments, "tool call started");
+
+        let started = Instant::now();
+        let result = self.tool_router.call(ToolCallContext::new(self, request, context)).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(result) => {
+                let result_bytes: usize = result.content.iter().filter_map(|content| content.as_text()).map(|text| text.text.len()).sum();
+                tracing::info!(tool = %tool, target, elapsed_ms, result_bytes, "tool call finished");
+            }
+            Err(error) => tracing::warn!(tool = %tool, target, elapsed_ms, error = %error.message, "tool call failed"),
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
 }
\ No newline at end of file
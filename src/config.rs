@@ -0,0 +1,33 @@
+use serde::Deserialize;
+use std::path::Path;
+use crate::models::ThresholdOverrides;
+
+/// Name of the optional per-workspace config file, looked up in the indexed root.
+pub const CONFIG_FILE_NAME: &str = "ruststudio.toml";
+
+/// Contents of `ruststudio.toml`:
+///
+/// ```toml
+/// [thresholds]
+/// max_function_lines = 80
+/// max_params = 7
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub thresholds: ThresholdOverrides,
+}
+
+impl Config {
+    /// Reads `ruststudio.toml` from `dir`. A missing file is `Ok(None)`; an unreadable or
+    /// invalid one is an error, so a typo doesn't silently fall back to the defaults.
+    pub fn load(dir: &Path) -> Result<Option<Self>, String> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        toml::from_str(&text).map(Some).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use similar::TextDiff;
+use crate::line_index::LineIndex;
+use crate::models::{PositionOptions, TextEdit, WorkspaceEdit};
+
+/// Applies `edits` to `source`. Ranges are resolved through their byte offsets when present,
+/// else through their positions encoded per `options`. Overlapping edits are an error.
+pub fn apply_edits(source: &str, edits: &[TextEdit], options: PositionOptions) -> Result<String, String> {
+    let line_index = LineIndex::new(source, options);
+    let mut spans: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .map(|edit| {
+            let start = edit.range.start_byte.unwrap_or_else(|| line_index.position_offset(&edit.range.start));
+            let end = edit.range.end_byte.unwrap_or_else(|| line_index.position_offset(&edit.range.end));
+            (start.min(source.len()), end.clamp(start, source.len()), edit.new_text.as_str())
+        })
+        .collect();
+    spans.sort_by_key(|&(start, end, _)| (start, end));
+
+    let mut out = String::with_capacity(source.len());
+    let mut copied = 0;
+    for (start, end, new_text) in spans {
+        if start < copied {
+            return Err(format!("Overlapping edits at byte {}", start));
+        }
+        if !source.is_char_boundary(start) || !source.is_char_boundary(end) {
+            return Err(format!("Edit at bytes {}..{} splits a character", start, end));
+        }
+        out.push_str(&source[copied..start]);
+        out.push_str(new_text);
+        copied = end;
+    }
+    out.push_str(&source[copied..]);
+    Ok(out)
+}
+
+/// Unified diff of one file with three lines of context, empty when nothing changed.
+pub fn unified_diff(file: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(file, file)
+        .to_string()
+}
+
+/// Unified diff of every file `edit` changes, in path order, computed from each file's current
+/// text in `sources`. Nothing is written anywhere.
+pub fn preview_diff(edit: &WorkspaceEdit, sources: &HashMap<String, String>, options: PositionOptions) -> Result<String, String> {
+    let mut files: Vec<&String> = edit.changes.keys().collect();
+    files.sort();
+
+    let mut diff = String::new();
+    for file in files {
+        let old = sources.get(file).ok_or_else(|| format!("No source for {}", file))?;
+        let new = apply_edits(old, &edit.changes[file], options)?;
+        diff.push_str(&unified_diff(file, old, &new));
+    }
+    Ok(diff)
+}
@@ -0,0 +1,123 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Finds the strongly connected components of a directed graph using Tarjan's algorithm.
+///
+/// Nodes that only appear as edge targets are included. The traversal is iterative so deep
+/// graphs can't overflow the stack. Each component is sorted, and components are returned
+/// in sorted order, so the output is deterministic.
+pub fn strongly_connected_components(graph: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    let nodes: Vec<&String> = graph
+        .iter()
+        .flat_map(|(node, targets)| std::iter::once(node).chain(targets))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let ids: HashMap<&String, usize> = nodes.iter().enumerate().map(|(id, node)| (*node, id)).collect();
+    let edges: Vec<Vec<usize>> = nodes
+        .iter()
+        .map(|node| graph.get(*node).map(|targets| targets.iter().map(|t| ids[t]).collect()).unwrap_or_default())
+        .collect();
+
+    let mut index: Vec<Option<usize>> = vec![None; nodes.len()];
+    let mut lowlink = vec![0; nodes.len()];
+    let mut on_stack = vec![false; nodes.len()];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut components = Vec::new();
+
+    for root in 0..nodes.len() {
+        if index[root].is_some() { continue; }
+
+        // Each frame is (node, position of the next edge to explore)
+        let mut work = vec![(root, 0)];
+        while let Some(&(v, edge)) = work.last() {
+            if edge == 0 && index[v].is_none() {
+                index[v] = Some(next_index);
+                lowlink[v] = next_index;
+                next_index += 1;
+                stack.push(v);
+                on_stack[v] = true;
+            }
+
+            if let Some(&w) = edges[v].get(edge) {
+                work.last_mut().unwrap().1 += 1;
+                match index[w] {
+                    None => work.push((w, 0)),
+                    Some(w_index) if on_stack[w] => lowlink[v] = lowlink[v].min(w_index),
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+            if Some(lowlink[v]) == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(nodes[w].clone());
+                    if w == v { break; }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+
+    components.sort();
+    components
+}
+
+/// Orders the nodes so every node comes after all of its targets, i.e. dependencies first.
+///
+/// Ties are broken by name, so the order is deterministic. If the graph has a cycle, no such
+/// order exists and one cycle is returned instead, as the nodes along it in edge order
+/// starting from the smallest.
+pub fn topological_sort(graph: &HashMap<String, Vec<String>>) -> Result<Vec<String>, Vec<String>> {
+    let nodes: BTreeSet<&String> = graph.iter().flat_map(|(node, targets)| std::iter::once(node).chain(targets)).collect();
+    let mut pending: HashMap<&String, usize> = nodes
+        .iter()
+        .map(|node| (*node, graph.get(*node).map(|targets| targets.iter().collect::<BTreeSet<_>>().len()).unwrap_or(0)))
+        .collect();
+    let mut dependents: HashMap<&String, BTreeSet<&String>> = HashMap::new();
+    for (node, targets) in graph {
+        for target in targets {
+            dependents.entry(target).or_default().insert(node);
+        }
+    }
+
+    let mut ready: BTreeSet<&String> = pending.iter().filter(|(_, count)| **count == 0).map(|(node, _)| *node).collect();
+    let mut order = Vec::new();
+    while let Some(node) = ready.pop_first() {
+        order.push(node.clone());
+        for dependent in dependents.get(node).into_iter().flatten() {
+            let count = pending.get_mut(dependent).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+    if order.len() == nodes.len() {
+        return Ok(order);
+    }
+
+    // Every node left over is on or behind a cycle; following the smallest remaining target
+    // from any of them must come back around to a node already seen
+    let remaining: BTreeSet<&String> = pending.iter().filter(|(_, count)| **count > 0).map(|(node, _)| *node).collect();
+    let mut walk: Vec<&String> = vec![remaining.first().unwrap()];
+    loop {
+        let current = walk.last().unwrap();
+        let next = graph[*current].iter().filter(|target| remaining.contains(target)).min().unwrap();
+        if let Some(start) = walk.iter().position(|node| *node == next) {
+            let mut cycle: Vec<String> = walk[start..].iter().map(|node| node.to_string()).collect();
+            // Start from the smallest node so the same cycle always reads the same way
+            let smallest = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+            cycle.rotate_left(smallest);
+            return Err(cycle);
+        }
+        walk.push(next);
+    }
+}
@@ -0,0 +1,3647 @@
+pub mod models;
+pub mod line_index;
+pub mod edits;
+pub mod visitors;
+pub mod analysis;
+pub mod cache;
+pub mod config;
+pub mod manifest;
+pub mod graph;
+pub mod modules;
+pub mod tools;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+
+pub use analysis::{analyze_source, analyze_source_with_options, AnalysisOptions};
+pub use cache::{AstCache, CacheBackend, CacheCapacity, InMemoryBackend, LruBackend, MyServer};
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::MyServer;
+    use tempfile::{NamedTempFile, TempDir};
+    use std::io::Write;
+    use rmcp::handler::server::wrapper::Parameters;
+    use crate::models::*;
+
+    #[tokio::test]
+    async fn test_check_file_valid() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let valid_code = r#"fn main() {
+    println!("Hello, world!");
+}"#;
+        temp_file.write_all(valid_code.as_bytes()).unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+
+        let server = MyServer::new();
+        let params = Parameters(CheckFileParams { path: Some(path), ..Default::default() });
+        let result = server.check_file(params).await.unwrap();
+
+        assert_eq!(result.content.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_check_file_invalid() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let invalid_code = r#"fn main() {
+    println!("Hello, world!" // missing )
+}"#;
+        temp_file.write_all(invalid_code.as_bytes()).unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+
+        let server = MyServer::new();
+        let params = Parameters(CheckFileParams { path: Some(path), ..Default::default() });
+        let result = server.check_file(params).await.unwrap();
+
+        assert_eq!(result.content.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_goto_definition() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let code = r#"fn foo() {
+    println!("foo");
+}
+
+fn main() {
+    foo();
+}"#;
+        temp_file.write_all(code.as_bytes()).unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+
+        let server = MyServer::new();
+        // First, check_file to cache the code
+        let params_check = Parameters(CheckFileParams { path: Some(path.clone()), ..Default::default() });
+        server.check_file(params_check).await.unwrap();
+
+        // Now, goto_definition for "foo"
+        let params_goto = Parameters(GotoDefinitionParams { name: "foo".to_string(), ..Default::default() });
+        let result = server.goto_definition(params_goto).await.unwrap();
+
+        assert_eq!(result.content.len(), 1);
+        // Should have found the definition
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir_path = temp_dir.path().to_string_lossy().to_string();
+
+        // Create some Rust files in the temp dir
+        let file1_path = temp_dir.path().join("lib.rs");
+        let mut file1 = std::fs::File::create(&file1_path).unwrap();
+        file1.write_all(b"fn foo() {}\n").unwrap();
+
+        let file2_path = temp_dir.path().join("main.rs");
+        let mut file2 = std::fs::File::create(&file2_path).unwrap();
+        file2.write_all(b"fn main() { foo(); }\n").unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams { root: Some(dir_path), ..Default::default() });
+        let result = server.index_workspace(params).await.unwrap();
+
+        assert_eq!(result.content.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_reads_concurrently() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..40 {
+            std::fs::write(temp_dir.path().join(format!("m{}.rs", i)), format!("pub fn f{}() {{}}\n", i)).unwrap();
+        }
+        let cached = temp_dir.path().join("m0.rs").to_string_lossy().to_string();
+
+        let server = MyServer::new();
+        // A cached file is indexed from the cache, not from disk
+        server.cache.insert(cached.clone(), "pub fn from_cache() {}\n".to_string()).await;
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_string_lossy().to_string()), ..Default::default() });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        assert_eq!(server.cache.get_all().await.len(), 40);
+        let names: Vec<&str> = graphs.function_info.iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(names.len(), 40);
+        assert!(names.contains(&"from_cache"));
+        assert!(!names.contains(&"f0"));
+        assert!(names.contains(&"f39"));
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_shares_identical_analyses() {
+        let temp_dir = TempDir::new().unwrap();
+        let generated = "pub struct Message { pub id: u32 }\npub fn decode() -> Message { Message { id: 0 } }\n";
+        std::fs::write(temp_dir.path().join("a.rs"), generated).unwrap();
+        std::fs::write(temp_dir.path().join("b.rs"), generated).unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "mod a;\nmod b;\n").unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        std::fs::write(temp_dir.path().join("sub").join("lib.rs"), "mod a;\nmod b;\n").unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_string_lossy().to_string()), ..Default::default() });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // a.rs and b.rs parse once; the lib.rs files declare modules, so they aren't shared
+        assert_eq!(server.cache.shared.read().await.analyses.len(), 1);
+        let mut files: Vec<&str> = graphs.function_info.iter().map(|info| info.file.as_str()).collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("a.rs") && files[1].ends_with("b.rs"));
+        let mut struct_files: Vec<&str> = graphs.struct_info.iter().map(|info| info.file.as_str()).collect();
+        struct_files.sort();
+        assert!(struct_files[0].ends_with("a.rs") && struct_files[1].ends_with("b.rs"));
+
+        let index = server.workspace_index.read().await;
+        let nested = index.as_ref().unwrap().analyses.iter().find(|(file, _)| file.contains("sub")).unwrap().1;
+        assert!(nested.mod_declarations[0].candidates[0].contains("sub"));
+        drop(index);
+
+        // An analysis goes once no cached file has its content
+        let a = temp_dir.path().join("a.rs").to_string_lossy().to_string();
+        let b = temp_dir.path().join("b.rs").to_string_lossy().to_string();
+        server.cache.insert(a, "pub fn edited() {}\n".to_string()).await;
+        assert_eq!(server.cache.shared.read().await.analyses.len(), 1);
+        server.cache.remove(&b).await;
+        assert!(server.cache.shared.read().await.analyses.is_empty());
+
+        // Evicting a file from a bounded cache drops its analysis too
+        let cache = crate::cache::AstCache::with_capacity(crate::cache::CacheCapacity::Entries(1));
+        cache.insert("x.rs".to_string(), generated.to_string()).await;
+        cache.analyze("x.rs", generated, crate::AnalysisOptions::default()).await.unwrap();
+        assert_eq!(cache.shared.read().await.analyses.len(), 1);
+        cache.insert("y.rs".to_string(), "fn y() {}".to_string()).await;
+        assert!(cache.shared.read().await.analyses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir_path = temp_dir.path().to_string_lossy().to_string();
+
+        // Create Rust files
+        let file1_path = temp_dir.path().join("lib.rs");
+        let mut file1 = std::fs::File::create(&file1_path).unwrap();
+        file1.write_all(b"fn foo() {}\n").unwrap();
+
+        let file2_path = temp_dir.path().join("main.rs");
+        let mut file2 = std::fs::File::create(&file2_path).unwrap();
+        file2.write_all(b"fn main() { foo(); }\n").unwrap();
+
+        let server = MyServer::new();
+        // Index first
+        let params_index = Parameters(IndexWorkspaceParams { root: Some(dir_path), ..Default::default() });
+        server.index_workspace(params_index).await.unwrap();
+
+        // Now find references for "foo"
+        let params_find = Parameters(FindReferencesParams { name: "foo".to_string(), ..Default::default() });
+        let result = server.find_references(params_find).await.unwrap();
+
+        assert_eq!(result.content.len(), 1);
+        // Should have found references
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_include_exclude() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir_path = temp_dir.path().to_string_lossy().to_string();
+
+        std::fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("tests")).unwrap();
+        std::fs::write(temp_dir.path().join("src/lib.rs"), "pub fn in_src() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("src/generated.rs"), "pub fn in_generated() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("tests/it.rs"), "pub fn in_tests() {}\n").unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(dir_path),
+            include: Some(vec!["src/**".to_string()]),
+            exclude: Some(vec!["**/generated.rs".to_string()]),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+
+        assert!(text.contains("in_src"));
+        assert!(!text.contains("in_generated"));
+        assert!(!text.contains("in_tests"));
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_multiple_roots_and_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let other_dir = TempDir::new().unwrap();
+        let src_dir = temp_dir.path().join("src");
+        std::fs::create_dir_all(&src_dir).unwrap();
+        std::fs::write(src_dir.join("lib.rs"), "pub fn shared() {}\n").unwrap();
+        std::fs::write(other_dir.path().join("extra.rs"), "pub fn extra() {}\n").unwrap();
+        let loose_file = other_dir.path().join("loose.rs");
+        std::fs::write(&loose_file, "pub fn loose() {}\n").unwrap();
+
+        let server = MyServer::new();
+        // `src` is reachable from both roots, so `shared` must only be counted once
+        let params = Parameters(IndexWorkspaceParams {
+            roots: Some(vec![
+                temp_dir.path().to_string_lossy().to_string(),
+                src_dir.to_string_lossy().to_string(),
+            ]),
+            files: Some(vec![loose_file.to_string_lossy().to_string()]),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+
+        assert_eq!(text.matches("\"name\":\"shared\"").count(), 1);
+        assert!(text.contains("loose"));
+        assert!(!text.contains("extra"));
+    }
+
+    #[tokio::test]
+    async fn test_check_file_inline_source() {
+        let server = MyServer::new();
+        let params = Parameters(CheckFileParams {
+            path: Some("untitled:buffer.rs".to_string()),
+            source: Some("fn buffered() {}\n".to_string()),
+            ..Default::default()
+        });
+        let result = server.check_file(params).await.unwrap();
+        assert_eq!(result.content[0].as_text().unwrap().text, "[]");
+
+        // The buffer is cached under its virtual path, so navigation can see it
+        let params_goto = Parameters(GotoDefinitionParams { name: "buffered".to_string(), ..Default::default() });
+        let result = server.goto_definition(params_goto).await.unwrap();
+        assert!(result.content[0].as_text().unwrap().text.contains("untitled:buffer.rs"));
+
+        let params = Parameters(CheckFileParams { source: Some("fn broken( {}".to_string()), ..Default::default() });
+        let result = server.check_file(params).await.unwrap();
+        assert!(result.content[0].as_text().unwrap().text.contains("\"severity\":\"error\""));
+    }
+
+    #[tokio::test]
+    async fn test_check_file_multiple_errors() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        let invalid_code = r#"fn first() {
+    let x = ;
+}
+
+fn valid() {}
+
+const LIMIT: u8 = if cfg!(test) { 1 } else { 2 };
+
+fn second() {
+    let y = 1 + ;
+}"#;
+        temp_file.write_all(invalid_code.as_bytes()).unwrap();
+        let path = temp_file.path().to_string_lossy().to_string();
+
+        let server = MyServer::new();
+        let params = Parameters(CheckFileParams { path: Some(path), ..Default::default() });
+        let result = server.check_file(params).await.unwrap();
+        let diagnostics: Vec<Diagnostic> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+        assert_eq!(diagnostics[0].range.start.line, 2);
+        assert_eq!(diagnostics[1].range.start.line, 10);
+    }
+
+    #[test]
+    fn test_severity_serde() {
+        assert_eq!(serde_json::to_string(&Severity::Warning).unwrap(), "\"warning\"");
+        assert_eq!(serde_json::from_str::<Severity>("\"error\"").unwrap(), Severity::Error);
+        assert_eq!(serde_json::from_str::<Severity>("\"warn\"").unwrap(), Severity::Warning);
+        assert!(serde_json::from_str::<Severity>("\"eror\"").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_goto_definition_byte_offsets() {
+        let code = "// é\nfn target() {}\n";
+        let server = MyServer::new();
+        server.cache.insert("offsets.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(GotoDefinitionParams {
+            name: "target".to_string(),
+            position_options: PositionOptions { byte_offsets: true, ..Default::default() },
+            ..Default::default()
+        });
+        let result = server.goto_definition(params).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        let range = &symbols[0].range;
+        assert_eq!(range.start_byte, Some(code.find("target").unwrap()));
+        assert_eq!(range.end_byte, Some(code.find("target").unwrap() + "target".len()));
+
+        // Offsets are left out unless requested
+        let params = Parameters(GotoDefinitionParams { name: "target".to_string(), ..Default::default() });
+        let result = server.goto_definition(params).await.unwrap();
+        assert!(!result.content[0].as_text().unwrap().text.contains("start_byte"));
+    }
+
+    #[tokio::test]
+    async fn test_find_references_utf16_columns() {
+        let code = "fn foo() {}\nfn main() { let s = \"😀\"; foo(); }\n";
+        let server = MyServer::new();
+        server.cache.insert("emoji.rs".to_string(), code.to_string()).await;
+
+        let find_columns = |encoding| {
+            let server = server.clone();
+            async move {
+                let params = Parameters(FindReferencesParams {
+                    name: "foo".to_string(),
+                    position_options: PositionOptions { position_encoding: encoding, ..Default::default() },
+                    ..Default::default()
+                });
+                let result = server.find_references(params).await.unwrap();
+                let refs: Vec<ReferenceLocation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+                refs.iter().filter(|r| r.range.start.line == 2).map(|r| r.range.start.character).next().unwrap()
+            }
+        };
+
+        // The emoji is one char, two UTF-16 code units, and four UTF-8 bytes
+        assert_eq!(find_columns(PositionEncoding::Utf32).await, 25);
+        assert_eq!(find_columns(PositionEncoding::Utf16).await, 26);
+        assert_eq!(find_columns(PositionEncoding::Utf8).await, 28);
+    }
+
+    #[tokio::test]
+    async fn test_position_line_conventions() {
+        let code = "struct Marker;\n\nfn    target() {}\n";
+        let server = MyServer::new();
+        server.cache.insert("lines.rs".to_string(), code.to_string()).await;
+
+        let goto = |zero_based_lines| {
+            let server = server.clone();
+            async move {
+                let params = Parameters(GotoDefinitionParams {
+                    name: "target".to_string(),
+                    position_options: PositionOptions { zero_based_lines, ..Default::default() },
+                    ..Default::default()
+                });
+                let result = server.goto_definition(params).await.unwrap();
+                let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+                symbols[0].range.clone()
+            }
+        };
+
+        let range = goto(false).await;
+        assert_eq!((range.start.line, range.start.character), (3, 6));
+        assert_eq!((range.end.line, range.end.character), (3, 12));
+
+        let range = goto(true).await;
+        assert_eq!((range.start.line, range.start.character), (2, 6));
+        assert_eq!((range.end.line, range.end.character), (2, 12));
+    }
+
+    #[tokio::test]
+    async fn test_find_references_deduplicates_type_paths() {
+        let server = MyServer::new();
+        server.cache.insert("point.rs".to_string(), "struct Point;\nfn take(p: Point) {}\n".to_string()).await;
+
+        let params = Parameters(FindReferencesParams { name: "Point".to_string(), ..Default::default() });
+        let result = server.find_references(params).await.unwrap();
+        let refs: Vec<ReferenceLocation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // One hit for the definition and exactly one for the type annotation
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs.iter().filter(|r| r.range.start.line == 2).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_type_usage_counts() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"struct Point { x: i32 }
+
+fn mirror(p: Point) -> Point { p }
+
+fn origin() -> Point { Point { x: 0 } }
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // Two in `mirror`'s signature, one return type and one literal in `origin`; the definition is excluded
+        assert_eq!(graphs.type_usage_graph.usages["Point"].len(), 4);
+        assert_eq!(graphs.type_usage_graph.usages["i32"].len(), 1);
+        assert_eq!(graphs.type_usage_graph.definitions["Point"].len(), 1);
+        assert_eq!(graphs.type_usage_graph.definitions["Point"][0].range.start.line, 1);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_qualified_calls() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"mod util;
+
+mod a {
+    pub fn foo() {}
+}
+
+mod b {
+    pub fn foo() { helper(); }
+    fn helper() {}
+}
+
+struct Counter;
+
+impl Counter {
+    fn tick(&self) { self.reset(); Self::log(); }
+    fn reset(&self) {}
+    fn log() {}
+}
+
+fn main() {
+    a::foo();
+    b::foo();
+    util::foo();
+}
+"#;
+        std::fs::write(temp_dir.path().join("main.rs"), code).unwrap();
+        // Same names as in `b`, but in another file
+        std::fs::write(temp_dir.path().join("util.rs"), "pub fn foo() { helper(); }\nfn helper() { log(); }\nfn log() {}\n").unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let calls = &graphs.call_graph.calls;
+
+        assert_eq!(calls["main"], vec!["a::foo", "b::foo", "util::foo"]);
+        assert!(calls["a::foo"].is_empty());
+        assert_eq!(calls["b::foo"], vec!["helper"]);
+        assert!(calls["b::helper"].is_empty());
+        assert_eq!(calls["util::foo"], vec!["helper"]);
+        assert_eq!(calls["util::helper"], vec!["log"]);
+        assert!(!calls.contains_key("helper"));
+        assert_eq!(calls["Counter::tick"], vec!["Counter::reset", "Counter::log"]);
+        let foos: Vec<&str> = graphs.function_info.iter().filter(|f| f.name == "foo").map(|f| f.qualified_name.as_str()).collect();
+        assert_eq!(foos, vec!["a::foo", "b::foo", "util::foo"]);
+        assert!(!graphs.unused_functions.contains(&"helper".to_string()));
+        let util_helper = graphs.function_info.iter().find(|f| f.qualified_name == "util::helper").unwrap();
+        assert_eq!((util_helper.fan_in, util_helper.fan_out), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_closure_calls() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn helper(x: i32) -> i32 { x }
+
+fn outer(values: Vec<i32>) -> Vec<i32> {
+    let doubled: Vec<i32> = values.into_iter().map(|x| helper(x)).collect();
+    doubled.iter().map(|x| { fn nested() {} nested(); *x }).collect()
+}
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        let outer_calls = &graphs.call_graph.calls["outer"];
+        assert!(outer_calls.contains(&"helper".to_string()));
+        assert!(outer_calls.contains(&"nested".to_string()));
+        assert!(!graphs.unused_functions.contains(&"helper".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_recursion() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn factorial(n: u64) -> u64 { if n == 0 { 1 } else { n * factorial(n - 1) } }
+
+fn is_even(n: u32) -> bool { n == 0 || is_odd(n - 1) }
+fn is_odd(n: u32) -> bool { n != 0 && is_even(n - 1) }
+
+fn main() { factorial(5); is_even(4); }
+"#;
+        std::fs::write(temp_dir.path().join("main.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        assert_eq!(graphs.recursive_functions, vec![vec!["factorial".to_string()], vec!["is_even".to_string(), "is_odd".to_string()]]);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_cognitive_complexity() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn nested(a: bool, b: bool, c: bool) {
+    if a {
+        for _ in 0..3 {
+            if b && c || a {
+                println!("deep");
+            }
+        }
+    } else if b {
+        println!("b");
+    } else {
+        println!("neither");
+    }
+}
+
+fn flat(a: bool, b: bool, c: bool) {
+    if a { println!("a"); }
+    if b { println!("b"); }
+    if c { println!("c"); }
+}
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let cognitive = |name: &str| graphs.function_info.iter().find(|f| f.name == name).unwrap().cognitive_complexity;
+
+        // if (1) + for (2) + nested if (3) + `&&`/`||` sequences (2) + else if (1) + else (1)
+        assert_eq!(cognitive("nested"), 10);
+        assert_eq!(cognitive("flat"), 3);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_nesting_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn deep(v: Vec<Option<i32>>) {
+    for item in v {
+        if let Some(x) = item {
+            match x {
+                0 => loop {
+                    while x > 0 { break; }
+                    break;
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+fn flat(a: bool) {
+    if a { println!("1"); }
+    if a { println!("2"); } else if !a { println!("3"); } else { println!("4"); }
+    if a { println!("5"); }
+}
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let depth = |name: &str| graphs.function_info.iter().find(|f| f.name == name).unwrap().max_nesting_depth;
+
+        assert_eq!(depth("deep"), 5);
+        assert_eq!(depth("flat"), 1);
+        assert!(graphs.refactoring_suggestions.iter().any(|s| s.target == "deep" && s.kind == RefactoringKind::DeepNesting));
+        assert!(!graphs.refactoring_suggestions.iter().any(|s| s.target == "flat"));
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_cyclomatic_complexity() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn parse(input: &str) -> Result<u8, std::num::ParseIntError> {
+    let trimmed = input.trim();
+    let value: u8 = trimmed.parse()?;
+    let doubled = value.to_string().parse::<u8>()?;
+    let Some(first) = trimmed.chars().next() else { return Ok(0) };
+    match first {
+        '0' => Ok(0),
+        '1' => Ok(doubled),
+        '2' => Ok(value),
+        _ => Ok(trimmed.parse()?),
+    }
+}
+
+fn simple() -> u8 { 1 }
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let complexity = |name: &str| graphs.function_info.iter().find(|f| f.name == name).unwrap().complexity;
+
+        // base (1) + three `?` (3) + let-else (1) + match (1) + three extra arms (3)
+        assert_eq!(complexity("parse"), 9);
+        assert_eq!(complexity("simple"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_find_panics() {
+        let code = r#"fn risky(v: Option<i32>, r: Result<i32, String>) -> i32 {
+    let a = v.unwrap();
+    let b = r.expect("must be ok");
+    if a < 0 { panic!("negative"); }
+    match b {
+        0 => unreachable!(),
+        _ => todo!(),
+    }
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("risky.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindPanicsParams::default());
+        let result = server.find_panics(params).await.unwrap();
+        let sites: Vec<PanicSite> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let mut kinds: Vec<PanicKind> = sites.iter().map(|site| site.kind).collect();
+        kinds.sort_by_key(|kind| format!("{:?}", kind));
+        assert_eq!(kinds, vec![PanicKind::Expect, PanicKind::Panic, PanicKind::Todo, PanicKind::Unreachable, PanicKind::Unwrap]);
+
+        let params = Parameters(FindPanicsParams { kinds: Some(vec![PanicKind::Unwrap]), ..Default::default() });
+        let result = server.find_panics(params).await.unwrap();
+        let sites: Vec<PanicSite> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(sites.len(), 1);
+        assert_eq!((sites[0].range.start.line, sites[0].range.start.character), (2, 14));
+    }
+
+    #[test]
+    fn test_analyze_source() {
+        let code = r#"use std::collections::HashMap;
+
+struct Cache { map: HashMap<String, String> }
+
+enum State { Empty, Full }
+
+fn lookup(cache: &Cache, key: &str) -> Option<String> { normalize(key); cache.map.get(key).cloned() }
+
+fn normalize(key: &str) -> String { key.to_lowercase() }
+"#;
+        let analysis = crate::analyze_source("cache.rs", code).unwrap();
+
+        assert_eq!(analysis.file, "cache.rs");
+        assert_eq!(analysis.symbols.len(), 4);
+        assert_eq!(analysis.functions.len(), 2);
+        assert_eq!(analysis.structs[0].field_count, 1);
+        assert_eq!(analysis.enums[0].variant_count, 2);
+        assert!(analysis.calls["lookup"].contains(&"normalize".to_string()));
+        assert_eq!(analysis.type_definitions["Cache"].len(), 1);
+        assert_eq!(analysis.type_usages["Cache"].len(), 1);
+        assert_eq!(analysis.module_dependencies, vec!["std", "collections", "HashMap"]);
+
+        assert!(crate::analyze_source("broken.rs", "fn broken( {}").is_err());
+    }
+
+    #[test]
+    fn test_analyze_source_full_ranges() {
+        let code = r#"/// Adds one.
+#[inline]
+pub fn increment(
+    value: u32,
+) -> u32 {
+    value + 1
+}
+"#;
+        let analysis = crate::analyze_source("math.rs", code).unwrap();
+        let at = |range: &Range| ((range.start.line, range.start.character), (range.end.line, range.end.character));
+
+        // `range` stays on the name for navigation, `full_range` covers docs, attributes, and body
+        let symbol = &analysis.symbols[0];
+        assert_eq!(at(&symbol.range), ((3, 7), (3, 16)));
+        assert_eq!(at(&symbol.full_range), ((1, 0), (7, 1)));
+
+        let function = &analysis.functions[0];
+        assert_eq!(at(&function.range), ((3, 7), (3, 16)));
+        assert_eq!(at(&function.full_range), ((1, 0), (7, 1)));
+        assert_eq!(function.line_count, 5);
+    }
+
+    #[test]
+    fn test_analyze_source_return_types() {
+        let code = r#"
+fn unit() {}
+fn explicit_unit() -> () {}
+fn read(path: &str) -> std::io::Result<Vec<u8>> { std::fs::read(path) }
+fn parse(s: &str) -> Result<(u8, String), Box<dyn std::error::Error + Send>> { todo!() }
+fn first<'a>(items: &'a [&'a str]) -> Option<&'a str> { items.first().copied() }
+fn evens() -> impl Iterator<Item = u32> { (0..10).filter(|n| n % 2 == 0) }
+fn callback() -> Box<dyn Fn(u8) -> bool> { Box::new(|_| true) }
+fn bytes() -> [u8; 4] { [0; 4] }
+"#;
+        let analysis = crate::analyze_source("types.rs", code).unwrap();
+        let function = |name: &str| analysis.functions.iter().find(|func| func.name == name).unwrap();
+        let return_type = |name: &str| function(name).return_type.clone();
+
+        assert_eq!(return_type("unit"), None);
+        assert_eq!(return_type("explicit_unit"), None);
+        assert_eq!(return_type("read").as_deref(), Some("std::io::Result<Vec<u8>>"));
+        assert_eq!(return_type("parse").as_deref(), Some("Result<(u8, String), Box<dyn std::error::Error + Send>>"));
+        assert_eq!(return_type("first").as_deref(), Some("Option<&'a str>"));
+        assert_eq!(return_type("evens").as_deref(), Some("impl Iterator<Item = u32>"));
+        assert_eq!(return_type("callback").as_deref(), Some("Box<dyn Fn(u8) -> bool>"));
+        assert_eq!(return_type("bytes").as_deref(), Some("[u8; 4]"));
+
+        assert!(function("read").returns_result && function("parse").returns_result);
+        assert!(function("first").returns_option && !function("first").returns_result);
+        assert!(!function("evens").returns_result && !function("unit").returns_option);
+    }
+
+    #[tokio::test]
+    async fn test_custom_cache_backend() {
+        use crate::cache::{AstCache, BoxFuture, CacheBackend, InMemoryBackend};
+        use std::collections::HashMap;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        struct CountingBackend {
+            inner: InMemoryBackend,
+            inserts: Arc<AtomicUsize>,
+        }
+
+        impl CacheBackend for CountingBackend {
+            fn get<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>> {
+                self.inner.get(path)
+            }
+            fn insert(&self, path: String, code: String) -> BoxFuture<'_, ()> {
+                self.inserts.fetch_add(1, Ordering::SeqCst);
+                self.inner.insert(path, code)
+            }
+            fn get_all(&self) -> BoxFuture<'_, HashMap<String, String>> {
+                self.inner.get_all()
+            }
+            fn remove<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Option<String>> {
+                self.inner.remove(path)
+            }
+        }
+
+        let inserts = Arc::new(AtomicUsize::new(0));
+        let backend = CountingBackend { inner: InMemoryBackend::default(), inserts: inserts.clone() };
+        let server = MyServer::with_cache(AstCache::with_backend(backend));
+
+        let params = Parameters(CheckFileParams {
+            path: Some("virtual.rs".to_string()),
+            source: Some("fn cached() {}".to_string()),
+            ..Default::default()
+        });
+        server.check_file(params).await.unwrap();
+
+        assert_eq!(inserts.load(Ordering::SeqCst), 1);
+        assert_eq!(server.cache.get("virtual.rs").await.as_deref(), Some("fn cached() {}"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_remove() {
+        let cache = crate::cache::AstCache::new();
+        cache.insert("a.rs".to_string(), "fn a() {}".to_string()).await;
+
+        assert_eq!(cache.remove("a.rs").await.as_deref(), Some("fn a() {}"));
+        assert_eq!(cache.remove("a.rs").await, None);
+        assert_eq!(cache.get("a.rs").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_cache_lru_eviction() {
+        use crate::cache::{AstCache, CacheCapacity};
+
+        let cache = AstCache::with_capacity(CacheCapacity::Entries(2));
+        cache.insert("a.rs".to_string(), "fn a() {}".to_string()).await;
+        cache.insert("b.rs".to_string(), "fn b() {}".to_string()).await;
+        // Reading a.rs makes b.rs the least recently used
+        assert!(cache.get("a.rs").await.is_some());
+        cache.insert("c.rs".to_string(), "fn c() {}".to_string()).await;
+
+        assert_eq!(cache.len().await, 2);
+        assert!(cache.contains("a.rs").await && cache.contains("c.rs").await);
+        assert!(!cache.contains("b.rs").await);
+
+        let cache = AstCache::with_capacity(CacheCapacity::Bytes(20));
+        cache.insert("a.rs".to_string(), "fn a() {}".to_string()).await;
+        cache.insert("b.rs".to_string(), "fn b() {}".to_string()).await;
+        cache.insert("c.rs".to_string(), "fn c() {}".to_string()).await;
+        assert!(!cache.contains("a.rs").await);
+        assert_eq!(cache.get_all().await.len(), 2);
+
+        // Too large to fit at all: not cached, and nothing else is evicted for it
+        cache.insert("big.rs".to_string(), "fn big() { let x = 1; }".to_string()).await;
+        assert!(!cache.contains("big.rs").await);
+        assert_eq!(cache.len().await, 2);
+
+        // Replacing a file frees its old size
+        cache.insert("b.rs".to_string(), "fn b2() {}".to_string()).await;
+        assert!(cache.contains("c.rs").await);
+        assert_eq!(cache.remove("b.rs").await.as_deref(), Some("fn b2() {}"));
+        assert_eq!(cache.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_len_and_contains() {
+        let cache = crate::cache::AstCache::new();
+        assert_eq!(cache.len().await, 0);
+        assert!(cache.is_empty().await);
+
+        cache.insert("a.rs".to_string(), "fn a() {}".to_string()).await;
+        cache.insert("b.rs".to_string(), "fn b() {}".to_string()).await;
+        cache.insert("a.rs".to_string(), "fn a2() {}".to_string()).await;
+
+        assert_eq!(cache.len().await, 2);
+        assert!(cache.contains("a.rs").await);
+        assert!(!cache.contains("c.rs").await);
+    }
+
+    #[tokio::test]
+    async fn test_cache_clear() {
+        let cache = crate::cache::AstCache::new();
+        cache.insert("a.rs".to_string(), "fn a() {}".to_string()).await;
+        cache.insert("b.rs".to_string(), "fn b() {}".to_string()).await;
+
+        cache.clear().await;
+
+        assert!(cache.is_empty().await);
+        assert!(!cache.contains("a.rs").await);
+    }
+
+    #[tokio::test]
+    async fn test_cache_stats_and_clear_cache() {
+        let server = MyServer::new();
+        server.cache.insert("a.rs".to_string(), "fn a() {}".to_string()).await;
+        server.cache.insert("b.rs".to_string(), "fn bb() {}".to_string()).await;
+
+        let result = server.cache_stats().await.unwrap();
+        let stats: CacheStats = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(stats, CacheStats { file_count: 2, total_bytes: 19 });
+
+        let result = server.clear_cache().await.unwrap();
+        let evicted: CacheStats = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(evicted.file_count, 2);
+        assert!(server.cache.is_empty().await);
+
+        let result = server.cache_stats().await.unwrap();
+        let stats: CacheStats = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(stats, CacheStats { file_count: 0, total_bytes: 0 });
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_reports_progress() {
+        use rmcp::model::{CallToolRequest, CallToolRequestParam, ClientRequest, Meta, NumberOrString, ProgressNotificationParam, ProgressToken};
+        use rmcp::service::{NotificationContext, PeerRequestOptions};
+        use rmcp::{ClientHandler, RoleClient, ServiceExt};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct ProgressRecorder {
+            updates: Arc<Mutex<Vec<ProgressNotificationParam>>>,
+        }
+
+        impl ClientHandler for ProgressRecorder {
+            async fn on_progress(&self, params: ProgressNotificationParam, _context: NotificationContext<RoleClient>) {
+                self.updates.lock().unwrap().push(params);
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..3 {
+            std::fs::write(temp_dir.path().join(format!("m{}.rs", i)), "fn f() {}").unwrap();
+        }
+
+        let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(async move {
+            let service = MyServer::new().serve(server_io).await.unwrap();
+            service.waiting().await.unwrap();
+        });
+        let recorder = ProgressRecorder::default();
+        let client = recorder.clone().serve(client_io).await.unwrap();
+
+        let mut meta = Meta::new();
+        meta.set_progress_token(ProgressToken(NumberOrString::Number(7)));
+        let arguments = serde_json::json!({ "root": temp_dir.path().to_str().unwrap() });
+        let request = ClientRequest::CallToolRequest(CallToolRequest::new(CallToolRequestParam {
+            name: "index_workspace".into(),
+            arguments: arguments.as_object().cloned(),
+        }));
+        client
+            .send_request_with_option(request, PeerRequestOptions { timeout: None, meta: Some(meta) })
+            .await
+            .unwrap()
+            .await_response()
+            .await
+            .unwrap();
+
+        // Notifications are handled concurrently with the response, so give them a moment to land
+        for _ in 0..50 {
+            if recorder.updates.lock().unwrap().last().is_some_and(|update| update.progress == 3.0) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let updates: Vec<_> = recorder.updates.lock().unwrap().iter().map(|update| (update.progress, update.total)).collect();
+        assert_eq!(updates, vec![(0.0, Some(3.0)), (1.0, Some(3.0)), (2.0, Some(3.0)), (3.0, Some(3.0))]);
+
+        client.cancel().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_symbol_kinds_serialize_as_snake_case() {
+        let code = "fn f() {}\nstruct S;\nenum E { A }\ntrait T {}";
+        let analysis = crate::analysis::analyze_source("kinds.rs", code).unwrap();
+
+        let kinds: Vec<SymbolKind> = analysis.symbols.iter().map(|symbol| symbol.kind).collect();
+        assert_eq!(kinds, vec![SymbolKind::Fn, SymbolKind::Struct, SymbolKind::Enum, SymbolKind::Trait]);
+
+        let json = serde_json::to_value(&analysis.symbols).unwrap();
+        let names: Vec<&str> = json.as_array().unwrap().iter().map(|symbol| symbol["kind"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["fn", "struct", "enum", "trait"]);
+        assert_eq!(serde_json::to_value(SymbolKind::TypeAlias).unwrap(), "type_alias");
+    }
+
+    #[tokio::test]
+    async fn test_outputs_are_sorted_and_stable() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["c", "a", "d", "b"] {
+            std::fs::write(
+                temp_dir.path().join(format!("{}.rs", name)),
+                format!("fn helper() {{}}\nfn {}_main() {{ helper(); helper(); }}\nstruct S{};", name, name),
+            ).unwrap();
+        }
+        let root = temp_dir.path().to_str().unwrap().to_string();
+
+        let index = |server: MyServer| {
+            let root = root.clone();
+            async move {
+                let params = Parameters(IndexWorkspaceParams { root: Some(root), ..Default::default() });
+                server.index_workspace(params).await.unwrap().content[0].as_text().unwrap().text.clone()
+            }
+        };
+        let server = MyServer::new();
+        let first = index(server.clone()).await;
+        let second = index(MyServer::new()).await;
+        assert_eq!(first, second);
+
+        let graphs: WorkspaceGraphs = serde_json::from_str(&first).unwrap();
+        let functions: Vec<(String, usize)> = graphs.function_info.iter()
+            .map(|f| (f.file.rsplit('/').next().unwrap().to_string(), f.range.start.line))
+            .collect();
+        let mut sorted = functions.clone();
+        sorted.sort();
+        assert_eq!(functions, sorted);
+
+        let params = Parameters(FindReferencesParams { name: "helper".to_string(), ..Default::default() });
+        let result = server.find_references(params).await.unwrap();
+        let refs: Vec<ReferenceLocation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(refs.len(), 12);
+        assert!(refs.windows(2).all(|w| (&w[0].file, &w[0].range.start) <= (&w[1].file, &w[1].range.start)));
+
+        let params = Parameters(GotoDefinitionParams { name: "helper".to_string(), ..Default::default() });
+        let result = server.goto_definition(params).await.unwrap();
+        let defs: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let files: Vec<&str> = defs.iter().map(|d| d.file.rsplit('/').next().unwrap()).collect();
+        assert_eq!(files, vec!["a.rs", "b.rs", "c.rs", "d.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_raw_identifiers() {
+        let server = MyServer::new();
+        let code = "fn r#type() {}\nfn caller() { r#type(); }\nstruct r#match;";
+        server.cache.insert("raw.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(GotoDefinitionParams { name: "type".to_string(), ..Default::default() });
+        let result = server.goto_definition(params).await.unwrap();
+        let defs: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].name, "type");
+        assert_eq!(defs[0].range.start.line, 1);
+
+        let params = Parameters(GotoDefinitionParams { name: "r#match".to_string(), ..Default::default() });
+        let result = server.goto_definition(params).await.unwrap();
+        let defs: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].name, "match");
+
+        let params = Parameters(FindReferencesParams { name: "type".to_string(), ..Default::default() });
+        let result = server.find_references(params).await.unwrap();
+        let refs: Vec<ReferenceLocation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let lines: Vec<usize> = refs.iter().map(|r| r.range.start.line).collect();
+        assert_eq!(lines, vec![1, 2]);
+
+        let analysis = crate::analysis::analyze_source("raw.rs", code).unwrap();
+        assert_eq!(analysis.calls["caller"], vec!["type"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_docs() {
+        let server = MyServer::new();
+        let code = r#"
+/// Adds two numbers.
+///
+/// Returns their sum.
+#[inline]
+pub fn add(a: i32, b: i32) -> i32 { a + b }
+
+pub struct Point { x: i32 }
+"#;
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+        server.cache.insert("other.rs".to_string(), "#[doc = \"Adds things.\"]\nfn add() {}".to_string()).await;
+
+        let params = Parameters(GetDocsParams { name: "add".to_string() });
+        let result = server.get_docs(params).await.unwrap();
+        let markdown = &result.content[0].as_text().unwrap().text;
+        assert_eq!(
+            markdown,
+            "`add` (lib.rs:6)\n\n```rust\npub fn add(a: i32, b: i32) -> i32\n```\n\nAdds two numbers.\n\nReturns their sum.\n\n---\n\n`add` (other.rs:2)\n\n```rust\nfn add()\n```\n\nAdds things."
+        );
+
+        let params = Parameters(GetDocsParams { name: "Point".to_string() });
+        let result = server.get_docs(params).await.unwrap();
+        let markdown = &result.content[0].as_text().unwrap().text;
+        assert!(markdown.contains("```rust\npub struct Point\n```"));
+        assert!(markdown.ends_with("_No documentation._"));
+
+        let params = Parameters(GetDocsParams { name: "missing".to_string() });
+        let result = server.get_docs(params).await.unwrap();
+        assert!(result.content[0].as_text().unwrap().text.starts_with("No symbol named `missing`"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_api_docs() {
+        let server = MyServer::new();
+        server.cache.insert("/ws/src/lib.rs".to_string(), "/// Entry point.\npub fn run() {}\nfn helper() {}".to_string()).await;
+        server.cache.insert(
+            "/ws/src/net/mod.rs".to_string(),
+            "/// A client.\n/// More details.\npub struct Client {\n    addr: String,\n}\npub enum Mode { A }".to_string(),
+        ).await;
+
+        let params = Parameters(GenerateApiDocsParams::default());
+        let result = server.generate_api_docs(params).await.unwrap();
+        assert_eq!(
+            result.content[0].as_text().unwrap().text,
+            "# API Reference\n\n## `crate`\n\n- `pub fn run()` — Entry point.\n\n## `crate::net`\n\n- `pub struct Client` — A client.\n- `pub enum Mode`\n"
+        );
+
+        let params = Parameters(GenerateApiDocsParams { include_private: true });
+        let result = server.generate_api_docs(params).await.unwrap();
+        assert!(result.content[0].as_text().unwrap().text.contains("- `fn helper()`\n"));
+    }
+
+    #[tokio::test]
+    async fn test_find_callers() {
+        let server = MyServer::new();
+        let code = r#"
+fn parse() {}
+fn first() { parse(); }
+mod nested {
+    fn second() {
+        crate::parse();
+    }
+}
+struct Parser;
+impl Parser {
+    fn parse(&self) {}
+    fn run(&self) { self.parse(); }
+}
+fn unrelated() { other(); }
+"#;
+        server.cache.insert("callers.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindCallersParams { name: "parse".to_string(), ..Default::default() });
+        let result = server.find_callers(params).await.unwrap();
+        let callers: Vec<CallSite> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(&str, usize, usize)> = callers.iter()
+            .map(|site| (site.caller.as_str(), site.range.start.line, site.range.start.character))
+            .collect();
+        assert_eq!(found, vec![("first", 3, 13), ("nested::second", 6, 15), ("Parser::run", 12, 25)]);
+
+        let params = Parameters(FindCallersParams { name: "Parser::parse".to_string(), ..Default::default() });
+        let result = server.find_callers(params).await.unwrap();
+        let callers: Vec<CallSite> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(callers.len(), 1);
+        assert_eq!(callers[0].caller, "Parser::run");
+    }
+
+    #[tokio::test]
+    async fn test_circular_modules() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        std::fs::create_dir_all(src.join("net")).unwrap();
+        std::fs::write(src.join("lib.rs"), "mod a;\nmod b;\nmod net;\nuse std::fmt;").unwrap();
+        std::fs::write(src.join("a.rs"), "use crate::b::B;\npub struct A;").unwrap();
+        std::fs::write(src.join("b.rs"), "use super::a::{self, A};\npub struct B;").unwrap();
+        std::fs::write(src.join("net/mod.rs"), "use crate::a::A;\nuse serde::Serialize;").unwrap();
+
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_str().unwrap().to_string()), ..Default::default() });
+        let result = server_index(params).await;
+        assert_eq!(result.circular_modules, vec![vec!["crate::a".to_string(), "crate::b".to_string()]]);
+
+        std::fs::write(src.join("b.rs"), "pub struct B;").unwrap();
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_str().unwrap().to_string()), ..Default::default() });
+        assert!(server_index(params).await.circular_modules.is_empty());
+
+        async fn server_index(params: Parameters<IndexWorkspaceParams>) -> WorkspaceGraphs {
+            let result = MyServer::new().index_workspace(params).await.unwrap();
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_module_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        std::fs::create_dir_all(src.join("net/proto")).unwrap();
+        std::fs::write(src.join("lib.rs"), "mod net;\nuse std::fmt;").unwrap();
+        std::fs::write(src.join("net/mod.rs"), "mod client;\nmod proto;").unwrap();
+        std::fs::write(src.join("net/client.rs"), "use crate::net::proto::wire::Frame;").unwrap();
+        std::fs::write(src.join("net/proto/wire.rs"), "pub struct Frame;").unwrap();
+
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_str().unwrap().to_string()), ..Default::default() });
+        let result = MyServer::new().index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        fn flatten(nodes: &[ModuleNode], out: &mut Vec<(String, bool)>) {
+            for node in nodes {
+                out.push((node.path.clone(), node.file.is_some()));
+                flatten(&node.children, out);
+            }
+        }
+        let mut modules = Vec::new();
+        flatten(&graphs.module_tree, &mut modules);
+        let expected = [
+            ("crate", true),
+            ("crate::net", true),
+            ("crate::net::client", true),
+            ("crate::net::proto", false),
+            ("crate::net::proto::wire", true),
+        ];
+        assert_eq!(modules, expected.map(|(path, has_file)| (path.to_string(), has_file)));
+        assert!(graphs.module_tree[0].children[0].file.as_deref().unwrap().ends_with("mod.rs"));
+
+        let dependencies = &graphs.module_dependency_graph.dependencies;
+        assert_eq!(dependencies.keys().collect::<Vec<_>>(), vec!["crate", "crate::net::client"]);
+    }
+
+    #[tokio::test]
+    async fn test_module_tree_per_crate_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let core = temp_dir.path().join("core");
+        let helper = temp_dir.path().join("helper");
+        std::fs::create_dir_all(core.join("src")).unwrap();
+        std::fs::create_dir_all(helper.join("src")).unwrap();
+        std::fs::write(core.join("Cargo.toml"), "[package]\nname = \"app-core\"\nversion = \"0.1.0\"\n").unwrap();
+        std::fs::write(core.join("src/lib.rs"), "pub mod util;").unwrap();
+        std::fs::write(core.join("src/util.rs"), "pub fn helper() {}").unwrap();
+        std::fs::write(core.join("src/main.rs"), "mod cli;\nuse app_core::util::helper;\nuse crate::cli::Args;").unwrap();
+        std::fs::write(core.join("src/cli.rs"), "pub struct Args;").unwrap();
+        std::fs::write(helper.join("src/lib.rs"), "mod util;").unwrap();
+        std::fs::write(helper.join("src/util.rs"), "").unwrap();
+
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_str().unwrap().to_string()), ..Default::default() });
+        let result = MyServer::new().index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        fn flatten(nodes: &[ModuleNode], out: &mut Vec<String>) {
+            for node in nodes {
+                out.push(node.path.clone());
+                flatten(&node.children, out);
+            }
+        }
+        let mut modules = Vec::new();
+        flatten(&graphs.module_tree, &mut modules);
+        assert_eq!(modules, vec![
+            "app_core", "app_core::util", "app_core(bin)", "app_core(bin)::cli", "helper", "helper::util",
+        ]);
+
+        // The binary imports both from the library by name and from itself through `crate`
+        let known: std::collections::HashSet<String> = modules.into_iter().collect();
+        let resolve = |path| crate::modules::resolve_use_path("app_core(bin)", path, &known);
+        assert_eq!(resolve("app_core::util::helper").as_deref(), Some("app_core::util"));
+        assert_eq!(resolve("crate::cli::Args").as_deref(), Some("app_core(bin)::cli"));
+    }
+
+    #[tokio::test]
+    async fn test_mod_resolution_styles() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        for dir in ["nested", "flat", "custom", "outer"] {
+            std::fs::create_dir_all(src.join(dir)).unwrap();
+        }
+        let lib = r#"
+mod flat;
+mod nested;
+#[path = "custom/renamed.rs"]
+mod aliased;
+mod outer {
+    mod deep;
+}
+"#;
+        std::fs::write(src.join("lib.rs"), lib).unwrap();
+        std::fs::write(src.join("flat.rs"), "mod child;").unwrap();
+        std::fs::write(src.join("flat/child.rs"), "").unwrap();
+        std::fs::write(src.join("nested/mod.rs"), "").unwrap();
+        std::fs::write(src.join("custom/renamed.rs"), "").unwrap();
+        std::fs::write(src.join("outer/deep.rs"), "").unwrap();
+
+        let analysis = crate::analysis::analyze_source("src/lib.rs", lib).unwrap();
+        let candidates: Vec<(&str, Vec<&str>)> = analysis.mod_declarations.iter()
+            .map(|decl| (decl.module.as_str(), decl.candidates.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(candidates, vec![
+            ("flat", vec!["src/flat.rs", "src/flat/mod.rs"]),
+            ("nested", vec!["src/nested.rs", "src/nested/mod.rs"]),
+            ("aliased", vec!["src/custom/renamed.rs"]),
+            ("outer::deep", vec!["src/outer/deep.rs", "src/outer/deep/mod.rs"]),
+        ]);
+
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_str().unwrap().to_string()), ..Default::default() });
+        let result = MyServer::new().index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        fn flatten(nodes: &[ModuleNode], out: &mut Vec<(String, String)>) {
+            for node in nodes {
+                if let Some(file) = &node.file {
+                    let src_index = file.rfind("src").unwrap();
+                    out.push((node.path.clone(), file[src_index..].to_string()));
+                }
+                flatten(&node.children, out);
+            }
+        }
+        let mut modules = Vec::new();
+        flatten(&graphs.module_tree, &mut modules);
+        let expected = [
+            ("crate", "src/lib.rs"),
+            ("crate::aliased", "src/custom/renamed.rs"),
+            ("crate::flat", "src/flat.rs"),
+            ("crate::flat::child", "src/flat/child.rs"),
+            ("crate::nested", "src/nested/mod.rs"),
+            ("crate::outer::deep", "src/outer/deep.rs"),
+        ];
+        assert_eq!(modules, expected.map(|(path, file)| (path.to_string(), file.to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_goto_definition_ranks_by_context_path() {
+        let server = MyServer::new();
+        server.cache.insert("/ws/src/lib.rs".to_string(), "mod net;\nmod util;\nfn connect() {}".to_string()).await;
+        server.cache.insert("/ws/src/net/mod.rs".to_string(), "mod client;\npub fn connect() {}".to_string()).await;
+        server.cache.insert("/ws/src/net/client.rs".to_string(), "fn run() { super::connect(); }".to_string()).await;
+        server.cache.insert("/ws/src/util.rs".to_string(), "pub fn connect() {}".to_string()).await;
+
+        let lookup = |context_path: Option<&str>| {
+            let params = Parameters(GotoDefinitionParams {
+                name: "connect".to_string(),
+                context_path: context_path.map(str::to_string),
+                ..Default::default()
+            });
+            let server = server.clone();
+            async move {
+                let result = server.goto_definition(params).await.unwrap();
+                let defs: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+                defs.into_iter().map(|def| def.file).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(lookup(None).await, vec!["/ws/src/lib.rs", "/ws/src/net/mod.rs", "/ws/src/util.rs"]);
+        assert_eq!(lookup(Some("crate::net::client")).await, vec!["/ws/src/net/mod.rs", "/ws/src/lib.rs", "/ws/src/util.rs"]);
+        assert_eq!(lookup(Some("util")).await, vec!["/ws/src/util.rs", "/ws/src/lib.rs", "/ws/src/net/mod.rs"]);
+    }
+
+    #[tokio::test]
+    async fn test_ignore_cfg_test() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"
+pub fn run() { helper(); }
+fn helper() {}
+
+#[cfg(not(test))]
+fn production_only() {}
+
+#[cfg(all(test, unix))]
+fn unix_test_helper() {}
+
+struct S;
+impl S {
+    #[cfg(test)]
+    fn test_method(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn fixture() { run(); }
+}
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let index = |ignore_cfg_test| {
+            let params = Parameters(IndexWorkspaceParams {
+                root: Some(temp_dir.path().to_str().unwrap().to_string()),
+                ignore_cfg_test,
+                ..Default::default()
+            });
+            async move {
+                let result = MyServer::new().index_workspace(params).await.unwrap();
+                let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+                let mut callers: Vec<String> = graphs.call_graph.calls.into_keys().collect();
+                callers.sort();
+                (callers, graphs.unused_functions)
+            }
+        };
+
+        let (callers, _) = index(false).await;
+        assert!(callers.contains(&"tests::fixture".to_string()));
+        assert!(callers.contains(&"S::test_method".to_string()));
+
+        let (callers, unused) = index(true).await;
+        assert_eq!(callers, vec!["helper", "production_only", "run"]);
+        assert_eq!(unused, vec!["production_only"]);
+    }
+
+    #[tokio::test]
+    async fn test_thresholds_from_config_and_params() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().to_str().unwrap().to_string();
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn three(a: u8, b: u8, c: u8) {}").unwrap();
+
+        let suggestions = |thresholds: Option<ThresholdOverrides>| {
+            let params = Parameters(IndexWorkspaceParams { root: Some(root.clone()), thresholds, ..Default::default() });
+            async move {
+                let result = MyServer::new().index_workspace(params).await?;
+                let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+                Ok::<_, rmcp::ErrorData>(graphs.refactoring_suggestions)
+            }
+        };
+
+        assert!(suggestions(None).await.unwrap().is_empty());
+
+        std::fs::write(temp_dir.path().join("ruststudio.toml"), "[thresholds]\nmax_params = 2\n").unwrap();
+        let from_config = suggestions(None).await.unwrap();
+        assert_eq!(from_config.len(), 1);
+        assert_eq!(from_config[0].kind, RefactoringKind::TooManyParams);
+        assert_eq!((from_config[0].metric, from_config[0].threshold), (3, 2));
+        assert_eq!(from_config[0].target, "three");
+        assert_eq!(from_config[0].range.as_ref().unwrap().start.line, 1);
+        assert!(from_config[0].message.contains("too many parameters (3)"));
+
+        // Request overrides win over the config file, field by field
+        let overrides = ThresholdOverrides { max_params: Some(3), ..Default::default() };
+        assert!(suggestions(Some(overrides)).await.unwrap().is_empty());
+
+        std::fs::write(temp_dir.path().join("ruststudio.toml"), "[thresholds]\nmax_parameters = 2\n").unwrap();
+        assert!(suggestions(None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_naming() {
+        let server = MyServer::new();
+        let code = r#"
+fn good_name() {}
+fn badName() {}
+struct GoodType;
+struct bad_type;
+const MAX_SIZE: usize = 1;
+const minSize: usize = 0;
+static _PRIVATE: u8 = 0;
+trait HTTPClient {}
+mod BadModule {}
+type lower_alias = u8;
+impl GoodType {
+    fn MethodName(&self) {}
+}
+fn SDL_Init() {}
+"#;
+        server.cache.insert("naming.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(CheckNamingParams { allowlist: Some(vec!["SDL_Init".to_string()]), ..Default::default() });
+        let result = server.check_naming(params).await.unwrap();
+        let violations: Vec<NamingViolation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(&str, NamingConvention, usize)> = violations.iter()
+            .map(|v| (v.name.as_str(), v.expected, v.range.start.line))
+            .collect();
+        assert_eq!(found, vec![
+            ("badName", NamingConvention::SnakeCase, 3),
+            ("bad_type", NamingConvention::UpperCamelCase, 5),
+            ("minSize", NamingConvention::ScreamingSnakeCase, 7),
+            ("BadModule", NamingConvention::SnakeCase, 10),
+            ("lower_alias", NamingConvention::UpperCamelCase, 11),
+            ("MethodName", NamingConvention::SnakeCase, 13),
+        ]);
+        assert_eq!(violations[5].kind, SymbolKind::Method);
+    }
+
+    #[tokio::test]
+    async fn test_find_missing_docs() {
+        let server = MyServer::new();
+        let lib = r#"
+//! Crate docs.
+/// Documented.
+pub fn documented() {}
+pub fn undocumented() {}
+fn private_fn() {}
+pub struct Config;
+impl Config {
+    pub fn load() {}
+    fn helper() {}
+}
+/// A trait.
+pub trait Source {
+    fn read(&self);
+}
+mod hidden {
+    pub fn inside_private_inline() {}
+}
+pub mod public;
+mod internal;
+"#;
+        server.cache.insert("/ws/src/lib.rs".to_string(), lib.to_string()).await;
+        server.cache.insert("/ws/src/public.rs".to_string(), "pub fn exported() {}".to_string()).await;
+        server.cache.insert("/ws/src/internal.rs".to_string(), "pub fn not_exported() {}".to_string()).await;
+
+        let missing = |reachable_only| {
+            let params = Parameters(FindMissingDocsParams { reachable_only, ..Default::default() });
+            let server = server.clone();
+            async move {
+                let result = server.find_missing_docs(params).await.unwrap();
+                let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+                symbols.into_iter().map(|sym| sym.name).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            missing(false).await,
+            vec!["not_exported", "undocumented", "Config", "load", "read", "inside_private_inline", "public", "exported"]
+        );
+        assert_eq!(missing(true).await, vec!["undocumented", "Config", "load", "read", "public", "exported"]);
+    }
+
+    #[tokio::test]
+    async fn test_cargo_check() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"checked\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn answer() -> u32 {\n    \"42\"\n}\n\nfn unused() {}\n").unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(CargoCheckParams {
+            manifest_dir: dir.path().to_string_lossy().to_string(),
+            ..Default::default()
+        });
+        let result = server.cargo_check(params).await.unwrap();
+        let diagnostics: Vec<Diagnostic> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        let lib_rs = dir.path().join("src/lib.rs").to_string_lossy().to_string();
+        let error = diagnostics.iter().find(|d| d.severity == Severity::Error).unwrap();
+        assert_eq!(error.message, "mismatched types");
+        assert_eq!(error.code.as_deref(), Some("E0308"));
+        assert_eq!(error.file.as_deref(), Some(lib_rs.as_str()));
+        assert_eq!((error.range.start.line, error.range.start.character), (2, 4));
+        assert_eq!((error.range.end.line, error.range.end.character), (2, 8));
+
+        let missing = server.cargo_check(Parameters(CargoCheckParams {
+            manifest_dir: dir.path().join("src").to_string_lossy().to_string(),
+            ..Default::default()
+        })).await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clippy() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"linted\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn answer() -> u32 {\n    return 42;\n}\n").unwrap();
+
+        let server = MyServer::new();
+        let lint = |min_severity| {
+            let params = Parameters(ClippyParams {
+                manifest_dir: dir.path().to_string_lossy().to_string(),
+                min_severity,
+                ..Default::default()
+            });
+            let server = server.clone();
+            async move {
+                let result = server.clippy(params).await.unwrap();
+                serde_json::from_str::<Vec<Diagnostic>>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let diagnostics = lint(None).await;
+        let needless_return = diagnostics.iter().find(|d| d.code.as_deref() == Some("clippy::needless_return")).unwrap();
+        assert_eq!(needless_return.severity, Severity::Warning);
+        assert_eq!(needless_return.range.start.line, 2);
+
+        assert!(lint(Some(Severity::Error)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_dependencies() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), r#"
+[workspace]
+members = ["member"]
+
+[workspace.dependencies]
+serde = { version = "1.0", features = ["derive"] }
+shared = { path = "shared" }
+"#).unwrap();
+        std::fs::create_dir(dir.path().join("member")).unwrap();
+        std::fs::write(dir.path().join("member/Cargo.toml"), r#"
+[package]
+name = "member"
+version = "0.1.0"
+
+[dependencies]
+serde = { workspace = true, features = ["rc"], optional = true }
+shared.workspace = true
+regex = { version = "1", default-features = false }
+json = { package = "serde_json", version = "1" }
+
+[dev-dependencies]
+tempfile = "3"
+
+[build-dependencies]
+cc = "1.0"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(ListDependenciesParams { manifest_path: dir.path().join("member").to_string_lossy().to_string() });
+        let result = server.list_dependencies(params).await.unwrap();
+        let dependencies: Vec<Dependency> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        let summary: Vec<(&str, DependencyKind, Option<&str>)> = dependencies
+            .iter()
+            .map(|dep| (dep.name.as_str(), dep.kind, dep.target.as_deref()))
+            .collect();
+        assert_eq!(summary, vec![
+            ("json", DependencyKind::Normal, None),
+            ("regex", DependencyKind::Normal, None),
+            ("serde", DependencyKind::Normal, None),
+            ("shared", DependencyKind::Normal, None),
+            ("libc", DependencyKind::Normal, Some("cfg(unix)")),
+            ("tempfile", DependencyKind::Dev, None),
+            ("cc", DependencyKind::Build, None),
+        ]);
+
+        let serde = &dependencies[2];
+        assert_eq!(serde.version.as_deref(), Some("1.0"));
+        assert_eq!(serde.features, vec!["derive", "rc"]);
+        assert!(serde.optional && serde.inherited);
+        assert_eq!(dependencies[0].package.as_deref(), Some("serde_json"));
+        assert!(!dependencies[1].default_features);
+        assert_eq!(dependencies[3].path.as_deref(), Some(dir.path().join("shared").to_string_lossy().as_ref()));
+
+        let missing = server.list_dependencies(Parameters(ListDependenciesParams {
+            manifest_path: dir.path().join("nope").to_string_lossy().to_string(),
+        })).await;
+        assert!(missing.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_unused_dependencies() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), r#"
+[package]
+name = "hygiene"
+version = "0.1.0"
+
+[dependencies]
+serde = "1"
+json = { package = "serde_json", version = "1" }
+lazy-static = "1"
+anyhow = "1"
+log = "0.4"
+itertools = "0.12"
+
+[dev-dependencies]
+tempfile = "3"
+
+[build-dependencies]
+cc = "1"
+"#).unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), r#"
+use serde::Serialize;
+#[macro_use]
+extern crate lazy_static;
+
+pub fn value() -> String {
+    format!("{}", json::json!({ "ok": true }))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {
+        log::info!("{:?}", tempfile::tempdir());
+    }
+}
+"#).unwrap();
+        std::fs::write(dir.path().join("build.rs"), "fn main() { anyhow::Ok(()); }").unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(FindUnusedDependenciesParams { manifest_path: dir.path().to_string_lossy().to_string() });
+        let result = server.find_unused_dependencies(params).await.unwrap();
+        let unused: Vec<Dependency> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let names: Vec<&str> = unused.iter().map(|dep| dep.name.as_str()).collect();
+
+        // `anyhow` is only used by the build script, `cc` only declared for it
+        assert_eq!(names, vec!["anyhow", "itertools", "cc"]);
+    }
+
+    #[tokio::test]
+    async fn test_reindex_changed() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        std::fs::write(dir.path().join("kept.rs"), "fn kept() {}").unwrap();
+        std::fs::write(dir.path().join("edited.rs"), "fn before() {}").unwrap();
+        std::fs::write(dir.path().join("deleted.rs"), "fn deleted() {}").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+
+        let server = MyServer::new();
+
+        // Nothing to merge into before a full index
+        let params = Parameters(ReindexChangedParams { root: root.clone(), ..Default::default() });
+        assert!(server.reindex_changed(params).await.is_err());
+
+        let params = Parameters(IndexWorkspaceParams { root: Some(root.clone()), ..Default::default() });
+        server.index_workspace(params).await.unwrap();
+
+        std::fs::write(dir.path().join("edited.rs"), "fn after() {}").unwrap();
+        std::fs::remove_file(dir.path().join("deleted.rs")).unwrap();
+        std::fs::write(dir.path().join("added.rs"), "fn added() {}").unwrap();
+
+        let params = Parameters(ReindexChangedParams { root: root.clone(), ..Default::default() });
+        let result = server.reindex_changed(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let functions: Vec<&str> = graphs.function_info.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(functions, vec!["added", "after", "kept"]);
+
+        // Refs that git would read as options are refused before git runs
+        let output = dir.path().join("injected.txt");
+        let git_ref = format!("--output={}", output.display());
+        let params = Parameters(ReindexChangedParams { root: root.clone(), git_ref: Some(git_ref) });
+        let error = server.reindex_changed(params).await.unwrap_err();
+        assert_eq!(error.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert!(!output.exists());
+
+        let deleted = dir.path().join("deleted.rs").to_string_lossy().to_string();
+        let edited = dir.path().join("edited.rs").to_string_lossy().to_string();
+        assert!(!server.cache.contains(&deleted).await);
+        assert_eq!(server.cache.get(&edited).await.as_deref(), Some("fn after() {}"));
+    }
+
+    #[tokio::test]
+    async fn test_reindex_changed_keeps_index_filter() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?} failed", args);
+        };
+        std::fs::create_dir(dir.path().join("generated")).unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "fn before() {}").unwrap();
+        std::fs::write(dir.path().join("generated").join("out.rs"), "fn generated() {}").unwrap();
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "initial"]);
+
+        // Indexed through a differently spelled root, without `generated/` and limited to functions
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(format!("{}/.", root)),
+            exclude: Some(vec!["generated/**".to_string()]),
+            kinds: Some(vec!["fn".to_string()]),
+            ..Default::default()
+        });
+        server.index_workspace(params).await.unwrap();
+
+        std::fs::write(dir.path().join("lib.rs"), "struct Added;\nfn after() {}").unwrap();
+        std::fs::write(dir.path().join("generated").join("out.rs"), "fn regenerated() {}").unwrap();
+
+        let params = Parameters(ReindexChangedParams { root: root.clone(), ..Default::default() });
+        let result = server.reindex_changed(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let functions: Vec<&str> = graphs.function_info.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(functions, vec!["after"]);
+        assert!(graphs.struct_info.is_empty());
+
+        let index = server.workspace_index.read().await;
+        let files: Vec<&String> = index.as_ref().unwrap().analyses.keys().collect();
+        assert_eq!(files, vec![&format!("{}/./lib.rs", root)]);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_tokens() {
+        let server = MyServer::new();
+        let code = r#"use std::fmt::Display;
+struct Point { x: i32 }
+enum Shape { Dot(Point) }
+trait Area { fn area(&self) -> f64; }
+fn scale<T: Display>(factor: i32) -> Option<Point> {
+    let p = Point::new(factor);
+    println!("{}", p.x);
+    Some(Point { x: factor })
+}
+"#;
+        server.cache.insert("/virtual/tokens.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(SemanticTokensParams { path: "/virtual/tokens.rs".to_string(), ..Default::default() });
+        let result = server.semantic_tokens(params).await.unwrap();
+        let tokens: Vec<SemanticToken> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let token_at = |line: usize, character: usize| {
+            tokens.iter().find(|t| t.range.start == Position { line, character }).map(|t| t.token_type)
+        };
+
+        assert_eq!(token_at(1, 4), Some(SemanticTokenType::Namespace));
+        assert_eq!(token_at(1, 14), Some(SemanticTokenType::Type));
+        assert_eq!(token_at(2, 7), Some(SemanticTokenType::Struct));
+        assert_eq!(token_at(2, 15), Some(SemanticTokenType::Property));
+        assert_eq!(token_at(3, 5), Some(SemanticTokenType::Enum));
+        assert_eq!(token_at(3, 13), Some(SemanticTokenType::EnumMember));
+        assert_eq!(token_at(4, 6), Some(SemanticTokenType::Interface));
+        assert_eq!(token_at(4, 16), Some(SemanticTokenType::Method));
+        assert_eq!(token_at(5, 3), Some(SemanticTokenType::Function));
+        assert_eq!(token_at(5, 9), Some(SemanticTokenType::TypeParameter));
+        assert_eq!(token_at(5, 21), Some(SemanticTokenType::Parameter));
+        assert_eq!(token_at(6, 8), Some(SemanticTokenType::Variable));
+        assert_eq!(token_at(6, 12), Some(SemanticTokenType::Struct));
+        assert_eq!(token_at(6, 19), Some(SemanticTokenType::Method));
+        assert_eq!(token_at(6, 23), Some(SemanticTokenType::Parameter));
+        assert_eq!(token_at(7, 4), Some(SemanticTokenType::Macro));
+        assert_eq!(token_at(7, 21), Some(SemanticTokenType::Property));
+        assert_eq!(token_at(8, 4), Some(SemanticTokenType::EnumMember));
+        assert_eq!(serde_json::to_value(SemanticTokenType::EnumMember).unwrap(), "enumMember");
+    }
+
+    #[tokio::test]
+    async fn test_find_shadowed_variables() {
+        let server = MyServer::new();
+        let code = r#"fn parse(input: &str) -> u32 {
+    let input = input.trim();
+    let count = input.len();
+    let count = count as u32;
+    for item in 0..count {
+        let item = item * 2;
+    }
+    {
+        let scoped = 1u8;
+    }
+    let scoped = "no longer in scope";
+    let closure = |count: u32| count;
+    count
+}
+"#;
+        server.cache.insert("/virtual/shadow.rs".to_string(), code.to_string()).await;
+
+        let find = |type_changed| {
+            let params = Parameters(FindShadowedVariablesParams { type_changed, ..Default::default() });
+            let server = server.clone();
+            async move {
+                let result = server.find_shadowed_variables(params).await.unwrap();
+                serde_json::from_str::<Vec<ShadowedVariable>>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let all = find(None).await;
+        let names: Vec<&str> = all.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["input", "count", "item"]);
+        assert_eq!(all[0].function, "parse");
+        assert_eq!((all[0].outer.start.line, all[0].inner.start.line), (1, 2));
+        assert_eq!(all[0].outer_type.as_deref(), Some("&str"));
+        assert_eq!(all[0].type_changed, None);
+        assert_eq!(all[1].outer_type.as_deref(), Some("usize"));
+        assert_eq!(all[1].inner_type.as_deref(), Some("u32"));
+
+        let changed: Vec<String> = find(Some(true)).await.into_iter().map(|s| s.name).collect();
+        assert_eq!(changed, vec!["count"]);
+        assert!(find(Some(false)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_unused_variables() {
+        let server = MyServer::new();
+        let code = r#"fn compute(flag: bool) -> i32 {
+    let used = 1;
+    let unused = 2;
+    let _ignored = 3;
+    let (a, b) = (4, 5);
+    let mut written = 0;
+    written = 6;
+    let captured = 7;
+    println!("{captured}");
+    let in_macro = 8;
+    assert_eq!(in_macro, 8);
+    let shadowed = 9;
+    let shadowed = 10;
+    if let Some(value) = Some(shadowed) {
+        let inner = value;
+    }
+    used + a
+}
+"#;
+        server.cache.insert("/virtual/unused.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindUnusedVariablesParams::default());
+        let result = server.find_unused_variables(params).await.unwrap();
+        let unused: Vec<UnusedVariable> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(&str, usize)> = unused.iter().map(|u| (u.name.as_str(), u.range.start.line)).collect();
+
+        assert_eq!(found, vec![("unused", 3), ("b", 5), ("written", 6), ("shadowed", 12), ("inner", 15)]);
+        assert!(unused.iter().all(|u| u.function == "compute"));
+    }
+
+    #[tokio::test]
+    async fn test_find_unreachable_code() {
+        let server = MyServer::new();
+        let code = r#"fn early(flag: bool) -> i32 {
+    if flag {
+        return 1;
+        println!("after return");
+    }
+    for i in 0..3 {
+        continue;
+        let skipped = i;
+    }
+    todo!();
+    fn helper() {}
+    let x = 2;
+    x
+}
+"#;
+        server.cache.insert("/virtual/unreachable.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindUnreachableCodeParams::default());
+        let result = server.find_unreachable_code(params).await.unwrap();
+        let unreachable: Vec<UnreachableCode> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let ranges: Vec<(usize, usize, usize)> = unreachable
+            .iter()
+            .map(|u| (u.range.start.line, u.range.start.character, u.range.end.line))
+            .collect();
+
+        assert_eq!(ranges, vec![(4, 8, 4), (8, 8, 8), (12, 4, 12), (13, 4, 13)]);
+    }
+
+    #[tokio::test]
+    async fn test_list_tests() {
+        let server = MyServer::new();
+        let code = r#"
+pub fn helper() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn plain() {}
+
+    #[test]
+    #[ignore]
+    fn slow() {}
+
+    #[tokio::test]
+    async fn asynchronous() {}
+
+    #[test_case(1)]
+    #[test_case(2)]
+    fn cases(n: u32) {}
+
+    fn not_a_test() {}
+}
+"#;
+        server.cache.insert("/virtual/tests.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(ListTestsParams::default());
+        let result = server.list_tests(params).await.unwrap();
+        let tests: Vec<TestInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let summary: Vec<(&str, &str, bool)> = tests.iter().map(|t| (t.path.as_str(), t.attribute.as_str(), t.ignored)).collect();
+
+        assert_eq!(summary, vec![
+            ("tests::plain", "test", false),
+            ("tests::slow", "test", true),
+            ("tests::asynchronous", "tokio::test", false),
+            ("tests::cases", "test_case", false),
+        ]);
+        assert_eq!(tests[0].range.start.line, 7);
+    }
+
+    #[tokio::test]
+    async fn test_find_tests_without_assertions() {
+        let server = MyServer::new();
+        let code = r#"
+#[test]
+fn empty() {}
+
+#[test]
+fn only_setup() {
+    let value = compute();
+}
+
+#[test]
+fn asserts() {
+    assert_eq!(compute(), 1);
+}
+
+#[test]
+fn unwraps() {
+    compute_result().unwrap();
+}
+
+#[test]
+fn propagates() -> Result<(), String> {
+    compute_result()?;
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn panics() {
+    compute();
+}
+
+#[tokio::test]
+async fn nested_macro() {
+    tokio::join!(async { assert!(true) });
+}
+"#;
+        server.cache.insert("/virtual/assertions.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindTestsWithoutAssertionsParams::default());
+        let result = server.find_tests_without_assertions(params).await.unwrap();
+        let tests: Vec<TestInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let names: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+
+        assert_eq!(names, vec!["empty", "only_setup"]);
+    }
+
+    #[tokio::test]
+    async fn test_find_blocking_in_async() {
+        let server = MyServer::new();
+        let code = r#"
+use std::thread;
+use std::fs::read_to_string;
+
+async fn handler() {
+    thread::sleep(std::time::Duration::from_secs(1));
+    let text = read_to_string("config.toml");
+    let file = std::fs::File::open("data.bin");
+    tokio::task::spawn_blocking(|| std::fs::read("big.bin"));
+    db::query_sync("select 1");
+    fn helper() {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+fn sync_entry() {
+    std::fs::read("fine.bin");
+    let fut = async {
+        std::fs::write("out.txt", "data");
+    };
+}
+"#;
+        server.cache.insert("/virtual/blocking.rs".to_string(), code.to_string()).await;
+
+        let find = |extra_blocking_calls| {
+            let params = Parameters(FindBlockingInAsyncParams { extra_blocking_calls, ..Default::default() });
+            let server = server.clone();
+            async move {
+                let result = server.find_blocking_in_async(params).await.unwrap();
+                serde_json::from_str::<Vec<BlockingCall>>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let calls = find(None).await;
+        let found: Vec<(&str, &str, &str)> = calls.iter().map(|c| (c.call.as_str(), c.blocking_api.as_str(), c.function.as_str())).collect();
+        assert_eq!(found, vec![
+            ("thread::sleep", "std::thread::sleep", "handler"),
+            ("read_to_string", "std::fs::read_to_string", "handler"),
+            ("std::fs::File::open", "std::fs::File::open", "handler"),
+            ("std::fs::write", "std::fs::write", "sync_entry"),
+        ]);
+        assert_eq!(calls[0].range.start.line, 6);
+
+        let calls = find(Some(vec!["app::db::query_sync".to_string()])).await;
+        assert!(calls.iter().any(|c| c.blocking_api == "app::db::query_sync"));
+    }
+
+    #[tokio::test]
+    async fn test_find_await_in_loops() {
+        let server = MyServer::new();
+        let code = r#"
+async fn fetch_all(urls: Vec<String>) {
+    for url in fetch_list().await {
+        fetch(url).await;
+    }
+    while let Some(item) = stream.next().await {
+        loop {
+            process(item).await;
+        }
+    }
+    let futures: Vec<_> = urls.iter().map(|url| async move { fetch(url).await }).collect();
+    for url in urls {
+        tasks.push(async move { fetch(url).await });
+    }
+    join_all(futures).await;
+}
+"#;
+        server.cache.insert("/virtual/await_loops.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindAwaitInLoopsParams::default());
+        let result = server.find_await_in_loops(params).await.unwrap();
+        let awaits: Vec<AwaitInLoop> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(usize, LoopKind)> = awaits.iter().map(|a| (a.range.start.line, a.loop_kind)).collect();
+
+        assert_eq!(found, vec![(4, LoopKind::For), (8, LoopKind::Loop)]);
+        assert_eq!(awaits[0].function, "fetch_all");
+    }
+
+    #[tokio::test]
+    async fn test_call_path() {
+        let server = MyServer::new();
+        server.cache.insert("/ws/src/main.rs".to_string(), r#"
+fn main() {
+    setup();
+    server::run();
+}
+fn setup() {}
+"#.to_string()).await;
+        server.cache.insert("/ws/src/server.rs".to_string(), r#"
+pub fn run() {
+    let handler = Handler;
+    handler.dispatch();
+    Handler::dispatch(&handler);
+}
+struct Handler;
+impl Handler {
+    fn dispatch(&self) {
+        store::save();
+    }
+}
+"#.to_string()).await;
+        server.cache.insert("/ws/src/store.rs".to_string(), "pub fn save() {}\npub fn unreachable_from_main() {}".to_string()).await;
+
+        let call_path = |from: &str, to: &str| {
+            let params = Parameters(CallPathParams { from: from.to_string(), to: to.to_string(), ..Default::default() });
+            let server = server.clone();
+            async move { server.call_path(params).await }
+        };
+
+        let result = call_path("main", "save").await.unwrap();
+        let path: CallPath = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(path.functions, vec!["main", "run", "Handler::dispatch", "save"]);
+        assert_eq!(path.hops.len(), 3);
+        assert_eq!(path.hops[0].file, "/ws/src/main.rs");
+        assert_eq!(path.hops[0].range.start.line, 4);
+        assert_eq!((path.hops[1].callee.as_str(), path.hops[1].range.start.line), ("dispatch", 4));
+
+        let result = call_path("save", "main").await.unwrap();
+        assert!(result.content[0].as_text().unwrap().text.starts_with("No call path"));
+        assert!(call_path("main", "missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_module_topo_order() {
+        let server = MyServer::new();
+        server.cache.insert("/ws/src/lib.rs".to_string(), "mod models;\nmod cache;\nmod tools;\nuse crate::tools::run;".to_string()).await;
+        server.cache.insert("/ws/src/models.rs".to_string(), "pub struct Range;".to_string()).await;
+        server.cache.insert("/ws/src/cache.rs".to_string(), "use crate::models::Range;".to_string()).await;
+        server.cache.insert("/ws/src/tools.rs".to_string(), "use crate::cache::*;\nuse crate::models::Range;\npub fn run() {}".to_string()).await;
+
+        let order = |server: MyServer| async move {
+            let result = server.module_topo_order().await.unwrap();
+            serde_json::from_str::<ModuleTopoOrder>(&result.content[0].as_text().unwrap().text).unwrap()
+        };
+
+        let result = order(server.clone()).await;
+        assert_eq!(
+            result.order.unwrap(),
+            vec!["crate::models", "crate::cache", "crate::tools", "crate"]
+        );
+        assert!(result.cycle.is_none());
+
+        server.cache.insert("/ws/src/models.rs".to_string(), "use crate::tools::run;".to_string()).await;
+        let result = order(server.clone()).await;
+        assert!(result.order.is_none());
+        assert_eq!(result.cycle.unwrap(), vec!["crate::cache", "crate::models", "crate::tools"]);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_entry_points_and_leaves() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn main() { run(); }
+fn run() { parse(); log(); }
+fn parse() -> u32 { 1 }
+fn log() {}
+pub fn api() { parse(); }
+"#;
+        std::fs::write(temp_dir.path().join("main.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        assert_eq!(graphs.entry_points, vec!["api".to_string()]);
+        assert_eq!(graphs.leaf_functions, vec!["log".to_string(), "parse".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_search_symbols() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("lib.rs");
+        let code = r#"struct RequestHandler;
+struct Request;
+fn test_parse() {}
+fn ErrorHandler() {}
+fn parse() {}
+"#;
+        std::fs::write(&file_path, code).unwrap();
+
+        let server = MyServer::new();
+        server.cache.insert(file_path.to_string_lossy().to_string(), code.to_string()).await;
+
+        let search = |pattern: &str, max_results: Option<usize>| {
+            let server = server.clone();
+            let params = Parameters(SearchSymbolsParams {
+                pattern: pattern.to_string(),
+                max_results,
+                ..Default::default()
+            });
+            async move { server.search_symbols(params).await }
+        };
+
+        let result = search(".*Handler$", None).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["ErrorHandler", "RequestHandler"]);
+        assert_eq!(symbols[1].kind, SymbolKind::Struct);
+
+        let result = search("^test_", None).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "test_parse");
+
+        let result = search("", Some(2)).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["ErrorHandler", "Request"]);
+
+        assert!(search("(unclosed", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_kinds() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"struct Point { x: i32 }
+enum Shape { Circle, Square }
+fn area() -> i32 { 0 }
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let index = |kinds: Vec<&str>| {
+            let server = server.clone();
+            let params = Parameters(IndexWorkspaceParams {
+                root: Some(temp_dir.path().to_string_lossy().to_string()),
+                kinds: Some(kinds.into_iter().map(String::from).collect()),
+                ..Default::default()
+            });
+            async move { server.index_workspace(params).await }
+        };
+
+        let result = index(vec!["enum", "trait"]).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(graphs.function_info.is_empty());
+        assert!(graphs.struct_info.is_empty());
+        assert_eq!(graphs.enum_info.len(), 1);
+        assert!(graphs.call_graph.calls.contains_key("area"));
+
+        let result = index(vec!["fn"]).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(graphs.function_info.len(), 1);
+        assert!(graphs.enum_info.is_empty());
+
+        assert!(index(vec!["widget"]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_symbol_container() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("lib.rs");
+        let code = r#"struct Foo;
+impl Foo { fn bar(&self) {} }
+trait Greet { fn bar(&self); }
+mod net { pub fn bar() {} }
+fn bar() {}
+"#;
+        std::fs::write(&file_path, code).unwrap();
+
+        let server = MyServer::new();
+        server.cache.insert(file_path.to_string_lossy().to_string(), code.to_string()).await;
+
+        let params = Parameters(GotoDefinitionParams { name: "bar".to_string(), ..Default::default() });
+        let result = server.goto_definition(params).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let containers: Vec<Option<&str>> = symbols.iter().map(|s| s.container.as_deref()).collect();
+        assert_eq!(containers, vec![Some("Foo"), Some("Greet"), Some("net"), None]);
+
+        let params = Parameters(GotoDefinitionParams { name: "net".to_string(), ..Default::default() });
+        let result = server.goto_definition(params).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(symbols[0].container, None);
+    }
+
+    #[tokio::test]
+    async fn test_crlf_positions() {
+        let lf = "/** Adds one.\nReally. */\nfn foo() {}\nfn main() { let s = \"é\"; foo(); }\n";
+        let crlf = lf.replace('\n', "\r\n");
+        let server = MyServer::new();
+        server.cache.insert("lf.rs".to_string(), lf.to_string()).await;
+        server.cache.insert("crlf.rs".to_string(), crlf.clone()).await;
+
+        let params = Parameters(FindReferencesParams {
+            name: "foo".to_string(),
+            position_options: PositionOptions {
+                byte_offsets: true,
+                position_encoding: PositionEncoding::Utf16,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let result = server.find_references(params).await.unwrap();
+        let refs: Vec<ReferenceLocation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let call = |file: &str| refs.iter().find(|r| r.file == file && r.range.start.line == 4).unwrap().range.clone();
+
+        let (lf_call, crlf_call) = (call("lf.rs"), call("crlf.rs"));
+        assert_eq!(crlf_call.start, lf_call.start);
+        assert_eq!(crlf_call.start.character, 25);
+        // Byte offsets index the stored text, `\r`s included
+        assert_eq!(crlf_call.start_byte, Some(crlf.find("foo();").unwrap()));
+
+        let params = Parameters(GotoDefinitionParams { name: "foo".to_string(), ..Default::default() });
+        let result = server.goto_definition(params).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(symbols.len(), 2);
+        assert!(symbols.iter().all(|s| s.docs.as_deref() == Some("Adds one.\nReally.")));
+    }
+
+    #[tokio::test]
+    async fn test_bom_and_shebang() {
+        let server = MyServer::new();
+        let check = |source: &str| {
+            let server = server.clone();
+            let params = Parameters(CheckFileParams { source: Some(source.to_string()), ..Default::default() });
+            async move {
+                let result = server.check_file(params).await.unwrap();
+                serde_json::from_str::<Vec<Diagnostic>>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let shebang = "#!/usr/bin/env run-cargo-script\nfn a() {}\nfn b() { let = 1; }\nstruct ;\n";
+        let diagnostics = check(shebang).await;
+        let lines: Vec<usize> = diagnostics.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![3, 4]);
+        assert!(check("#!/usr/bin/env run-cargo-script\nfn a() {}\n").await.is_empty());
+        assert!(check("#![allow(dead_code)]\nfn a() {}\n").await.is_empty());
+
+        let bom = "\u{feff}fn a() {}\nfn b() { let = 1; }\nstruct ;\n";
+        let diagnostics = check(bom).await;
+        let starts: Vec<(usize, usize)> = diagnostics.iter().map(|d| (d.range.start.line, d.range.start.character)).collect();
+        assert_eq!(starts, vec![(2, 13), (3, 7)]);
+
+        // The BOM isn't part of any column, but byte offsets still count it
+        let bom = "\u{feff}fn target() {}\n";
+        server.cache.insert("bom.rs".to_string(), bom.to_string()).await;
+        let params = Parameters(GotoDefinitionParams {
+            name: "target".to_string(),
+            position_options: PositionOptions {
+                byte_offsets: true,
+                position_encoding: PositionEncoding::Utf8,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let result = server.goto_definition(params).await.unwrap();
+        let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(symbols[0].range.start.character, 3);
+        assert_eq!(symbols[0].range.start_byte, Some(bom.find("target").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_max_file_bytes() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("small.rs"), "fn small() {}\n").unwrap();
+        let generated: String = (0..200).map(|i| format!("pub const C{}: u32 = {};\n", i, i)).collect();
+        std::fs::write(temp_dir.path().join("generated.rs"), &generated).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            max_file_bytes: Some(1024),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        assert_eq!(graphs.skipped_files.len(), 1);
+        assert!(graphs.skipped_files[0].file.ends_with("generated.rs"));
+        assert!(graphs.skipped_files[0].reason.contains(&generated.len().to_string()));
+        assert_eq!(graphs.function_info.len(), 1);
+        assert!(server.cache.get(&graphs.skipped_files[0].file).await.is_none());
+
+        // The default limit is far above this fixture
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(graphs.skipped_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_cancelled() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "fn a() {}\n").unwrap();
+        let params = || Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+
+        let server = MyServer::new();
+        let cancellation = tokio_util::sync::CancellationToken::new();
+        cancellation.cancel();
+        let result = crate::tools::index_workspace::index_workspace(&server, params(), None, Some(&cancellation)).await;
+        let error = result.unwrap_err();
+        assert_eq!(error.data.unwrap()["processed_files"], 0);
+        assert!(server.workspace_index.read().await.is_none());
+
+        let live = tokio_util::sync::CancellationToken::new();
+        let result = crate::tools::index_workspace::index_workspace(&server, params(), None, Some(&live)).await;
+        assert!(result.is_ok());
+        assert!(server.workspace_index.read().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let server = MyServer::new();
+        server.cache.insert("a.rs".to_string(), "fn a() {}".to_string()).await;
+
+        let result = server.ping().await.unwrap();
+        let response: PingResponse = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(response.name, env!("CARGO_PKG_NAME"));
+        assert_eq!(response.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(response.cached_files, 1);
+        assert!(response.tools.contains(&"ping".to_string()));
+        assert!(response.tools.contains(&"index_workspace".to_string()));
+        assert!(response.tools.is_sorted());
+    }
+
+    #[tokio::test]
+    async fn test_find_variant_references() {
+        let code = r#"enum Shape { Circle(f64), Square }
+struct Circle;
+impl Shape {
+    fn unit() -> Self { Self::Circle(1.0) }
+}
+fn area(shape: &Shape) -> f64 {
+    let Circle = 2.0;
+    match shape {
+        Shape::Circle(r) => r * r * Circle,
+        crate::Shape::Square => 1.0,
+    }
+}
+fn is_round(shape: &Shape) -> bool { if let Shape::Circle(_) = shape { true } else { false } }
+"#;
+        let server = MyServer::new();
+        server.cache.insert("shapes.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindVariantReferencesParams {
+            enum_name: "Shape".to_string(),
+            variant_name: "Circle".to_string(),
+            ..Default::default()
+        });
+        let result = server.find_variant_references(params).await.unwrap();
+        let refs: Vec<ReferenceLocation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let starts: Vec<(usize, usize)> = refs.iter().map(|r| (r.range.start.line, r.range.start.character)).collect();
+        assert_eq!(starts, vec![(4, 30), (9, 15), (13, 51)]);
+    }
+
+    #[tokio::test]
+    async fn test_find_field_references() {
+        let code = r#"struct Point { x: i32, y: i32 }
+struct Size { x: i32 }
+impl Point {
+    fn new(x: i32) -> Self { Self { x, y: 0 } }
+    fn shift(&mut self) { self.x += 1; }
+}
+fn f(p: &Point, s: Size, any: Box<Point>) -> i32 {
+    let Point { x, .. } = *p;
+    let q = Point { x: 1, y: 2 };
+    p.x + s.x + q.x + any.x + x
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("points.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindFieldReferencesParams {
+            struct_name: "Point".to_string(),
+            field: "x".to_string(),
+            ..Default::default()
+        });
+        let result = server.find_field_references(params).await.unwrap();
+        let refs: Vec<FieldReference> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(usize, usize, FieldReferenceKind, bool)> = refs
+            .iter()
+            .map(|r| (r.range.start.line, r.range.start.character, r.kind, r.exact))
+            .collect();
+        assert_eq!(found, vec![
+            (4, 36, FieldReferenceKind::Init, true),
+            (5, 31, FieldReferenceKind::Access, true),
+            (8, 16, FieldReferenceKind::Pattern, true),
+            (9, 20, FieldReferenceKind::Init, true),
+            (10, 6, FieldReferenceKind::Access, true),
+            (10, 18, FieldReferenceKind::Access, true),
+            (10, 26, FieldReferenceKind::Access, false),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_list_derives() {
+        let code = r#"/// A point.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Point { x: i32 }
+
+#[derive(Debug)]
+#[non_exhaustive]
+enum Shape { Circle }
+
+#[repr(C)]
+union Bits { i: u32, f: f32 }
+"#;
+        let server = MyServer::new();
+        server.cache.insert("types.rs".to_string(), code.to_string()).await;
+
+        let list = |derive: Option<&str>| {
+            let server = server.clone();
+            let params = Parameters(ListDerivesParams { derive: derive.map(String::from), ..Default::default() });
+            async move {
+                let result = server.list_derives(params).await.unwrap();
+                serde_json::from_str::<Vec<TypeDerives>>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let types = list(None).await;
+        assert_eq!(types.len(), 3);
+        assert_eq!(types[0].name, "Point");
+        assert_eq!(types[0].derives, vec!["Debug", "Clone", "serde::Serialize"]);
+        assert_eq!(types[0].attributes, vec!["serde"]);
+        assert_eq!(types[1].kind, SymbolKind::Enum);
+        assert_eq!(types[1].attributes, vec!["non_exhaustive"]);
+        assert!(types[2].derives.is_empty());
+        assert_eq!(types[2].attributes, vec!["repr"]);
+
+        let names: Vec<String> = list(Some("Serialize")).await.into_iter().map(|ty| ty.name).collect();
+        assert_eq!(names, vec!["Point"]);
+        assert_eq!(list(Some("Debug")).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_deprecated_usages() {
+        let code = r#"#[deprecated(since = "0.3.0", note = "use `connect` instead")]
+pub fn open() {}
+
+#[deprecated = "use `Config`"]
+pub struct Settings;
+
+pub fn connect() {}
+
+fn main() {
+    open();
+    let _s: Settings = Settings;
+    let _c = Conn::new();
+    let _p = Pool::new();
+    let _v = Vec::<u8>::new();
+}
+
+pub struct Conn;
+pub struct Pool;
+
+impl Conn {
+    #[deprecated]
+    pub fn new() -> Self { Conn }
+}
+
+impl Pool {
+    pub fn new() -> Self { Pool }
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+        // Its own `open`, which shares only the name
+        server.cache.insert("other.rs".to_string(), "pub fn open() {}
+fn run() { open(); }
+".to_string()).await;
+
+        let params = Parameters(FindDeprecatedUsagesParams::default());
+        let result = server.find_deprecated_usages(params).await.unwrap();
+        let usages: Vec<DeprecatedUsage> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(&str, usize, usize)> = usages
+            .iter()
+            .map(|u| (u.item.name.as_str(), u.range.start.line, u.range.start.character))
+            .collect();
+        assert_eq!(found, vec![("open", 10, 4), ("Settings", 11, 12), ("Settings", 11, 23), ("new", 12, 19)]);
+        assert_eq!(usages[3].item.container.as_deref(), Some("Conn"));
+        assert_eq!(usages[0].item.since.as_deref(), Some("0.3.0"));
+        assert_eq!(usages[0].item.note.as_deref(), Some("use `connect` instead"));
+        assert_eq!(usages[1].item.since, None);
+        assert_eq!(usages[1].item.note.as_deref(), Some("use `Config`"));
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicate_definitions() {
+        let server = MyServer::new();
+        server.cache.insert("a.rs".to_string(), "pub fn parse() {}\nstruct Parser;\nimpl Parser { fn new() -> Self { Parser } }\nfn helper() {}\n".to_string()).await;
+        server.cache.insert("b.rs".to_string(), "pub fn parse() {}\nstruct Lexer;\nimpl Lexer { fn new() -> Self { Lexer } }\nfn helper() {}\nstruct parse;\n".to_string()).await;
+
+        let find = |public_only| {
+            let server = server.clone();
+            let params = Parameters(FindDuplicateDefinitionsParams { public_only, ..Default::default() });
+            async move {
+                let result = server.find_duplicate_definitions(params).await.unwrap();
+                serde_json::from_str::<Vec<DuplicateDefinition>>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let duplicates = find(false).await;
+        let names: Vec<(&str, SymbolKind)> = duplicates.iter().map(|d| (d.name.as_str(), d.kind)).collect();
+        assert_eq!(names, vec![("helper", SymbolKind::Fn), ("parse", SymbolKind::Fn)]);
+        let files: Vec<&str> = duplicates[1].definitions.iter().map(|d| d.file.as_str()).collect();
+        assert_eq!(files, vec!["a.rs", "b.rs"]);
+
+        let names: Vec<String> = find(true).await.into_iter().map(|d| d.name).collect();
+        assert_eq!(names, vec!["parse"]);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_fan_in_and_out() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn hub() { a(); b(); c(); a(); }
+fn a() {}
+fn b() {}
+fn c() {}
+fn x() { hub(); }
+fn y() { hub(); hub(); }
+fn z() { hub(); }
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let index = |max_fan_in_and_out| {
+            let server = server.clone();
+            let params = Parameters(IndexWorkspaceParams {
+                root: Some(temp_dir.path().to_string_lossy().to_string()),
+                thresholds: Some(ThresholdOverrides { max_fan_in_and_out: Some(max_fan_in_and_out), ..Default::default() }),
+                ..Default::default()
+            });
+            async move {
+                let result = server.index_workspace(params).await.unwrap();
+                serde_json::from_str::<WorkspaceGraphs>(&result.content[0].as_text().unwrap().text).unwrap()
+            }
+        };
+
+        let graphs = index(2).await;
+        let hub = graphs.function_info.iter().find(|f| f.name == "hub").unwrap();
+        assert_eq!((hub.fan_in, hub.fan_out), (3, 3));
+        let a = graphs.function_info.iter().find(|f| f.name == "a").unwrap();
+        assert_eq!((a.fan_in, a.fan_out), (1, 0));
+
+        let hubs: Vec<&RefactoringSuggestion> = graphs.refactoring_suggestions.iter().filter(|s| s.kind == RefactoringKind::HubFunction).collect();
+        assert_eq!(hubs.len(), 1);
+        assert_eq!((hubs[0].target.as_str(), hubs[0].metric), ("hub", 3));
+
+        let graphs = index(3).await;
+        assert!(!graphs.refactoring_suggestions.iter().any(|s| s.kind == RefactoringKind::HubFunction));
+    }
+
+    #[tokio::test]
+    async fn test_find_commented_code() {
+        let code = r#"/// Doc example:
+/// let x = compute();
+/// x.finish();
+fn compute() -> i32 {
+    // The answer is computed lazily; see the
+    // module docs for why.
+    // let cached = CACHE.get();
+    // if let Some(v) = cached {
+    //     return v;
+    // }
+    42
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindCommentedCodeParams::default());
+        let result = server.find_commented_code(params).await.unwrap();
+        let blocks: Vec<CommentedCode> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // The doc comment is skipped; the prose run merges with the code, which still dominates it
+        assert_eq!(blocks.len(), 1);
+        assert_eq!((blocks[0].range.start.line, blocks[0].range.start.character), (5, 4));
+        assert_eq!((blocks[0].range.end.line, blocks[0].range.end.character), (10, 8));
+        assert_eq!((blocks[0].lines, blocks[0].code_lines), (6, 4));
+
+        let params = Parameters(FindCommentedCodeParams { min_lines: Some(5), ..Default::default() });
+        let result = server.find_commented_code(params).await.unwrap();
+        let blocks: Vec<CommentedCode> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(blocks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_file_comment_stats() {
+        let code = r##"//! Crate docs.
+
+/* A block comment
+   /* nested */
+   still the comment */
+fn url() -> &'static str {
+    let quote = '"'; // trailing comment
+    let _ = quote;
+    r#"http://example.com /* not a comment */"#
+}
+"##;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FileCommentStatsParams::default());
+        let result = server.file_comment_stats(params).await.unwrap();
+        let stats: Vec<FileCommentStats> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].code_lines, stats[0].comment_lines, stats[0].blank_lines), (5, 4, 1));
+        assert_eq!(stats[0].ratio, 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_find_empty_functions() {
+        let code = r#"fn noop() {}
+
+fn later() -> u32 {
+    todo!("after the parser lands")
+}
+
+struct Parser;
+
+impl Parser {
+    fn parse(&self) { unimplemented!(); }
+    fn reset(&self) { let _ = self; }
+}
+
+trait Hook {
+    fn before(&self) {}
+    fn after(&self);
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindEmptyFunctionsParams::default());
+        let result = server.find_empty_functions(params).await.unwrap();
+        let functions: Vec<EmptyFunction> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(&str, EmptyFunctionKind)> = functions.iter().map(|f| (f.name.as_str(), f.kind)).collect();
+        assert_eq!(found, vec![
+            ("noop", EmptyFunctionKind::Empty),
+            ("later", EmptyFunctionKind::Placeholder),
+            ("parse", EmptyFunctionKind::Placeholder),
+            ("before", EmptyFunctionKind::Empty),
+        ]);
+
+        let params = Parameters(FindEmptyFunctionsParams { kinds: Some(vec![EmptyFunctionKind::Placeholder]), ..Default::default() });
+        let result = server.find_empty_functions(params).await.unwrap();
+        let functions: Vec<EmptyFunction> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(functions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_find_large_matches() {
+        let code = r#"enum Key { Up, Down, Left, Right }
+
+fn handle(key: Key) {
+    match key {
+        Key::Up => println!("up"),
+        _ => {}
+    }
+}
+
+fn digit(n: u8) -> &'static str {
+    match n {
+        0 => "zero",
+        1 => "one",
+        2 => "two",
+        _ => {
+            match n % 2 { 0 => "even", _ => "odd" }
+        }
+    }
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindLargeMatchesParams { max_arms: Some(3), ..Default::default() });
+        let result = server.find_large_matches(params).await.unwrap();
+        let issues: Vec<MatchIssue> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let found: Vec<(MatchIssueKind, &str, usize, usize)> = issues.iter()
+            .map(|issue| (issue.kind, issue.function.as_str(), issue.arms, issue.range.start.line))
+            .collect();
+
+        // The catch-all over plain integers isn't flagged, nor is the two-arm inner match
+        assert_eq!(found, vec![
+            (MatchIssueKind::EmptyCatchAll, "handle", 2, 4),
+            (MatchIssueKind::TooManyArms, "digit", 4, 11),
+        ]);
+    }
+
+    #[test]
+    fn test_workspace_edit_serializes_like_lsp() {
+        let position = |line, character| Position { line, character };
+        let edit = TextEdit {
+            range: Range { start: position(3, 4), end: position(3, 7), start_byte: None, end_byte: None },
+            new_text: "count".to_string(),
+        };
+        let workspace_edit = WorkspaceEdit { changes: [("src/lib.rs".to_string(), vec![edit])].into() };
+
+        let json = serde_json::to_value(&workspace_edit).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "changes": {
+                "src/lib.rs": [{
+                    "range": { "start": { "line": 3, "character": 4 }, "end": { "line": 3, "character": 7 } },
+                    "newText": "count"
+                }]
+            }
+        }));
+        assert_eq!(serde_json::from_value::<WorkspaceEdit>(json).unwrap(), workspace_edit);
+    }
+
+    #[test]
+    fn test_edit_preview_diff() {
+        let source = "use std::fmt;\nuse std::io;\n\nfn main() {}\n";
+        let position = |line, character| Position { line, character };
+        // Utf-16 columns on 0-based lines, the way an LSP client sends them
+        let options = PositionOptions { position_encoding: PositionEncoding::Utf16, zero_based_lines: true, byte_offsets: false };
+        let edits = vec![
+            TextEdit {
+                range: Range { start: position(0, 0), end: position(2, 0), start_byte: None, end_byte: None },
+                new_text: "use std::{fmt, io};\n".to_string(),
+            },
+            TextEdit {
+                range: Range { start: position(3, 3), end: position(3, 7), start_byte: None, end_byte: None },
+                new_text: "run".to_string(),
+            },
+        ];
+
+        let updated = crate::edits::apply_edits(source, &edits, options).unwrap();
+        assert_eq!(updated, "use std::{fmt, io};\n\nfn run() {}\n");
+
+        let workspace_edit = WorkspaceEdit { changes: [("main.rs".to_string(), edits.clone())].into() };
+        let sources = [("main.rs".to_string(), source.to_string())].into();
+        let diff = crate::edits::preview_diff(&workspace_edit, &sources, options).unwrap();
+        assert_eq!(diff, "--- main.rs\n+++ main.rs\n@@ -1,4 +1,3 @@\n-use std::fmt;\n-use std::io;\n+use std::{fmt, io};\n \n-fn main() {}\n+fn run() {}\n");
+
+        let overlapping = vec![edits[0].clone(), edits[0].clone()];
+        assert!(crate::edits::apply_edits(source, &overlapping, options).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_organize_imports() {
+        let code = r#"use crate::config::Config;
+use std::io::Write;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+/// Only for the demo binary.
+use std::env;
+#[cfg(windows)]
+use std::os::windows::fs::MetadataExt;
+use std::collections::{BTreeMap, HashSet};
+
+pub fn report(out: &mut impl std::io::Write, config: &Config) -> fmt::Result {
+    let _: HashMap<String, BTreeMap<u8, u8>> = HashMap::new();
+    let _ = writeln!(out, "{:?}", config);
+    Ok(())
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(OrganizeImportsParams { path: "lib.rs".to_string(), preview: true, ..Default::default() });
+        let result = server.organize_imports(params).await.unwrap();
+        let result: EditResult = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // Unused `HashSet` is dropped; `Write` and `Serialize` are kept since they may be traits used only through methods
+        let edits = &result.edit.changes["lib.rs"];
+        assert_eq!(edits.len(), 1);
+        assert_eq!((edits[0].range.start.line, edits[0].range.end.line), (1, 11));
+        assert_eq!(edits[0].new_text, r#"use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::config::Config;
+
+/// Only for the demo binary.
+use std::env;
+#[cfg(windows)]
+use std::os::windows::fs::MetadataExt;
+"#);
+        assert!(result.diff.unwrap().starts_with("--- lib.rs\n+++ lib.rs\n"));
+
+        // Running it again on the organized file proposes nothing
+        let organized = crate::edits::apply_edits(code, edits, PositionOptions::default()).unwrap();
+        server.cache.insert("lib.rs".to_string(), organized).await;
+        let params = Parameters(OrganizeImportsParams { path: "lib.rs".to_string(), ..Default::default() });
+        let result = server.organize_imports(params).await.unwrap();
+        let result: EditResult = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(result.edit.changes.is_empty());
+        assert!(result.diff.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_organize_imports_keeps_possible_traits() {
+        let model = "pub struct Record;\npub struct Unused;\npub trait Helper { fn help(&self) {} }\n";
+        let code = r#"use sha2::{Digest, Sha256};
+use crate::model;
+use crate::model::Unused;
+use crate::model::Helper;
+use std::collections::HashMap;
+
+pub use model::Record;
+
+pub fn hash(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("model.rs".to_string(), model.to_string()).await;
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(OrganizeImportsParams { path: "lib.rs".to_string(), ..Default::default() });
+        let result = server.organize_imports(params).await.unwrap();
+        let result: EditResult = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // `Digest` is only used through `Sha256::digest` and `Helper` is a trait, so both stay;
+        // `model` is used by the `pub use`; the struct `Unused` and `HashMap` are provably unused
+        let edits = &result.edit.changes["lib.rs"];
+        assert_eq!(edits[0].new_text, r#"use sha2::{Digest, Sha256};
+
+use crate::model::{self, Helper};
+"#);
+    }
+
+    #[tokio::test]
+    async fn test_organize_imports_keeps_imported_parents() {
+        let code = r#"use crate::model;
+use model::Record;
+
+pub fn make() -> Record {
+    Record
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("model.rs".to_string(), "pub struct Record;\n".to_string()).await;
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(OrganizeImportsParams { path: "lib.rs".to_string(), ..Default::default() });
+        let result = server.organize_imports(params).await.unwrap();
+        let result: EditResult = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // `model` is only named by the other import, which still needs it
+        let edits = &result.edit.changes["lib.rs"];
+        assert_eq!(edits[0].new_text, "use model::Record;\n\nuse crate::model;\n");
+    }
+
+    #[tokio::test]
+    async fn test_suggest_derives() {
+        let code = r#"use std::sync::Mutex;
+
+#[derive(Debug)]
+pub struct Point { x: i32, y: i32 }
+
+#[derive(Debug, Clone)]
+pub struct Shared { lock: Mutex<u8> }
+
+pub struct Labeled<'a> { label: &'a str, at: Point, children: Vec<Labeled<'a>> }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode { #[default] Fast, Slow }
+
+impl Default for Point {
+    fn default() -> Self { Point { x: 0, y: 0 } }
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(SuggestDerivesParams::default());
+        let result = server.suggest_derives(params).await.unwrap();
+        let suggestions: Vec<DeriveSuggestion> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let traits = |reasons: &[DeriveReason]| reasons.iter().map(|r| r.trait_name.clone()).collect::<Vec<_>>();
+        let by_name = |name: &str| suggestions.iter().find(|s| s.name == name).unwrap();
+
+        // Default is implemented by hand, so only Clone and PartialEq are left for Point
+        let point = by_name("Point");
+        assert_eq!(traits(&point.derivable), ["Clone", "PartialEq"]);
+        assert!(point.blocked.is_empty());
+
+        let shared = by_name("Shared");
+        assert_eq!(traits(&shared.derivable), ["Default"]);
+        assert_eq!(shared.blocked[0].reason, "field `lock` of type `Mutex<u8>` holds `Mutex`, which doesn't implement PartialEq");
+
+        // The reference only blocks Default; `Point` isn't Clone or PartialEq yet, but the type's own name is fine
+        let labeled = by_name("Labeled");
+        assert_eq!(traits(&labeled.derivable), ["Debug"]);
+        assert_eq!(traits(&labeled.blocked), ["Clone", "PartialEq", "Default"]);
+        assert_eq!(labeled.blocked[0].reason, "field `at` of type `Point` holds `Point`, which doesn't derive or implement Clone");
+        assert_eq!(labeled.blocked[2].reason, "field `label` of type `&'a str` is a reference, which has no default");
+
+        let mode = by_name("Mode");
+        assert_eq!(mode.derivable, vec![DeriveReason { trait_name: "Default".to_string(), reason: "variant `Fast` is marked `#[default]`".to_string() }]);
+
+        let params = Parameters(SuggestDerivesParams { name: Some("Mode".to_string()), ..Default::default() });
+        let result = server.suggest_derives(params).await.unwrap();
+        let suggestions: Vec<DeriveSuggestion> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(suggestions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_index_workspace_diverging_functions() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"fn serve() {
+    loop {
+        for job in jobs() {
+            if job == 0 { break; }
+        }
+    }
+}
+
+fn poll() {
+    'outer: loop {
+        loop { break 'outer; }
+    }
+}
+
+fn fail(code: i32) -> ! {
+    std::process::exit(code)
+}
+
+fn usage() {
+    eprintln!("usage");
+    fail(2)
+}
+
+fn check(ok: bool) {
+    if ok { panic!("bad") } else { unreachable!() }
+}
+
+fn retry() -> u8 {
+    loop {
+        match try_once() { Ok(v) => return v, Err(_) => continue }
+    }
+}
+
+fn read_all() -> Result<(), Error> {
+    loop { next()?; }
+}
+
+fn run() -> u8 {
+    retry()
+}
+
+fn spin() {
+    loop { let _ = jobs().iter().map(|job| { return job; }); }
+}
+
+fn jobs() -> Vec<u8> { Vec::new() }
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(IndexWorkspaceParams {
+            root: Some(temp_dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        });
+        let result = server.index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // The inner `break` only leaves the `for`; `poll` breaks out through its label, `retry`
+        // and `read_all` leave through `return` and `?`, but a closure's `return` doesn't leave `spin`
+        assert_eq!(graphs.diverging_functions, vec!["check", "fail", "serve", "spin", "usage"]);
+        let run = graphs.function_info.iter().find(|f| f.name == "run").unwrap();
+        assert!(!run.diverges);
+        let usage = graphs.function_info.iter().find(|f| f.name == "usage").unwrap();
+        assert!(usage.diverges);
+        let poll = graphs.function_info.iter().find(|f| f.name == "poll").unwrap();
+        assert!(!poll.diverges);
+    }
+
+    #[tokio::test]
+    async fn test_get_source() {
+        let code = "pub struct Parser;\n\nimpl Parser {\n    /// Makes one.\n    pub fn new() -> Self {\n        Parser\n    }\n}\n\nfn new() {}\n";
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(GetSourceParams { name: Some("Parser::new".to_string()), ..Default::default() });
+        let result = server.get_source(params).await.unwrap();
+        let snippets: Vec<SourceSnippet> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].source, "/// Makes one.\n    pub fn new() -> Self {\n        Parser\n    }");
+        assert_eq!((snippets[0].range.start.line, snippets[0].range.end.line), (4, 7));
+        assert_eq!(snippets[0].range.start_byte, None);
+
+        // Without a container both `new`s match
+        let params = Parameters(GetSourceParams { name: Some("new".to_string()), ..Default::default() });
+        let result = server.get_source(params).await.unwrap();
+        let snippets: Vec<SourceSnippet> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(snippets.len(), 2);
+
+        // By line, the innermost item wins over the impl's type
+        let params = Parameters(GetSourceParams { path: Some("lib.rs".to_string()), line: Some(6), ..Default::default() });
+        let result = server.get_source(params).await.unwrap();
+        let snippets: Vec<SourceSnippet> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(snippets[0].container.as_deref(), Some("Parser"));
+        assert_eq!(snippets[0].kind, SymbolKind::Method);
+
+        let params = Parameters(GetSourceParams { name: Some("missing".to_string()), ..Default::default() });
+        assert!(server.get_source(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_diff_public_api() {
+        let old_dir = TempDir::new().unwrap();
+        let new_dir = TempDir::new().unwrap();
+        for (dir, lib, format, internal) in [
+            (
+                &old_dir,
+                "pub mod format;\nmod internal;\n\npub fn parse(input: &str) -> u32 { 0 }\n",
+                "pub enum Format { Json, Yaml }\n#[non_exhaustive]\npub enum Level { Low }\n/// Old docs.\npub fn render() {}\npub fn gone() {}\npub trait Codec { fn encode(&self); fn name(&self) -> u8 { 0 } }\n",
+                "pub fn helper() {}\n",
+            ),
+            (
+                &new_dir,
+                "pub mod format;\nmod internal;\n\npub fn parse(input: &str, strict: bool) -> u32 { 0 }\n",
+                "pub enum Format { Json, Toml }\n#[non_exhaustive]\npub enum Level { Low, High }\n/// New docs.\npub fn render() {}\npub fn added() {}\npub trait Codec { fn encode(&self); fn name(&self) -> u8; fn decode(&self); fn version(&self) -> u8 { 1 } }\n",
+                "pub fn renamed_helper() {}\n",
+            ),
+        ] {
+            std::fs::create_dir(dir.path().join("src")).unwrap();
+            std::fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+            std::fs::write(dir.path().join("src/format.rs"), format).unwrap();
+            std::fs::write(dir.path().join("src/internal.rs"), internal).unwrap();
+            // Only the library target is public API
+            std::fs::write(dir.path().join("src/main.rs"), format!("pub fn cli_{}() {{}}\n", lib.len())).unwrap();
+            std::fs::create_dir(dir.path().join("benches")).unwrap();
+            std::fs::write(dir.path().join("benches/bench.rs"), format!("pub fn bench_{}() {{}}\n", lib.len())).unwrap();
+        }
+
+        let server = MyServer::new();
+        let params = Parameters(DiffPublicApiParams {
+            old_root: old_dir.path().to_string_lossy().to_string(),
+            new_root: new_dir.path().to_string_lossy().to_string(),
+        });
+        let result = server.diff_public_api(params).await.unwrap();
+        let diff: PublicApiDiff = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(diff.required_bump, Some(SemverImpact::Major));
+
+        let summary: Vec<(SemverImpact, &str, &str)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.impact, change.name.as_str(), change.detail.as_str()))
+            .collect();
+        // Items of the private `internal` module aren't part of the API
+        assert_eq!(summary, vec![
+            (SemverImpact::Major, "crate::format::Codec::decode", "added without a default body"),
+            (SemverImpact::Major, "crate::format::Codec::name", "default body removed"),
+            (SemverImpact::Major, "crate::format::Format", "variant `Yaml` removed"),
+            (SemverImpact::Major, "crate::format::Format", "variant `Toml` added"),
+            (SemverImpact::Major, "crate::format::gone", "removed"),
+            (SemverImpact::Major, "crate::parse", "signature changed"),
+            (SemverImpact::Minor, "crate::format::Codec::version", "added"),
+            (SemverImpact::Minor, "crate::format::Level", "variant `High` added"),
+            (SemverImpact::Minor, "crate::format::added", "added"),
+            (SemverImpact::Patch, "crate::format::render", "docs changed"),
+        ]);
+        let parse = diff.changes.iter().find(|change| change.name == "crate::parse").unwrap();
+        assert_eq!(parse.old_signature.as_deref(), Some("pub fn parse(input: &str) -> u32"));
+        assert_eq!(parse.file, "src/lib.rs");
+
+        let params = Parameters(DiffPublicApiParams {
+            old_root: old_dir.path().join("missing").to_string_lossy().to_string(),
+            new_root: new_dir.path().to_string_lossy().to_string(),
+        });
+        assert!(server.diff_public_api(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compare_workspaces() {
+        let old_dir = TempDir::new().unwrap();
+        let new_dir = TempDir::new().unwrap();
+        for (dir, lib, util) in [
+            (&old_dir, "mod util;\n\nfn main() {}\nstruct Config;\n", "pub fn load() {}\nfn helper() {}\n"),
+            (&new_dir, "mod util;\n\nfn main() {}\nstruct Settings;\n", "pub fn load() {}\n"),
+        ] {
+            std::fs::create_dir(dir.path().join("src")).unwrap();
+            std::fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+            std::fs::write(dir.path().join("src/util.rs"), util).unwrap();
+        }
+
+        let server = MyServer::new();
+        let params = Parameters(CompareWorkspacesParams {
+            old_root: old_dir.path().to_string_lossy().to_string(),
+            new_root: new_dir.path().to_string_lossy().to_string(),
+        });
+        let result = server.compare_workspaces(params).await.unwrap();
+        let comparison: WorkspaceComparison = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!((comparison.added, comparison.removed, comparison.unchanged), (1, 2, 3));
+
+        let names = |symbols: &[QualifiedSymbol]| symbols.iter().map(|symbol| symbol.name.clone()).collect::<Vec<_>>();
+        assert_eq!(comparison.files.len(), 2);
+        let lib = &comparison.files[0];
+        assert_eq!(lib.file, "src/lib.rs");
+        assert_eq!(names(&lib.added), vec!["crate::Settings"]);
+        assert_eq!(names(&lib.removed), vec!["crate::Config"]);
+        assert_eq!(names(&lib.unchanged), vec!["crate::main", "crate::util"]);
+        let util = &comparison.files[1];
+        assert_eq!(names(&util.removed), vec!["crate::util::helper"]);
+        assert_eq!(util.removed[0].kind, SymbolKind::Fn);
+        assert_eq!(names(&util.unchanged), vec!["crate::util::load"]);
+    }
+
+    #[test]
+    fn test_model_display() {
+        let range = Range {
+            start: Position { line: 3, character: 8 },
+            end: Position { line: 3, character: 12 },
+            start_byte: None,
+            end_byte: None,
+        };
+        assert_eq!(range.start.to_string(), "3:8");
+        assert_eq!(range.to_string(), "3:8-3:12");
+
+        let mut diagnostic = Diagnostic {
+            message: "mismatched types".to_string(),
+            range: range.clone(),
+            severity: Severity::Error,
+            file: Some("src/lib.rs".to_string()),
+            code: Some("E0308".to_string()),
+        };
+        assert_eq!(diagnostic.to_string(), "src/lib.rs:3:8: error[E0308]: mismatched types");
+        diagnostic.file = None;
+        diagnostic.code = None;
+        diagnostic.severity = Severity::Warning;
+        assert_eq!(diagnostic.to_string(), "3:8: warning: mismatched types");
+
+        let code = "pub struct Parser;\n\nimpl Parser {\n    pub fn new() -> Self { Parser }\n}\n";
+        let symbols = crate::analyze_source("src/parser.rs", code).unwrap().symbols;
+        let rendered: Vec<String> = symbols.iter().map(ToString::to_string).collect();
+        assert_eq!(rendered, vec!["struct Parser at src/parser.rs:1:11", "method Parser::new at src/parser.rs:4:11"]);
+        assert_eq!(SymbolKind::TypeAlias.to_string(), "type_alias");
+    }
+
+    #[cfg(feature = "lsp")]
+    #[test]
+    fn test_lsp_conversions() {
+        let range = Range {
+            start: Position { line: 2, character: 4 },
+            end: Position { line: 2, character: 9 },
+            start_byte: Some(30),
+            end_byte: Some(35),
+        };
+        let lsp_range: lsp_types::Range = range.clone().into();
+        assert_eq!(lsp_range.start, lsp_types::Position { line: 2, character: 4 });
+        assert_eq!(lsp_range.end, lsp_types::Position { line: 2, character: 9 });
+
+        // Byte offsets don't survive the round trip
+        let back: Range = lsp_range.into();
+        assert_eq!(back, Range { start_byte: None, end_byte: None, ..range.clone() });
+
+        let diagnostic: lsp_types::Diagnostic = Diagnostic {
+            message: "unused variable".to_string(),
+            range,
+            severity: Severity::Info,
+            file: None,
+            code: Some("unused_variables".to_string()),
+        }
+        .into();
+        assert_eq!(diagnostic.severity, Some(lsp_types::DiagnosticSeverity::INFORMATION));
+        assert_eq!(diagnostic.code, Some(lsp_types::NumberOrString::String("unused_variables".to_string())));
+        assert_eq!(diagnostic.message, "unused variable");
+    }
+
+    #[test]
+    fn test_output_field_case() {
+        let range = Range {
+            start: Position { line: 1, character: 0 },
+            end: Position { line: 1, character: 4 },
+            start_byte: Some(0),
+            end_byte: Some(4),
+        };
+        let json = serde_json::to_value(&range).unwrap();
+        let key = if cfg!(feature = "camel-case") { "startByte" } else { "start_byte" };
+        assert_eq!(json[key], 0);
+
+        // Output models read back what they write, whichever case is enabled
+        let back: Range = serde_json::from_value(json).unwrap();
+        assert_eq!(back, range);
+    }
+
+    #[tokio::test]
+    async fn test_find_clone_hotspots() {
+        let code = r#"
+fn busy(a: &String, b: &Vec<u8>) {
+    let x = a.clone();
+    let y = b.clone();
+    let f = || a.clone();
+    fn inner(s: &String) -> String {
+        s.clone()
+    }
+    drop((x, y, f(), inner(a)));
+}
+
+fn calm(a: &String) -> String {
+    a.clone()
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("lib.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindCloneHotspotsParams { max_clones: Some(2), ..Default::default() });
+        let result = server.find_clone_hotspots(params).await.unwrap();
+        let hotspots: Vec<CloneHotspot> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        // The closure's clone counts toward `busy`, the nested function's doesn't
+        assert_eq!(hotspots.len(), 1);
+        assert_eq!(hotspots[0].function, "busy");
+        assert_eq!(hotspots[0].clones, 3);
+        assert_eq!(hotspots[0].range.start.line, 2);
+        let lines: Vec<usize> = hotspots[0].sites.iter().map(|site| site.start.line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+
+        let params = Parameters(FindCloneHotspotsParams { max_clones: Some(0), ..Default::default() });
+        let result = server.find_clone_hotspots(params).await.unwrap();
+        let hotspots: Vec<CloneHotspot> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let functions: Vec<&str> = hotspots.iter().map(|hotspot| hotspot.function.as_str()).collect();
+        assert_eq!(functions, vec!["busy", "inner", "calm"]);
+    }
+
+    #[tokio::test]
+    async fn test_generic_function_metrics() {
+        let temp_dir = TempDir::new().unwrap();
+        let code = r#"
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::BuildHasher;
+
+fn merge<K, V, S, const N: usize>(items: [(K, V); N], hasher: S) -> HashMap<K, V, S>
+where
+    K: Eq + std::hash::Hash,
+    V: Clone + Default,
+    S: BuildHasher,
+{
+    items.into_iter().fold(HashMap::with_hasher(hasher), |mut map, (k, v)| {
+        map.insert(k, v);
+        map
+    })
+}
+
+fn show<'a, T: Display + ?Sized>(value: &'a T) -> String {
+    value.to_string()
+}
+"#;
+        std::fs::write(temp_dir.path().join("lib.rs"), code).unwrap();
+
+        let params = Parameters(IndexWorkspaceParams { root: Some(temp_dir.path().to_str().unwrap().to_string()), ..Default::default() });
+        let result = MyServer::new().index_workspace(params).await.unwrap();
+        let graphs: WorkspaceGraphs = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        let merge = graphs.function_info.iter().find(|func| func.name == "merge").unwrap();
+        assert_eq!((merge.generic_param_count, merge.where_predicate_count), (4, 3));
+        assert_eq!(merge.trait_bounds, vec!["K: Eq", "K: std::hash::Hash", "V: Clone", "V: Default", "S: BuildHasher"]);
+
+        // Lifetimes don't count as generic parameters
+        let show = graphs.function_info.iter().find(|func| func.name == "show").unwrap();
+        assert_eq!((show.generic_param_count, show.where_predicate_count), (1, 0));
+        assert_eq!(show.trait_bounds, vec!["T: Display", "T: ?Sized"]);
+
+        let generic: Vec<_> = graphs.refactoring_suggestions.iter().filter(|s| s.kind == RefactoringKind::ComplexGenerics).collect();
+        assert_eq!(generic.len(), 1);
+        assert_eq!(generic[0].target, "merge");
+        assert_eq!((generic[0].metric, generic[0].threshold), (7, 4));
+    }
+
+    #[tokio::test]
+    async fn test_largest_files() {
+        let server = MyServer::new();
+        let many_items: String = (0..4).map(|n| format!("fn f{}() {{}}\n", n)).collect();
+        server.cache.insert("items.rs".to_string(), many_items).await;
+        server.cache.insert("long.rs".to_string(), "struct S;\n\n\n\n\n\nimpl S {\n    fn new() -> Self { S }\n}\n".to_string()).await;
+        server.cache.insert("small.rs".to_string(), "const X: u8 = 1;\n".to_string()).await;
+
+        let params = Parameters(LargestFilesParams { max_lines: Some(8), max_items: Some(3), ..Default::default() });
+        let result = server.largest_files(params).await.unwrap();
+        let sizes: Vec<FileSize> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let summary: Vec<(&str, usize, usize, bool)> = sizes
+            .iter()
+            .map(|size| (size.file.as_str(), size.line_count, size.item_count, size.oversized))
+            .collect();
+        // The struct and its method both count as items
+        assert_eq!(summary, vec![
+            ("long.rs", 9, 2, true),
+            ("items.rs", 4, 4, true),
+            ("small.rs", 1, 1, false),
+        ]);
+
+        let params = Parameters(LargestFilesParams { limit: Some(1), ..Default::default() });
+        let result = server.largest_files(params).await.unwrap();
+        let sizes: Vec<FileSize> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(sizes.len(), 1);
+        assert!(!sizes[0].oversized);
+    }
+
+    #[tokio::test]
+    async fn test_goto_definition_at_position() {
+        let lib = "mod net;\n\nfn helper() {}\n\nfn main() {\n    net::connect();\n    println!(\"{:?}\", helper());\n}\n";
+        let net = "pub fn connect() { helper(); }\n\nfn helper() {}\n";
+        let server = MyServer::new();
+        server.cache.insert("src/lib.rs".to_string(), lib.to_string()).await;
+        server.cache.insert("src/net.rs".to_string(), net.to_string()).await;
+
+        let goto = |file: &str, line: usize, character: usize, position_options: PositionOptions| {
+            let params = Parameters(GotoDefinitionParams {
+                file: Some(file.to_string()),
+                position: Some(Position { line, character }),
+                position_options,
+                ..Default::default()
+            });
+            let server = &server;
+            async move {
+                let result = server.goto_definition(params).await?;
+                let symbols: Vec<SymbolInfo> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+                Ok::<_, rmcp::ErrorData>(symbols.into_iter().map(|sym| (sym.file, sym.range.start.line)).collect::<Vec<_>>())
+            }
+        };
+
+        // On the last segment of a path, and right at the end of the identifier
+        assert_eq!(goto("src/lib.rs", 6, 9, PositionOptions::default()).await.unwrap(), vec![("src/net.rs".to_string(), 1)]);
+        assert_eq!(goto("src/lib.rs", 6, 16, PositionOptions::default()).await.unwrap(), vec![("src/net.rs".to_string(), 1)]);
+
+        // Inside a macro, the definition in the cursor's own module comes first
+        let zero_based = PositionOptions { zero_based_lines: true, ..Default::default() };
+        assert_eq!(goto("src/lib.rs", 6, 22, zero_based).await.unwrap(), vec![
+            ("src/lib.rs".to_string(), 2),
+            ("src/net.rs".to_string(), 2),
+        ]);
+        assert_eq!(goto("src/net.rs", 1, 20, PositionOptions::default()).await.unwrap(), vec![
+            ("src/net.rs".to_string(), 3),
+            ("src/lib.rs".to_string(), 3),
+        ]);
+
+        assert!(goto("src/lib.rs", 2, 0, PositionOptions::default()).await.is_err());
+        assert!(server.goto_definition(Parameters(GotoDefinitionParams::default())).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_references_at_position() {
+        let code = "fn total(values: &[u32]) -> u32 {\n    values.iter().sum()\n}\n\nfn main() {\n    let values = vec![1, 2];\n    total(&values);\n}\n";
+        let server = MyServer::new();
+        server.cache.insert("main.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindReferencesParams {
+            file: Some("main.rs".to_string()),
+            position: Some(Position { line: 7, character: 6 }),
+            ..Default::default()
+        });
+        let result = server.find_references(params).await.unwrap();
+        let refs: Vec<ReferenceLocation> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let lines: Vec<usize> = refs.iter().map(|location| location.range.start.line).collect();
+        assert_eq!(lines, vec![1, 7]);
+
+        // Without a position the name is still required
+        let params = Parameters(FindReferencesParams { file: Some("main.rs".to_string()), ..Default::default() });
+        assert!(server.find_references(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_detect_long_chains() {
+        let code = r#"
+async fn load(client: &Client, ids: &[u32]) -> Result<Vec<String>, Error> {
+    let names = ids.iter().map(|id| id.to_string().trim().to_lowercase().to_owned()).collect::<Vec<_>>();
+    let body = client.get(URL).header(KEY, names.join(",")).send().await?.error_for_status()?.text().await?;
+    Ok(vec![body])
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("chains.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(DetectLongChainsParams { max_length: Some(3), ..Default::default() });
+        let result = server.detect_long_chains(params).await.unwrap();
+        let chains: Vec<MethodChain> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // The chain inside the closure is measured on its own, the one around it stays short;
+        // `?` and `.await` don't break a chain
+        let summary: Vec<(usize, usize)> = chains.iter().map(|chain| (chain.range.start.line, chain.length)).collect();
+        assert_eq!(summary, vec![(3, 4), (4, 5)]);
+        assert_eq!(chains[0].methods, vec!["to_string", "trim", "to_lowercase", "to_owned"]);
+        assert_eq!(chains[1].methods, vec!["get", "header", "send", "error_for_status", "text"]);
+        assert_eq!(chains[1].range.start.character, 15);
+        assert!(chains.iter().all(|chain| chain.function == "load"));
+
+        let result = server.detect_long_chains(Parameters(DetectLongChainsParams::default())).await.unwrap();
+        let chains: Vec<MethodChain> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(chains.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_large_returns() {
+        let types = "pub struct Report { a: u8, b: u8, c: u8, d: u8, e: u8, f: u8, g: u8, h: u8 }\npub struct Small { a: u8 }\n";
+        let code = r#"
+impl Report {
+    pub fn new() -> Self { todo!() }
+    pub fn rows(&self) -> Vec<u8> { todo!() }
+    pub fn small(&self) -> Small { todo!() }
+}
+
+fn load(path: &str) -> Result<HashMap<String, u8>, Error> { todo!() }
+fn count() -> usize { 0 }
+fn boxed() -> Box<Vec<u8>> { todo!() }
+"#;
+        let server = MyServer::new();
+        server.cache.insert("types.rs".to_string(), types.to_string()).await;
+        server.cache.insert("report.rs".to_string(), code.to_string()).await;
+
+        let result = server.find_large_returns(Parameters(FindLargeReturnsParams::default())).await.unwrap();
+        let returns: Vec<LargeReturn> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        let summary: Vec<(&str, &str, LargeReturnKind, bool)> = returns
+            .iter()
+            .map(|ret| (ret.function.as_str(), ret.return_type.as_str(), ret.kind, ret.borrows_input))
+            .collect();
+        // `Self` resolves to the impl type; boxed values are already on the heap
+        assert_eq!(summary, vec![
+            ("new", "Self", LargeReturnKind::LargeStruct, false),
+            ("rows", "Vec<u8>", LargeReturnKind::Collection, true),
+            ("load", "Result<HashMap<String, u8>, Error>", LargeReturnKind::Collection, true),
+        ]);
+        assert_eq!(returns[0].container.as_deref(), Some("Report"));
+        assert_eq!(returns[2].container, None);
+
+        let params = Parameters(FindLargeReturnsParams { min_struct_fields: Some(1), ..Default::default() });
+        let result = server.find_large_returns(params).await.unwrap();
+        let returns: Vec<LargeReturn> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(returns.iter().any(|ret| ret.function == "small"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_early_returns() {
+        let code = r#"
+fn classify(n: i32) -> &'static str {
+    if n < 0 { return "negative"; }
+    if n == 0 { return "zero"; }
+    let check = |x: i32| { if x > 9 { return true; } false };
+    if check(n) { return "large"; }
+    return "small";
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<u8> {
+        fn helper() -> u8 { return 1; }
+        if self.done { return None; }
+        if self.skip { return None; }
+        if self.eof { return None; }
+        let task = async { return 2; };
+        Some(helper())
+    }
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("returns.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(DetectEarlyReturnsParams { max_returns: Some(2), ..Default::default() });
+        let result = server.detect_early_returns(params).await.unwrap();
+        let functions: Vec<EarlyReturns> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        // The tail `return "small"` and returns in the closure, async block, and nested fn don't count
+        let summary: Vec<(&str, usize)> = functions.iter().map(|f| (f.function.as_str(), f.count)).collect();
+        assert_eq!(summary, vec![("classify", 3), ("next", 3)]);
+        let lines: Vec<usize> = functions[0].returns.iter().map(|range| range.start.line).collect();
+        assert_eq!(lines, vec![3, 4, 6]);
+        assert_eq!(functions[0].range.start, Position { line: 2, character: 3 });
+
+        let result = server.detect_early_returns(Parameters(DetectEarlyReturnsParams::default())).await.unwrap();
+        let functions: Vec<EarlyReturns> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert!(functions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_nested_wrappers() {
+        let code = r#"
+pub struct Cache {
+    entries: Vec<Option<Option<Option<u8>>>>,
+    last: Result<Option<u8>, Error>,
+}
+
+pub enum Event {
+    Loaded(Option<Result<Option<String>, Error>>),
+}
+
+impl Cache {
+    pub fn lookup(&self, key: &Option<Option<Option<u8>>>) -> Result<Option<Result<u8, Error>>, Error> { todo!() }
+}
+
+pub trait Store {
+    fn get(&self) -> io::Result<Option<u8>>;
+}
+"#;
+        let server = MyServer::new();
+        server.cache.insert("wrappers.rs".to_string(), code.to_string()).await;
+
+        let params = Parameters(FindNestedWrappersParams::default());
+        let result = server.find_nested_wrappers(params).await.unwrap();
+        let wrappers: Vec<NestedWrapper> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        let summary: Vec<(&str, Option<&str>, WrapperSite, usize)> = wrappers
+            .iter()
+            .map(|w| (w.item.as_str(), w.container.as_deref(), w.site, w.depth))
+            .collect();
+        assert_eq!(summary, vec![
+            ("entries", Some("Cache"), WrapperSite::Field, 3),
+            ("0", Some("Event::Loaded"), WrapperSite::Field, 3),
+            ("lookup", Some("Cache"), WrapperSite::Parameter, 3),
+            ("lookup", Some("Cache"), WrapperSite::Return, 3),
+        ]);
+        // The nesting inside `Vec` is reported at its outermost wrapper
+        assert_eq!(wrappers[0].type_text, "Option<Option<Option<u8>>>");
+        assert_eq!(wrappers[0].range.start, Position { line: 3, character: 17 });
+        assert_eq!(wrappers[3].type_text, "Result<Option<Result<u8, Error>>, Error>");
+
+        let params = Parameters(FindNestedWrappersParams { max_depth: Some(1), ..Default::default() });
+        let result = server.find_nested_wrappers(params).await.unwrap();
+        let wrappers: Vec<NestedWrapper> = serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+        assert_eq!(wrappers.len(), 6);
+        assert!(wrappers.iter().any(|w| w.container.as_deref() == Some("Store") && w.type_text == "io::Result<Option<u8>>"));
+    }
+
+    #[tokio::test]
+    async fn test_check_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = |name: &str| temp_dir.path().join(name).to_string_lossy().to_string();
+        std::fs::write(path("good.rs"), "fn ok() {}\n").unwrap();
+        std::fs::write(path("bad.rs"), "fn broken( {}\n").unwrap();
+
+        let server = MyServer::new();
+        let params = Parameters(CheckFilesParams {
+            paths: vec![path("good.rs"), path("bad.rs"), path("missing.rs")],
+            ..Default::default()
+        });
+        let result = server.check_files(params).await.unwrap();
+        let results: std::collections::HashMap<String, Vec<Diagnostic>> =
+            serde_json::from_str(&result.content[0].as_text().unwrap().text).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[&path("good.rs")].is_empty());
+        assert_eq!(results[&path("bad.rs")][0].severity, Severity::Error);
+        assert_eq!(results[&path("bad.rs")][0].range.start.line, 1);
+        assert!(results[&path("missing.rs")][0].message.starts_with("Failed to read file"));
+
+        // Only the file that parsed is cached
+        assert!(server.cache.get(&path("good.rs")).await.is_some());
+        assert!(server.cache.get(&path("bad.rs")).await.is_none());
+    }
+}
@@ -0,0 +1,126 @@
+use proc_macro2::{LineColumn, Span};
+use crate::models::{Position, PositionEncoding, PositionOptions, Range};
+
+/// Maps proc-macro2 line/column locations back to the source text, so positions can be
+/// re-encoded for the client and resolved to byte offsets.
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    options: PositionOptions,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str, options: PositionOptions) -> Self {
+        // syn skips a leading byte order mark, so columns on the first line start after it
+        let mut line_starts = vec![if source.starts_with('\u{feff}') { '\u{feff}'.len_utf8() } else { 0 }];
+        line_starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+        Self { source, line_starts, options }
+    }
+
+    /// Start offset and text of a 1-based line, without its `\n` or `\r\n` terminator, so CRLF
+    /// and LF files resolve to the same columns.
+    fn line_text(&self, line: usize) -> Option<(usize, &'a str)> {
+        let line_start = *self.line_starts.get(line.saturating_sub(1))?;
+        let line_end = self.line_starts.get(line).copied().unwrap_or(self.source.len());
+        let text = &self.source[line_start..line_end];
+        let text = text.strip_suffix('\n').map(|text| text.strip_suffix('\r').unwrap_or(text)).unwrap_or(text);
+        Some((line_start, text))
+    }
+
+    /// Byte offset of a 1-based line and 0-based char column, clamped to the end of that line.
+    pub fn byte_offset(&self, location: LineColumn) -> usize {
+        let Some((line_start, line)) = self.line_text(location.line) else {
+            return self.source.len();
+        };
+        line_start + line.char_indices().nth(location.column).map(|(i, _)| i).unwrap_or(line.len())
+    }
+
+    /// Source text between two locations.
+    pub fn text(&self, start: LineColumn, end: LineColumn) -> &'a str {
+        let start = self.byte_offset(start);
+        let end = self.byte_offset(end).max(start);
+        &self.source[start..end]
+    }
+
+    /// Re-encodes the char-based column reported by proc-macro2 into the requested encoding.
+    pub fn position(&self, location: LineColumn) -> Position {
+        let character = match (self.options.position_encoding, self.line_text(location.line)) {
+            (PositionEncoding::Utf32, _) | (_, None) => location.column,
+            (PositionEncoding::Utf8, Some((_, line))) => line.chars().take(location.column).map(char::len_utf8).sum(),
+            (PositionEncoding::Utf16, Some((_, line))) => line.chars().take(location.column).map(char::len_utf16).sum(),
+        };
+        let line = if self.options.zero_based_lines { location.line.saturating_sub(1) } else { location.line };
+        Position { line, character }
+    }
+
+    /// Byte offset of a position encoded per the index's options; the inverse of `position`.
+    /// Positions past the end of their line are clamped to it.
+    pub fn position_offset(&self, position: &Position) -> usize {
+        let line = if self.options.zero_based_lines { position.line + 1 } else { position.line };
+        let Some((line_start, text)) = self.line_text(line) else {
+            return self.source.len();
+        };
+        let mut units = 0;
+        for (i, c) in text.char_indices() {
+            if units >= position.character {
+                return line_start + i;
+            }
+            units += match self.options.position_encoding {
+                PositionEncoding::Utf32 => 1,
+                PositionEncoding::Utf8 => c.len_utf8(),
+                PositionEncoding::Utf16 => c.len_utf16(),
+            };
+        }
+        line_start + text.len()
+    }
+
+    pub fn range(&self, span: Span) -> Range {
+        self.location_range(span.start(), span.end())
+    }
+
+    /// Same as `range`, for locations that don't come from a proc-macro2 span.
+    pub fn location_range(&self, start: LineColumn, end: LineColumn) -> Range {
+        Range {
+            start: self.position(start),
+            end: self.position(end),
+            start_byte: self.options.byte_offsets.then(|| self.byte_offset(start)),
+            end_byte: self.options.byte_offsets.then(|| self.byte_offset(end)),
+        }
+    }
+}
+
+impl PositionOptions {
+    /// Builds a line index for `source`, or `None` when the defaults need no source lookups.
+    pub fn line_index<'a>(&self, source: &'a str) -> Option<LineIndex<'a>> {
+        (!self.is_default()).then(|| LineIndex::new(source, *self))
+    }
+
+    /// Whether positions can be reported as-is, without looking at the source.
+    pub fn is_default(&self) -> bool {
+        !self.byte_offsets
+            && !self.zero_based_lines
+            && self.position_encoding == PositionEncoding::Utf32
+    }
+}
+
+/// Converts a span into a model `Range`, applying the line index's position options when given.
+///
+/// Every visitor and tool goes through this helper so the line/column convention documented
+/// on `Position` is applied consistently.
+pub fn span_to_range(span: Span, line_index: Option<&LineIndex>) -> Range {
+    locations_to_range(span.start(), span.end(), line_index)
+}
+
+/// Same as `span_to_range`, for locations that don't come from a proc-macro2 span.
+pub fn locations_to_range(start: LineColumn, end: LineColumn, line_index: Option<&LineIndex>) -> Range {
+    if let Some(line_index) = line_index {
+        return line_index.location_range(start, end);
+    }
+
+    Range {
+        start: Position { line: start.line, character: start.column },
+        end: Position { line: end.line, character: end.column },
+        start_byte: None,
+        end_byte: None,
+    }
+}
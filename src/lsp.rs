@@ -0,0 +1,59 @@
+//! Conversions between the model types and their `lsp-types` counterparts, enabled by the
+//! `lsp` feature.
+//!
+//! Fields are copied as they are: request ranges with `zero_based_lines` and a `utf16`
+//! `position_encoding` to get the coordinates LSP clients expect.
+
+use crate::models::{Diagnostic, Position, Range, Severity};
+
+impl From<Position> for lsp_types::Position {
+    fn from(position: Position) -> Self {
+        lsp_types::Position {
+            line: u32::try_from(position.line).unwrap_or(u32::MAX),
+            character: u32::try_from(position.character).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+impl From<lsp_types::Position> for Position {
+    fn from(position: lsp_types::Position) -> Self {
+        Position { line: position.line as usize, character: position.character as usize }
+    }
+}
+
+/// Byte offsets have no LSP equivalent and are dropped.
+impl From<Range> for lsp_types::Range {
+    fn from(range: Range) -> Self {
+        lsp_types::Range { start: range.start.into(), end: range.end.into() }
+    }
+}
+
+impl From<lsp_types::Range> for Range {
+    fn from(range: lsp_types::Range) -> Self {
+        Range { start: range.start.into(), end: range.end.into(), start_byte: None, end_byte: None }
+    }
+}
+
+impl From<Severity> for lsp_types::DiagnosticSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
+            Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+            Severity::Info => lsp_types::DiagnosticSeverity::INFORMATION,
+            Severity::Hint => lsp_types::DiagnosticSeverity::HINT,
+        }
+    }
+}
+
+/// The file isn't part of an LSP diagnostic; clients publish diagnostics per document.
+impl From<Diagnostic> for lsp_types::Diagnostic {
+    fn from(diagnostic: Diagnostic) -> Self {
+        lsp_types::Diagnostic {
+            range: diagnostic.range.into(),
+            severity: Some(diagnostic.severity.into()),
+            code: diagnostic.code.map(lsp_types::NumberOrString::String),
+            message: diagnostic.message,
+            ..Default::default()
+        }
+    }
+}
@@ -1,131 +1,100 @@
-mod models;
-mod visitors;
-mod cache;
-mod tools;
-
-use anyhow::Result;
-use cache::MyServer;
+use anyhow::{bail, Context, Result};
+use rust_mcp_server::{AstCache, CacheCapacity, MyServer};
 use rmcp::ServiceExt;
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let server = MyServer::new();
-    let service = server.serve(rmcp::transport::stdio()).await?;
-    service.waiting().await?;
-    Ok(())
+use std::net::SocketAddr;
+use tracing_subscriber::EnvFilter;
+
+/// How the server talks to its clients.
+enum Transport {
+    /// A single client over stdin/stdout (the default).
+    Stdio,
+    /// Any number of clients over streamable HTTP (`--http <host:port>`), served at `/mcp`.
+    Http(SocketAddr),
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::{NamedTempFile, TempDir};
-    use std::io::Write;
-    use rmcp::handler::server::wrapper::Parameters;
-    use crate::models::*;
-
-    #[tokio::test]
-    async fn test_check_file_valid() {
-        let mut temp_file = NamedTempFile::new().unwrap();
-        let valid_code = r#"fn main() {
-    println!("Hello, world!");
-}"#;
-        temp_file.write_all(valid_code.as_bytes()).unwrap();
-        let path = temp_file.path().to_string_lossy().to_string();
-
-        let server = MyServer::new();
-        let params = Parameters(CheckFileParams { path });
-        let result = server.check_file(params).await.unwrap();
-
-        assert_eq!(result.content.len(), 1);
-    }
-
-    #[tokio::test]
-    async fn test_check_file_invalid() {
-        let mut temp_file = NamedTempFile::new().unwrap();
-        let invalid_code = r#"fn main() {
-    println!("Hello, world!" // missing )
-}"#;
-        temp_file.write_all(invalid_code.as_bytes()).unwrap();
-        let path = temp_file.path().to_string_lossy().to_string();
-
-        let server = MyServer::new();
-        let params = Parameters(CheckFileParams { path });
-        let result = server.check_file(params).await.unwrap();
-
-        assert_eq!(result.content.len(), 1);
-    }
-
-    #[tokio::test]
-    async fn test_goto_definition() {
-        let mut temp_file = NamedTempFile::new().unwrap();
-        let code = r#"fn foo() {
-    println!("foo");
+/// Command-line options.
+struct Args {
+    transport: Transport,
+    /// Set by `--cache-max-entries` or `--cache-max-bytes`; the cache is unbounded otherwise.
+    cache_capacity: Option<CacheCapacity>,
 }
 
-fn main() {
-    foo();
-}"#;
-        temp_file.write_all(code.as_bytes()).unwrap();
-        let path = temp_file.path().to_string_lossy().to_string();
-
-        let server = MyServer::new();
-        // First, check_file to cache the code
-        let params_check = Parameters(CheckFileParams { path: path.clone() });
-        server.check_file(params_check).await.unwrap();
-
-        // Now, goto_definition for "foo"
-        let params_goto = Parameters(GotoDefinitionParams { name: "foo".to_string() });
-        let result = server.goto_definition(params_goto).await.unwrap();
-
-        assert_eq!(result.content.len(), 1);
-        // Should have found the definition
+#[tokio::main]
+async fn main() -> Result<()> {
+    // stdout carries the MCP protocol, so logs must go to stderr
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("rust_mcp_server=info,warn")))
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let args = parse_args(std::env::args().skip(1))?;
+    let server = match args.cache_capacity {
+        Some(capacity) => MyServer::with_cache(AstCache::with_capacity(capacity)),
+        None => MyServer::new(),
+    };
+    match args.transport {
+        Transport::Stdio => serve_stdio(server).await,
+        Transport::Http(addr) => serve_http(server, addr).await,
     }
+}
 
-    #[tokio::test]
-    async fn test_index_workspace() {
-        let temp_dir = TempDir::new().unwrap();
-        let dir_path = temp_dir.path().to_string_lossy().to_string();
-
-        // Create some Rust files in the temp dir
-        let file1_path = temp_dir.path().join("lib.rs");
-        let mut file1 = std::fs::File::create(&file1_path).unwrap();
-        file1.write_all(b"fn foo() {}\n").unwrap();
-
-        let file2_path = temp_dir.path().join("main.rs");
-        let mut file2 = std::fs::File::create(&file2_path).unwrap();
-        file2.write_all(b"fn main() { foo(); }\n").unwrap();
-
-        let server = MyServer::new();
-        let params = Parameters(IndexWorkspaceParams { root: dir_path });
-        let result = server.index_workspace(params).await.unwrap();
-
-        assert_eq!(result.content.len(), 1);
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args> {
+    let mut parsed = Args { transport: Transport::Stdio, cache_capacity: None };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--http" => {
+                let addr = args.next().context("--http needs an address, e.g. --http 127.0.0.1:8080")?;
+                parsed.transport = Transport::Http(addr.parse().with_context(|| format!("invalid --http address `{}`", addr))?);
+            }
+            "--cache-max-entries" | "--cache-max-bytes" => {
+                let value = args.next().with_context(|| format!("{} needs a number", arg))?;
+                let limit = value.parse().with_context(|| format!("invalid {} value `{}`", arg, value))?;
+                parsed.cache_capacity = Some(if arg == "--cache-max-entries" {
+                    CacheCapacity::Entries(limit)
+                } else {
+                    CacheCapacity::Bytes(limit)
+                });
+            }
+            _ => bail!(
+                "unknown argument `{}`; usage: rust-mcp-server [--http <host:port>] [--cache-max-entries <n> | --cache-max-bytes <n>]",
+                arg
+            ),
+        }
     }
+    Ok(parsed)
+}
 
-    #[tokio::test]
-    async fn test_find_references() {
-        let temp_dir = TempDir::new().unwrap();
-        let dir_path = temp_dir.path().to_string_lossy().to_string();
-
-        // Create Rust files
-        let file1_path = temp_dir.path().join("lib.rs");
-        let mut file1 = std::fs::File::create(&file1_path).unwrap();
-        file1.write_all(b"fn foo() {}\n").unwrap();
-
-        let file2_path = temp_dir.path().join("main.rs");
-        let mut file2 = std::fs::File::create(&file2_path).unwrap();
-        file2.write_all(b"fn main() { foo(); }\n").unwrap();
-
-        let server = MyServer::new();
-        // Index first
-        let params_index = Parameters(IndexWorkspaceParams { root: dir_path });
-        server.index_workspace(params_index).await.unwrap();
+async fn serve_stdio(server: MyServer) -> Result<()> {
+    let service = server.serve(rmcp::transport::stdio()).await?;
+    service.waiting().await?;
+    Ok(())
+}
 
-        // Now find references for "foo"
-        let params_find = Parameters(FindReferencesParams { name: "foo".to_string() });
-        let result = server.find_references(params_find).await.unwrap();
+/// Every HTTP session gets a clone of `server`, so all clients share one cache and index.
+#[cfg(feature = "http")]
+async fn serve_http(server: MyServer, addr: SocketAddr) -> Result<()> {
+    use rmcp::transport::streamable_http_server::{
+        session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+    };
+
+    let service = StreamableHttpService::new(
+        move || Ok(server.clone()),
+        LocalSessionManager::default().into(),
+        StreamableHttpServerConfig::default(),
+    );
+    let router = axum::Router::new().nest_service("/mcp", service);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!(%addr, "serving MCP over HTTP at /mcp");
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
 
-        assert_eq!(result.content.len(), 1);
-        // Should have found references
-    }
-}
\ No newline at end of file
+#[cfg(not(feature = "http"))]
+async fn serve_http(_server: MyServer, _addr: SocketAddr) -> Result<()> {
+    bail!("--http needs a build with the `http` feature: cargo build --features http")
+}
@@ -0,0 +1,193 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::models::{Dependency, DependencyKind};
+
+/// The parts of a `Cargo.toml` needed to list dependencies.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct Manifest {
+    package: Option<Package>,
+    workspace: Option<Workspace>,
+    lib: Option<LibTarget>,
+    dependencies: BTreeMap<String, DependencySpec>,
+    dev_dependencies: BTreeMap<String, DependencySpec>,
+    build_dependencies: BTreeMap<String, DependencySpec>,
+    target: BTreeMap<String, Target>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Package {
+    name: Option<String>,
+    /// Explicit path to the workspace root, overriding the upward search.
+    workspace: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LibTarget {
+    /// Root file of the library, relative to the manifest; `src/lib.rs` when unset.
+    path: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Workspace {
+    dependencies: BTreeMap<String, DependencySpec>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct Target {
+    dependencies: BTreeMap<String, DependencySpec>,
+    dev_dependencies: BTreeMap<String, DependencySpec>,
+    build_dependencies: BTreeMap<String, DependencySpec>,
+}
+
+/// `name = "1.0"` or `name = { ... }`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum DependencySpec {
+    Version(String),
+    Detailed(DetailedSpec),
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct DetailedSpec {
+    version: Option<String>,
+    path: Option<String>,
+    git: Option<String>,
+    package: Option<String>,
+    features: Vec<String>,
+    optional: bool,
+    default_features: Option<bool>,
+    workspace: bool,
+}
+
+impl DependencySpec {
+    fn into_detailed(self) -> DetailedSpec {
+        match self {
+            DependencySpec::Version(version) => DetailedSpec { version: Some(version), ..Default::default() },
+            DependencySpec::Detailed(detailed) => detailed,
+        }
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<Manifest, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Root file of the library target of the package in `package_dir`: the `[lib]` table's
+/// `path` in its `Cargo.toml`, or else `src/lib.rs`. `None` when that file doesn't exist.
+pub fn library_root(package_dir: &Path) -> Result<Option<PathBuf>, String> {
+    let manifest_path = package_dir.join("Cargo.toml");
+    let manifest = match manifest_path.is_file() {
+        true => read_manifest(&manifest_path)?,
+        false => Manifest::default(),
+    };
+    let path = manifest.lib.and_then(|lib| lib.path).unwrap_or_else(|| "src/lib.rs".to_string());
+    let root = package_dir.join(path);
+    Ok(root.is_file().then_some(root))
+}
+
+/// `[package] name` of the `Cargo.toml` in `package_dir`, if it has one and it parses.
+pub fn package_name(package_dir: &Path) -> Option<String> {
+    let manifest_path = package_dir.join("Cargo.toml");
+    if !manifest_path.is_file() {
+        return None;
+    }
+    read_manifest(&manifest_path).ok()?.package?.name
+}
+
+/// Finds the workspace root manifest for the package at `manifest_path`: the one named by
+/// `package.workspace`, or else the closest ancestor `Cargo.toml` with a `[workspace]` table.
+fn find_workspace_root(manifest_path: &Path, package: Option<&Package>) -> Result<Option<(PathBuf, Manifest)>, String> {
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    if let Some(workspace) = package.and_then(|package| package.workspace.as_deref()) {
+        let root = manifest_dir.join(workspace).join("Cargo.toml");
+        return read_manifest(&root).map(|manifest| Some((root, manifest)));
+    }
+
+    for dir in manifest_dir.ancestors().skip(1) {
+        let candidate = dir.join("Cargo.toml");
+        if candidate.is_file() {
+            let manifest = read_manifest(&candidate)?;
+            if manifest.workspace.is_some() {
+                return Ok(Some((candidate, manifest)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Lists the normal, dev and build dependencies of the manifest at `manifest_path`, including
+/// platform-specific ones, resolving `workspace = true` entries against the workspace root.
+///
+/// Dependencies are ordered by kind, then by platform-specific or not, then by name. `path`
+/// dependencies are resolved against the directory of the manifest that declares them.
+pub fn dependencies(manifest_path: &Path) -> Result<Vec<Dependency>, String> {
+    let manifest = read_manifest(manifest_path)?;
+    let manifest_dir = manifest_path.parent().unwrap_or(Path::new("."));
+
+    // A workspace root inherits from its own [workspace.dependencies]
+    let found_root = match manifest.workspace {
+        Some(_) => None,
+        None => find_workspace_root(manifest_path, manifest.package.as_ref())?,
+    };
+    let (root_path, root_dependencies) = match (&manifest.workspace, &found_root) {
+        (Some(workspace), _) => (manifest_path, Some(&workspace.dependencies)),
+        (None, Some((path, root))) => (path.as_path(), root.workspace.as_ref().map(|workspace| &workspace.dependencies)),
+        (None, None) => (manifest_path, None),
+    };
+    let root_dir = root_path.parent().unwrap_or(Path::new("."));
+
+    let mut sections = vec![
+        (DependencyKind::Normal, None, &manifest.dependencies),
+        (DependencyKind::Dev, None, &manifest.dev_dependencies),
+        (DependencyKind::Build, None, &manifest.build_dependencies),
+    ];
+    for (platform, target) in &manifest.target {
+        sections.push((DependencyKind::Normal, Some(platform), &target.dependencies));
+        sections.push((DependencyKind::Dev, Some(platform), &target.dev_dependencies));
+        sections.push((DependencyKind::Build, Some(platform), &target.build_dependencies));
+    }
+    sections.sort_by_key(|(kind, platform, _)| (*kind, platform.is_some()));
+
+    let mut out = Vec::new();
+    for (kind, platform, specs) in sections {
+        for (name, spec) in specs {
+            let spec = spec.clone().into_detailed();
+            let inherited = spec.workspace;
+            let (spec, base_dir) = if inherited {
+                let root_spec = root_dependencies.and_then(|dependencies| dependencies.get(name)).ok_or_else(|| {
+                    format!("{}: `{}` is not in [workspace.dependencies]", root_path.display(), name)
+                })?;
+                let mut root_spec = root_spec.clone().into_detailed();
+                // Members can only add features and mark the dependency optional
+                root_spec.features.extend(spec.features);
+                root_spec.optional = spec.optional;
+                (root_spec, root_dir)
+            } else {
+                (spec, manifest_dir)
+            };
+
+            out.push(Dependency {
+                name: name.clone(),
+                package: spec.package,
+                kind,
+                target: platform.cloned(),
+                version: spec.version,
+                features: spec.features,
+                default_features: spec.default_features.unwrap_or(true),
+                optional: spec.optional,
+                path: spec.path.map(|path| base_dir.join(path).to_string_lossy().to_string()),
+                git: spec.git,
+                inherited,
+            });
+        }
+    }
+    Ok(out)
+}
@@ -1,65 +1,956 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// A location in a source file.
+///
+/// By default `line` is 1-based (as reported by syn) and `character` is a 0-based column
+/// counted in chars. `PositionOptions` can switch to 0-based lines and UTF-8/UTF-16 columns.
+/// Lines end at `\n`; the `\r` of a `\r\n` ending is never counted in a column, so CRLF
+/// and LF files report the same positions. Byte offsets still index the original text.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, schemars::JsonSchema)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct Position {
     pub line: usize,
     pub character: usize,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct Range {
     pub start: Position,
     pub end: Position,
+    /// Absolute byte offset of `start` in the source, only present when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_byte: Option<usize>,
+    /// Absolute byte offset of `end` in the source, only present when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_byte: Option<usize>,
+}
+
+/// `line:character`, e.g. `12:4`.
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.character)
+    }
+}
+
+/// `start-end`, e.g. `12:4-12:9`.
+impl std::fmt::Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Unit used for the `character` field of a `Position`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PositionEncoding {
+    /// Bytes of UTF-8.
+    Utf8,
+    /// UTF-16 code units, as expected by most LSP clients.
+    Utf16,
+    /// Unicode scalar values (chars), which is what syn reports natively.
+    #[default]
+    Utf32,
+}
+
+/// Position-reporting options shared by every tool that returns ranges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Deserialize, schemars::JsonSchema)]
+pub struct PositionOptions {
+    /// Include absolute `start_byte`/`end_byte` offsets in every returned range.
+    #[serde(default)]
+    pub byte_offsets: bool,
+    /// Encoding of the `character` column: `utf8`, `utf16`, or `utf32` (the default).
+    #[serde(default)]
+    pub position_encoding: PositionEncoding,
+    /// Report 0-based line numbers instead of the default 1-based ones.
+    #[serde(default)]
+    pub zero_based_lines: bool,
+}
+
+/// Diagnostic severity, most severe first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    #[serde(alias = "warn")]
+    Warning,
+    #[serde(alias = "information")]
+    Info,
+    Hint,
+}
+
+/// The serialized name, e.g. `warning`.
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+            Severity::Hint => "hint",
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct Diagnostic {
     pub message: String,
     pub range: Range,
-    pub severity: String,
+    pub severity: Severity,
+    /// File the diagnostic points into, when it isn't implied by the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Compiler error code or lint name, e.g. `E0308` or `clippy::needless_return`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+/// Compiler style, e.g. `src/lib.rs:3:8: error[E0308]: mismatched types`.
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}: {}", self.range.start, self.severity)?;
+        if let Some(code) = &self.code {
+            write!(f, "[{}]", code)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// A replacement of the text in `range`, named like the LSP `TextEdit`. Insertions have an
+/// empty range and deletions an empty `new_text`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+/// The edits a refactoring makes, keyed by file path, named like the LSP `WorkspaceEdit`.
+/// Tools only propose these; applying them is up to the client.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct WorkspaceEdit {
+    /// Edits of each file; they don't overlap and all refer to the file's original text.
+    pub changes: std::collections::HashMap<String, Vec<TextEdit>>,
+}
+
+/// What an edit-producing tool (such as `organize_imports`) returns. The server only
+/// proposes the edit and never writes it to disk.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct EditResult {
+    pub edit: WorkspaceEdit,
+    /// Unified diff of the proposed changes, only present when a preview was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+/// Token classification for highlighting, using the LSP semantic token type names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SemanticTokenType {
+    Namespace,
+    Type,
+    Struct,
+    Enum,
+    Interface,
+    TypeParameter,
+    Parameter,
+    Variable,
+    Property,
+    EnumMember,
+    Function,
+    Method,
+    Macro,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SemanticToken {
+    pub range: Range,
+    pub token_type: SemanticTokenType,
+}
+
+/// Which `[*dependencies]` table a dependency is declared in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+/// A dependency declared in a `Cargo.toml`, with workspace inheritance already applied.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct Dependency {
+    /// Name the dependency is declared (and imported) under.
+    pub name: String,
+    /// Actual crate name when the dependency is renamed with `package = "..."`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub kind: DependencyKind,
+    /// Platform of a `[target.'cfg(..)'.dependencies]` entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Version requirement, e.g. `^1.0`; absent for path or git dependencies without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub features: Vec<String>,
+    pub default_features: bool,
+    pub optional: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    /// Declared with `workspace = true`.
+    pub inherited: bool,
+}
+
+/// What kind of item a symbol names. Serialized in snake_case (`fn`, `type_alias`, ...).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Fn,
+    Method,
+    Struct,
+    Enum,
+    Union,
+    Trait,
+    TypeAlias,
+    Const,
+    Static,
+    Module,
+    Macro,
+    Variant,
+    Field,
+}
+
+/// The serialized name, e.g. `type_alias`.
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SymbolKind::Fn => "fn",
+            SymbolKind::Method => "method",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Union => "union",
+            SymbolKind::Trait => "trait",
+            SymbolKind::TypeAlias => "type_alias",
+            SymbolKind::Const => "const",
+            SymbolKind::Static => "static",
+            SymbolKind::Module => "module",
+            SymbolKind::Macro => "macro",
+            SymbolKind::Variant => "variant",
+            SymbolKind::Field => "field",
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct SymbolInfo {
     pub name: String,
-    pub kind: String,
+    pub kind: SymbolKind,
+    /// The symbol's name, for navigation.
     pub range: Range,
+    /// The whole item, from its first attribute or doc comment to its closing brace or `;`.
+    pub full_range: Range,
     pub file: String,
+    /// The item's doc comment, with the `///` markers removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs: Option<String>,
+    /// Declaration text without attributes or body, only collected by tools that render it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub visibility: String,
+    /// Name of the enclosing impl type, trait, or inline module, e.g. `Foo` for a method
+    /// in `impl Foo`. Absent for items at the top of a file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
 }
 
+/// Kind, name, and location, e.g. `method Parser::new at src/parser.rs:12:11`.
+impl std::fmt::Display for SymbolInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.kind)?;
+        if let Some(container) = &self.container {
+            write!(f, "{}::", container)?;
+        }
+        write!(f, "{} at {}:{}", self.name, self.file, self.range.start)
+    }
+}
+
+/// The source text of one item, as returned by `get_source`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SourceSnippet {
+    pub name: String,
+    pub kind: SymbolKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    pub file: String,
+    /// The whole item, as in `SymbolInfo::full_range`.
+    pub range: Range,
+    /// The item's text, attributes and doc comments included.
+    pub source: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct ReferenceLocation {
     pub file: String,
     pub range: Range,
 }
 
+/// A construct that can panic at runtime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PanicKind {
+    Unwrap,
+    Expect,
+    Panic,
+    Unreachable,
+    Todo,
+    Unimplemented,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct PanicSite {
+    pub kind: PanicKind,
+    pub file: String,
+    pub range: Range,
+}
+
+/// Why a function body counts as empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyFunctionKind {
+    /// The body has no statements at all.
+    Empty,
+    /// The body is a lone `todo!()` or `unimplemented!()`.
+    Placeholder,
+}
+
+/// A function, method, or default trait method with an empty or placeholder body.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct EmptyFunction {
+    pub name: String,
+    pub kind: EmptyFunctionKind,
+    pub file: String,
+    pub range: Range,
+}
+
+/// A `let` binding that shadows a binding of the same name still in scope.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ShadowedVariable {
+    pub name: String,
+    /// Function the bindings are in.
+    pub function: String,
+    pub file: String,
+    /// The binding being shadowed: an earlier `let`, a parameter, or a pattern binding.
+    pub outer: Range,
+    /// The shadowing `let` binding.
+    pub inner: Range,
+    /// Declared or evident type of each binding, when it can be told from the source alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outer_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inner_type: Option<String>,
+    /// Whether the two types differ; absent when either type is unknown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_changed: Option<bool>,
+}
+
+/// A `let` binding whose value is never read.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct UnusedVariable {
+    pub name: String,
+    /// Function the binding is in.
+    pub function: String,
+    pub file: String,
+    pub range: Range,
+}
+
+/// A statement that can't run because an earlier statement in its block always diverges.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct UnreachableCode {
+    pub file: String,
+    pub range: Range,
+}
+
+/// A function marked `#[test]`, `#[tokio::test]`, `#[test_case(..)]` or similar.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct TestInfo {
+    pub name: String,
+    /// Name qualified by the inline modules it's declared in, e.g. `tests::parses_input`.
+    pub path: String,
+    /// The test attribute as written, e.g. `test` or `tokio::test`.
+    pub attribute: String,
+    pub ignored: bool,
+    /// Whether the body can fail: an assertion or panicking macro, `?`, `unwrap`/`expect`,
+    /// or a `#[should_panic]` attribute.
+    pub has_assertions: bool,
+    pub file: String,
+    pub range: Range,
+}
+
+/// A call to a known-blocking API from async code.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct BlockingCall {
+    /// The call path as written, e.g. `thread::sleep`.
+    pub call: String,
+    /// The blocking API it resolved to, e.g. `std::thread::sleep`.
+    pub blocking_api: String,
+    /// Enclosing function; for an `async` block in a sync function, that function.
+    pub function: String,
+    pub file: String,
+    pub range: Range,
+}
+
+/// Kind of loop an `.await` sits in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoopKind {
+    For,
+    While,
+    Loop,
+}
+
+/// An `.await` evaluated once per iteration of an enclosing loop.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct AwaitInLoop {
+    pub function: String,
+    /// The innermost loop around the `.await`.
+    pub loop_kind: LoopKind,
+    pub file: String,
+    pub range: Range,
+}
+
+/// What makes a `match` expression worth a look.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchIssueKind {
+    /// More arms than the threshold.
+    TooManyArms,
+    /// A `_ => {}` arm in a match over enum variants, which silently ignores variants added later.
+    EmptyCatchAll,
+}
+
+/// A `match` expression flagged by `find_large_matches`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct MatchIssue {
+    pub kind: MatchIssueKind,
+    /// Function the match is in; empty outside functions.
+    pub function: String,
+    pub arms: usize,
+    pub file: String,
+    /// The `match` keyword.
+    pub range: Range,
+}
+
+/// A function with more `.clone()` calls than `find_clone_hotspots` allows.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct CloneHotspot {
+    pub function: String,
+    pub clones: usize,
+    pub file: String,
+    /// The function's name.
+    pub range: Range,
+    /// Each call, at the `clone` method name. Calls in closures count toward the enclosing
+    /// function; nested functions are counted on their own.
+    pub sites: Vec<Range>,
+}
+
+/// A method chain longer than `detect_long_chains` allows.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct MethodChain {
+    /// Function the chain is in; empty outside functions.
+    pub function: String,
+    /// Consecutive method calls, counted through `?` and `.await`.
+    pub length: usize,
+    /// The methods called, in call order.
+    pub methods: Vec<String>,
+    pub file: String,
+    /// The whole chain expression.
+    pub range: Range,
+}
+
+/// Why `find_large_returns` flagged a return type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeReturnKind {
+    /// An owned collection such as `Vec` or `HashMap`.
+    Collection,
+    /// A workspace struct with many fields.
+    LargeStruct,
+}
+
+/// A function returning a potentially large value by value, for performance review.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct LargeReturn {
+    pub function: String,
+    /// Impl type, for methods.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// The declared return type, e.g. `Result<Vec<Row>, Error>`.
+    pub return_type: String,
+    pub kind: LargeReturnKind,
+    /// Takes `&self` or another reference, so returning a borrow of existing data (or an
+    /// iterator over it) may be possible instead.
+    pub borrows_input: bool,
+    pub file: String,
+    /// The function's name.
+    pub range: Range,
+}
+
+/// A function with more early `return`s than `detect_early_returns` allows.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct EarlyReturns {
+    pub function: String,
+    /// Explicit `return` expressions, not counting one that ends the body.
+    pub count: usize,
+    pub file: String,
+    /// The function's name.
+    pub range: Range,
+    /// Each `return` expression. Returns from closures and async blocks belong to those, and
+    /// nested functions are counted on their own.
+    pub returns: Vec<Range>,
+}
+
+/// Where a type flagged by `find_nested_wrappers` is declared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WrapperSite {
+    Parameter,
+    Return,
+    Field,
+}
+
+/// A type nesting `Result` and `Option` deeper than `find_nested_wrappers` allows.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct NestedWrapper {
+    /// The function, or the field's name (its index for tuple fields).
+    pub item: String,
+    /// Impl or trait, for methods; the struct, or `Enum::Variant`, for fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    pub site: WrapperSite,
+    /// `Result`s and `Option`s directly inside one another, e.g. 3 for `Result<Option<Result<T, E>>, E>`.
+    pub depth: usize,
+    /// The outermost wrapper of the nesting, as written.
+    #[serde(rename = "type")]
+    pub type_text: String,
+    pub file: String,
+    /// The outermost wrapper.
+    pub range: Range,
+}
+
+/// A name defined more than once with the same kind.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DuplicateDefinition {
+    /// The symbol name; methods are qualified by their impl type or trait (`Foo::new`).
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Every definition, sorted by file and position.
+    pub definitions: Vec<ReferenceLocation>,
+}
+
+/// An item marked `#[deprecated]`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DeprecatedItem {
+    pub name: String,
+    /// Type, trait or enum a deprecated method or variant belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub file: String,
+    pub range: Range,
+}
+
+/// A use of a deprecated item's name.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DeprecatedUsage {
+    pub item: DeprecatedItem,
+    pub file: String,
+    pub range: Range,
+}
+
+/// Line counts of one file by what each line holds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FileCommentStats {
+    pub file: String,
+    /// Lines with any code, including those that also end in a comment.
+    pub code_lines: usize,
+    /// Lines with only comments (doc comments included).
+    pub comment_lines: usize,
+    /// Lines with only whitespace.
+    pub blank_lines: usize,
+    /// `comment_lines / code_lines`, or 0 when the file has no code.
+    pub ratio: f64,
+}
+
+/// How big one file is, as reported by `largest_files`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FileSize {
+    pub file: String,
+    pub line_count: usize,
+    /// Items declared in the file, including methods and items nested in inline modules.
+    /// 0 when the file doesn't parse.
+    pub item_count: usize,
+    /// More lines than `max_lines` or more items than `max_items`.
+    pub oversized: bool,
+}
+
+/// A run of `//` comment lines that looks like commented-out code.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct CommentedCode {
+    pub file: String,
+    /// From the first `//` of the run to the end of its last line.
+    pub range: Range,
+    /// Comment lines in the run.
+    pub lines: usize,
+    /// Comment lines in the run that look like Rust code.
+    pub code_lines: usize,
+}
+
+/// The derives and other attributes on a struct, enum, or union.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct TypeDerives {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Derived traits as written, e.g. `Clone` or `serde::Serialize`.
+    pub derives: Vec<String>,
+    /// Paths of the other attributes, e.g. `serde` or `non_exhaustive`; doc comments are left out.
+    pub attributes: Vec<String>,
+    pub file: String,
+    pub range: Range,
+}
+
+/// A common trait a type could derive, or what keeps it from deriving it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DeriveReason {
+    #[serde(rename = "trait")]
+    pub trait_name: String,
+    pub reason: String,
+}
+
+/// Common traits a struct or enum doesn't derive yet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct DeriveSuggestion {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Traits every field supports, so adding them to the derive list should compile.
+    pub derivable: Vec<DeriveReason>,
+    /// Traits that can't be derived as the type stands, with the field in the way.
+    pub blocked: Vec<DeriveReason>,
+    pub file: String,
+    pub range: Range,
+}
+
+/// How much a public API change matters to semver, least severe first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SemverImpact {
+    /// Nothing callers can observe, such as reworded docs.
+    Patch,
+    /// Additions that existing callers keep compiling against.
+    Minor,
+    /// Removals or changes that can break existing callers.
+    Major,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One difference between the public items of two source trees.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ApiChange {
+    pub change: ApiChangeKind,
+    pub impact: SemverImpact,
+    pub kind: SymbolKind,
+    /// Path of the item from its crate root, e.g. `crate::parser::Parser::new`.
+    pub name: String,
+    /// What changed, e.g. `variant `Json` removed`.
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_signature: Option<String>,
+    /// File declaring the item, relative to its root: the new tree's, or the old one's for removals.
+    pub file: String,
+}
+
+/// Public API differences between two source trees, as returned by `diff_public_api`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct PublicApiDiff {
+    /// The most severe impact among `changes`. Absent when the public API is unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_bump: Option<SemverImpact>,
+    pub changes: Vec<ApiChange>,
+}
+
+/// A symbol named by its kind and path from the crate root, e.g. `crate::parser::Parser::new`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct QualifiedSymbol {
+    pub kind: SymbolKind,
+    pub name: String,
+}
+
+/// One file's share of a `compare_workspaces` result. Removed symbols are listed under the
+/// file that declared them in the old tree, the others under their file in the new tree.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FileSymbolDiff {
+    /// Relative to the roots.
+    pub file: String,
+    pub added: Vec<QualifiedSymbol>,
+    pub removed: Vec<QualifiedSymbol>,
+    pub unchanged: Vec<QualifiedSymbol>,
+}
+
+/// Symbols present in only one of two source trees, or in both.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct WorkspaceComparison {
+    pub added: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    pub files: Vec<FileSymbolDiff>,
+}
+
+/// How a struct field is referenced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldReferenceKind {
+    /// `value.field`
+    Access,
+    /// `Struct { field: value }` or the shorthand `Struct { field }`
+    Init,
+    /// `Struct { field, .. }` in a pattern
+    Pattern,
+}
+
+/// A use of a struct field.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FieldReference {
+    pub kind: FieldReferenceKind,
+    /// Whether the struct is known. `false` for accesses on values whose type couldn't be
+    /// determined, which match by field name alone.
+    pub exact: bool,
+    pub file: String,
+    pub range: Range,
+}
+
+/// A chain of calls from one function to another.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct CallPath {
+    /// Qualified caller keys from the start function to the target, both included.
+    pub functions: Vec<String>,
+    /// The call site of each hop, so `hops[i]` is the call from `functions[i]` to `functions[i + 1]`.
+    pub hops: Vec<CallSite>,
+}
+
+/// Result of `module_topo_order`: an order when the module graph is acyclic, else a cycle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ModuleTopoOrder {
+    /// Every module, each after the modules it imports from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<Vec<String>>,
+    /// Modules importing from each other in a loop, each importing from the next and the
+    /// last from the first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycle: Option<Vec<String>>,
+}
+
+/// Snapshot of the in-memory source cache.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct CacheStats {
+    pub file_count: usize,
+    pub total_bytes: usize,
+}
+
+/// Liveness and capability report returned by `ping`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct PingResponse {
+    pub name: String,
+    pub version: String,
+    pub uptime_seconds: u64,
+    pub cached_files: usize,
+    /// Names of every tool the server offers, sorted.
+    pub tools: Vec<String>,
+}
+
+/// One call from `caller` to `callee`, located at the callee's name in the call expression.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct CallSite {
+    pub caller: String,
+    pub callee: String,
+    pub file: String,
+    pub range: Range,
+}
+
+/// Rust's naming conventions, as enforced by rustc's style lints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamingConvention {
+    SnakeCase,
+    UpperCamelCase,
+    ScreamingSnakeCase,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct NamingViolation {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub expected: NamingConvention,
+    pub file: String,
+    pub range: Range,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct CallGraph {
-    pub calls: std::collections::HashMap<String, Vec<String>>, // caller -> callees
+    pub calls: std::collections::BTreeMap<String, Vec<String>>, // caller -> callees
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct TypeUsageGraph {
-    pub usages: std::collections::HashMap<String, Vec<ReferenceLocation>>, // type -> usages
+    pub definitions: std::collections::BTreeMap<String, Vec<ReferenceLocation>>, // type -> declaration sites
+    pub usages: std::collections::BTreeMap<String, Vec<ReferenceLocation>>, // type -> usages
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct ModuleDependencyGraph {
-    pub dependencies: std::collections::HashMap<String, Vec<String>>, // module -> dependencies
+    pub dependencies: std::collections::BTreeMap<String, Vec<String>>, // module path -> dependencies
+}
+
+/// An out-of-line `mod foo;` declaration.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ModDeclaration {
+    /// Module path relative to the declaring file's module, e.g. `inner::foo` for a
+    /// `mod foo;` inside `mod inner { .. }`.
+    pub module: String,
+    /// Files the module may be loaded from, in the order rustc tries them.
+    pub candidates: Vec<String>,
+    /// Whether the declaration and every enclosing inline module are `pub`.
+    pub public: bool,
+}
+
+/// A module and its submodules, derived from the indexed file paths.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct ModuleNode {
+    pub name: String,
+    /// Full module path, e.g. `crate::net::client`.
+    pub path: String,
+    /// File defining the module; `None` for directories without a `mod.rs` or `<name>.rs`.
+    pub file: Option<String>,
+    pub children: Vec<ModuleNode>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct FunctionInfo {
     pub name: String,
+    /// Lines from the signature to the closing brace, attributes and doc comments excluded.
     pub line_count: usize,
     pub complexity: usize,
+    /// Nesting-aware readability metric, as defined by SonarSource.
+    pub cognitive_complexity: usize,
+    /// Deepest nesting of `if`/`match`/loops/blocks inside the body; the body itself is depth 0.
+    pub max_nesting_depth: usize,
     pub param_count: usize,
+    /// Type and const parameters in the signature's `<...>`; lifetimes aren't counted.
+    #[serde(default)]
+    pub generic_param_count: usize,
+    /// Predicates in the `where` clause.
+    #[serde(default)]
+    pub where_predicate_count: usize,
+    /// Every trait bound on a generic parameter, inline or in the `where` clause, as
+    /// `Type: Trait` without generic arguments, e.g. `T: Into` for `T: Into<String>`.
+    #[serde(default)]
+    pub trait_bounds: Vec<String>,
+    /// The declared return type, e.g. `Result<Vec<u8>, io::Error>` or `impl Iterator<Item = u8>`.
+    /// Absent for functions returning `()`, whether or not they spell it out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_type: Option<String>,
+    /// Returns a `Result` (including aliases named `Result`, such as `io::Result`).
+    #[serde(default)]
+    pub returns_result: bool,
+    /// Returns an `Option`.
+    #[serde(default)]
+    pub returns_option: bool,
     pub visibility: String,
+    /// Key of the function in the call graph: its name behind its file's module path and any
+    /// enclosing inline modules.
+    pub qualified_name: String,
+    /// Distinct functions in the workspace that call this one.
+    #[serde(default)]
+    pub fan_in: usize,
+    /// Distinct callees this function calls, as written at the call sites.
+    #[serde(default)]
+    pub fan_out: usize,
+    /// Never returns: declared `-> !`, or the body ends in a `loop` without `break`, `return`,
+    /// or `?`, a `panic!`-style macro, `process::exit`, or a call to another diverging function.
+    #[serde(default)]
+    pub diverges: bool,
     pub file: String,
+    /// The function's name, for navigation.
     pub range: Range,
+    /// The whole function, from its first attribute or doc comment to its closing brace.
+    pub full_range: Range,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct StructInfo {
     pub name: String,
     pub field_count: usize,
@@ -68,6 +959,7 @@ pub struct StructInfo {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct EnumInfo {
     pub name: String,
     pub variant_count: usize,
@@ -76,33 +968,638 @@ pub struct EnumInfo {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
 pub struct WorkspaceGraphs {
     pub call_graph: CallGraph,
     pub type_usage_graph: TypeUsageGraph,
     pub module_dependency_graph: ModuleDependencyGraph,
     pub unused_functions: Vec<String>,
-    pub refactoring_suggestions: Vec<String>,
+    /// Directly recursive functions (single entry) and mutually recursive groups.
+    pub recursive_functions: Vec<Vec<String>>,
+    /// Groups of modules that import from each other in a cycle.
+    pub circular_modules: Vec<Vec<String>>,
+    /// Functions nothing in the call graph calls, excluding `main`: public API or dead roots.
+    pub entry_points: Vec<String>,
+    /// Functions that call nothing.
+    pub leaf_functions: Vec<String>,
+    /// Functions that never return, such as servers ending in `loop {}` or error exits.
+    pub diverging_functions: Vec<String>,
+    /// Module hierarchy of the indexed files: a single `crate` root, or one root per crate root
+    /// found, named after its crate, when there are several.
+    pub module_tree: Vec<ModuleNode>,
+    pub refactoring_suggestions: Vec<RefactoringSuggestion>,
     pub function_info: Vec<FunctionInfo>,
     pub struct_info: Vec<StructInfo>,
     pub enum_info: Vec<EnumInfo>,
+    /// Files left out of the analysis, such as ones larger than `max_file_bytes`.
+    pub skipped_files: Vec<SkippedFile>,
 }
 
-#[derive(serde::Deserialize, schemars::JsonSchema)]
+/// A file `index_workspace` did not analyze.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct SkippedFile {
+    pub file: String,
+    pub reason: String,
+}
+
+/// Everything the collectors extract from a single source file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct FileAnalysis {
+    pub file: String,
+    pub symbols: Vec<SymbolInfo>,
+    pub functions: Vec<FunctionInfo>,
+    pub structs: Vec<StructInfo>,
+    pub enums: Vec<EnumInfo>,
+    pub calls: std::collections::HashMap<String, Vec<String>>, // caller -> callees
+    pub type_definitions: std::collections::HashMap<String, Vec<ReferenceLocation>>, // type -> declaration sites
+    pub type_usages: std::collections::HashMap<String, Vec<ReferenceLocation>>, // type -> usages
+    pub call_sites: Vec<CallSite>,
+    pub tail_calls: std::collections::HashMap<String, String>, // caller -> callee its body ends in
+    pub module_dependencies: Vec<String>, // names imported by `use` items
+    pub use_paths: Vec<String>, // full paths imported by `use` items
+    pub mod_declarations: Vec<ModDeclaration>,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct CheckFileParams {
-    pub path: String,
+    /// Path of the file to check. When `source` is also given, the path is only used
+    /// as the (possibly virtual) cache key and is not read from disk.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Inline source code to check instead of reading `path`, e.g. an unsaved editor buffer.
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct CheckFilesParams {
+    /// Files to read and check. Each is reported under the path as given.
+    pub paths: Vec<String>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+/// Which threshold a `RefactoringSuggestion` exceeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefactoringKind {
+    LongFunction,
+    HighComplexity,
+    HighCognitiveComplexity,
+    DeepNesting,
+    TooManyParams,
+    LargeStruct,
+    LargeEnum,
+    TooManyCallees,
+    TooManyCallers,
+    /// Both fan-in and fan-out are high: a hub that many callers depend on and that depends on much.
+    HubFunction,
+    /// Many generic parameters and `where` predicates, which makes the signature hard to follow.
+    ComplexGenerics,
+    GodObject,
 }
 
-#[derive(serde::Deserialize, schemars::JsonSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel-case", serde(rename_all = "camelCase"))]
+pub struct RefactoringSuggestion {
+    pub kind: RefactoringKind,
+    /// Function, struct, or enum the suggestion is about.
+    pub target: String,
+    /// Where `target` is defined; absent when it can't be located (e.g. a callee path).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<Range>,
+    /// The measured value, e.g. the line count for `LongFunction` or the smaller of fan-in and
+    /// fan-out for `HubFunction`.
+    pub metric: usize,
+    /// The configured limit `metric` exceeds.
+    pub threshold: usize,
+    /// Human-readable description of the problem and a suggested fix.
+    pub message: String,
+}
+
+/// Limits above which `index_workspace` suggests a refactoring.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thresholds {
+    pub max_function_lines: usize,
+    pub max_complexity: usize,
+    pub max_cognitive_complexity: usize,
+    pub max_nesting_depth: usize,
+    pub max_params: usize,
+    pub max_struct_fields: usize,
+    pub max_enum_variants: usize,
+    pub max_callees: usize,
+    pub max_callers: usize,
+    pub max_fan_in_and_out: usize,
+    pub max_type_usages: usize,
+    pub max_generics: usize,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            max_function_lines: 50,
+            max_complexity: 10,
+            max_cognitive_complexity: 15,
+            max_nesting_depth: 4,
+            max_params: 5,
+            max_struct_fields: 10,
+            max_enum_variants: 10,
+            max_callees: 10,
+            max_callers: 10,
+            max_fan_in_and_out: 5,
+            max_type_usages: 10,
+            max_generics: 4,
+        }
+    }
+}
+
+impl Thresholds {
+    /// Replaces every threshold that `overrides` sets, keeping the rest.
+    pub fn with_overrides(self, overrides: &ThresholdOverrides) -> Self {
+        Self {
+            max_function_lines: overrides.max_function_lines.unwrap_or(self.max_function_lines),
+            max_complexity: overrides.max_complexity.unwrap_or(self.max_complexity),
+            max_cognitive_complexity: overrides.max_cognitive_complexity.unwrap_or(self.max_cognitive_complexity),
+            max_nesting_depth: overrides.max_nesting_depth.unwrap_or(self.max_nesting_depth),
+            max_params: overrides.max_params.unwrap_or(self.max_params),
+            max_struct_fields: overrides.max_struct_fields.unwrap_or(self.max_struct_fields),
+            max_enum_variants: overrides.max_enum_variants.unwrap_or(self.max_enum_variants),
+            max_callees: overrides.max_callees.unwrap_or(self.max_callees),
+            max_callers: overrides.max_callers.unwrap_or(self.max_callers),
+            max_fan_in_and_out: overrides.max_fan_in_and_out.unwrap_or(self.max_fan_in_and_out),
+            max_type_usages: overrides.max_type_usages.unwrap_or(self.max_type_usages),
+            max_generics: overrides.max_generics.unwrap_or(self.max_generics),
+        }
+    }
+}
+
+/// Partial `Thresholds`, as written in `ruststudio.toml` or passed to `index_workspace`.
+/// Unset fields keep their previous value.
+#[derive(Clone, Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdOverrides {
+    /// Functions longer than this many lines (default 50).
+    pub max_function_lines: Option<usize>,
+    /// Cyclomatic complexity (default 10).
+    pub max_complexity: Option<usize>,
+    /// Cognitive complexity (default 15).
+    pub max_cognitive_complexity: Option<usize>,
+    /// Control-flow nesting depth (default 4).
+    pub max_nesting_depth: Option<usize>,
+    /// Function parameters (default 5).
+    pub max_params: Option<usize>,
+    /// Struct fields (default 10).
+    pub max_struct_fields: Option<usize>,
+    /// Enum variants (default 10).
+    pub max_enum_variants: Option<usize>,
+    /// Calls made by one function (default 10).
+    pub max_callees: Option<usize>,
+    /// Call sites of one function (default 10).
+    pub max_callers: Option<usize>,
+    /// Fan-in and fan-out that a function must both exceed to be flagged as a hub (default 5).
+    pub max_fan_in_and_out: Option<usize>,
+    /// Usages of one struct before it's flagged as a god object (default 10).
+    pub max_type_usages: Option<usize>,
+    /// Generic parameters plus `where` predicates of one function (default 4).
+    pub max_generics: Option<usize>,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct IndexWorkspaceParams {
-    pub root: String,
+    /// Single root directory to index. Kept for backward compatibility; merged with `roots`.
+    #[serde(default)]
+    pub root: Option<String>,
+    /// Root directories to index. Files reachable from several roots are indexed once.
+    #[serde(default)]
+    pub roots: Option<Vec<String>>,
+    /// Explicit files to index in addition to the roots. These bypass `include`/`exclude`.
+    #[serde(default)]
+    pub files: Option<Vec<String>>,
+    /// Glob patterns (e.g. `src/**`) matched against each file's path relative to `root`.
+    /// When absent or empty, every `.rs` file under `root` is included.
+    #[serde(default)]
+    pub include: Option<Vec<String>>,
+    /// Glob patterns matched against each file's path relative to `root`.
+    /// A file matching any exclude pattern is skipped, even if it also matches `include`.
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+    /// Skip items annotated `#[cfg(test)]` (including whole test modules), so call graphs and
+    /// dead-code detection reflect production code only. Defaults to `false`.
+    #[serde(default)]
+    pub ignore_cfg_test: bool,
+    /// Refactoring thresholds for this request. They override `ruststudio.toml` (looked up in
+    /// the first root), which overrides the built-in defaults.
+    #[serde(default)]
+    pub thresholds: Option<ThresholdOverrides>,
+    /// Symbol kinds (`fn`, `method`, `struct`, `enum`, ...) to keep in `function_info`,
+    /// `struct_info`, and `enum_info`; lists of other kinds come back empty. The graphs are
+    /// always built from every item. When absent, nothing is filtered.
+    #[serde(default)]
+    pub kinds: Option<Vec<String>>,
+    /// Files larger than this many bytes are not parsed and are listed in `skipped_files`
+    /// instead. Defaults to 1 MiB.
+    #[serde(default)]
+    pub max_file_bytes: Option<u64>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
 }
 
-#[derive(serde::Deserialize, schemars::JsonSchema)]
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct GotoDefinitionParams {
+    /// Name to look up. Not needed when `file` and `position` are given.
+    #[serde(default)]
     pub name: String,
+    /// File holding the cursor, read from the cache or else from disk. Used with `position`.
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Cursor position in `file`, in the encoding and line base of the position options. The
+    /// identifier under it is looked up instead of `name`, ranked from the file's module.
+    #[serde(default)]
+    pub position: Option<Position>,
+    /// Module the lookup is made from, e.g. `crate::net::client`. When given, definitions in the
+    /// closest modules are returned first.
+    #[serde(default)]
+    pub context_path: Option<String>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct SearchSymbolsParams {
+    /// Regular expression matched against symbol names, e.g. `^test_` or `Handler$`.
+    pub pattern: String,
+    /// Maximum number of matches to return (default 200).
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
 }
 
-#[derive(serde::Deserialize, schemars::JsonSchema)]
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetDocsParams {
+    /// Name of the symbol to document.
+    pub name: String,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetSourceParams {
+    /// Name of the item, optionally behind its impl type, trait, or module (`Parser::new`).
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Only look in this cached file. Required when `name` is absent.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Without `name`, return the innermost item of `path` spanning this line.
+    #[serde(default)]
+    pub line: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct GenerateApiDocsParams {
+    /// Also list private items. Defaults to `false`, documenting only the `pub` surface.
+    #[serde(default)]
+    pub include_private: bool,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct CallPathParams {
+    /// Function to start from, bare (`run`) or qualified like the call graph keys (`Server::run`).
+    pub from: String,
+    /// Function to reach, bare or qualified.
+    pub to: String,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindCallersParams {
+    /// Function to find callers of: a bare name like `parse`, or a path suffix like `Parser::parse`.
+    pub name: String,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
 pub struct FindReferencesParams {
+    /// Name to search for. Not needed when `file` and `position` are given.
+    #[serde(default)]
     pub name: String,
-}
\ No newline at end of file
+    /// File holding the cursor, read from the cache or else from disk. Used with `position`.
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Cursor position in `file`, in the encoding and line base of the position options. The
+    /// identifier under it is searched for instead of `name`.
+    #[serde(default)]
+    pub position: Option<Position>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindVariantReferencesParams {
+    /// Name of the enum, e.g. `Shape`.
+    pub enum_name: String,
+    /// Name of the variant, e.g. `Circle`.
+    pub variant_name: String,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindFieldReferencesParams {
+    /// Name of the struct, e.g. `Point`.
+    pub struct_name: String,
+    /// Field name, or its index for tuple structs (`0`, `1`, ...).
+    pub field: String,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct CheckNamingParams {
+    /// Names that are intentionally unconventional (e.g. FFI bindings) and never reported.
+    #[serde(default)]
+    pub allowlist: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct CargoCheckParams {
+    /// Directory containing the `Cargo.toml` to check.
+    pub manifest_dir: String,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct SemanticTokensParams {
+    /// Path of the file to classify; the cached copy is used when there is one.
+    pub path: String,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct SuggestDerivesParams {
+    /// Only suggest derives for the struct or enum with this name. When absent, every type is checked.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct OrganizeImportsParams {
+    /// Path of the file whose imports to organize; the cached copy is used when there is one.
+    pub path: String,
+    /// Also return a unified diff of the proposed changes. Nothing is written to disk either way.
+    #[serde(default)]
+    pub preview: bool,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ReindexChangedParams {
+    /// Directory inside a git work tree; only changes below it are picked up.
+    pub root: String,
+    /// Revision to diff the work tree against. Defaults to `HEAD`.
+    #[serde(default)]
+    pub git_ref: Option<String>,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListDependenciesParams {
+    /// Path to a `Cargo.toml`, or to the directory containing it.
+    pub manifest_path: String,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindUnusedDependenciesParams {
+    /// Path to a `Cargo.toml`, or to the directory containing it.
+    pub manifest_path: String,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ClippyParams {
+    /// Directory containing the `Cargo.toml` to lint.
+    pub manifest_dir: String,
+    /// Only return diagnostics at least this severe, e.g. `warning` drops notes and help.
+    #[serde(default)]
+    pub min_severity: Option<Severity>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindMissingDocsParams {
+    /// Only report items reachable from outside the crate, i.e. not inside a private module.
+    /// Defaults to `false`, reporting every `pub` item.
+    #[serde(default)]
+    pub reachable_only: bool,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindBlockingInAsyncParams {
+    /// Additional blocking function paths to look for, fully qualified like
+    /// `my_crate::db::query_sync`, on top of the built-in std list.
+    #[serde(default)]
+    pub extra_blocking_calls: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindAwaitInLoopsParams {
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FileCommentStatsParams {
+    /// Only count this file, using the cached copy when there is one. When absent, every
+    /// cached file is counted.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindCommentedCodeParams {
+    /// Minimum number of code-like lines for a comment run to be reported. Defaults to 2.
+    #[serde(default)]
+    pub min_lines: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindDuplicateDefinitionsParams {
+    /// Only consider `pub` items. Defaults to `false`.
+    #[serde(default)]
+    pub public_only: bool,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindDeprecatedUsagesParams {
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListDerivesParams {
+    /// Only list types deriving this trait, matched on its last path segment (`Serialize`
+    /// also matches `serde::Serialize`).
+    #[serde(default)]
+    pub derive: Option<String>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListTestsParams {
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindTestsWithoutAssertionsParams {
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindUnreachableCodeParams {
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindUnusedVariablesParams {
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindLargeMatchesParams {
+    /// Report matches with more arms than this. Defaults to 10.
+    #[serde(default)]
+    pub max_arms: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindEmptyFunctionsParams {
+    /// Only report these kinds of empty bodies. When absent or empty, every kind is reported.
+    #[serde(default)]
+    pub kinds: Option<Vec<EmptyFunctionKind>>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindShadowedVariablesParams {
+    /// Only report shadowing that changes the type (`true`) or keeps it (`false`); either
+    /// way, shadowing whose types can't be told is left out. When absent, everything is reported.
+    #[serde(default)]
+    pub type_changed: Option<bool>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindPanicsParams {
+    /// Only report these kinds of panic points. When absent or empty, every kind is reported.
+    #[serde(default)]
+    pub kinds: Option<Vec<PanicKind>>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct DiffPublicApiParams {
+    /// Root directory of the previous version, e.g. a checkout of the last release.
+    pub old_root: String,
+    /// Root directory of the version to compare against it.
+    pub new_root: String,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct CompareWorkspacesParams {
+    /// Root directory of the tree before the change.
+    pub old_root: String,
+    /// Root directory of the tree after it.
+    pub new_root: String,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindCloneHotspotsParams {
+    /// Report functions with more `.clone()` calls than this. Defaults to 5.
+    #[serde(default)]
+    pub max_clones: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct LargestFilesParams {
+    /// Flag files with more lines than this. Defaults to 500.
+    #[serde(default)]
+    pub max_lines: Option<usize>,
+    /// Flag files with more items than this. Defaults to 50.
+    #[serde(default)]
+    pub max_items: Option<usize>,
+    /// Only return this many files, largest first. When absent, every cached file is returned.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct DetectLongChainsParams {
+    /// Report chains of more method calls than this. Defaults to 5.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindLargeReturnsParams {
+    /// Flag workspace structs with at least this many fields. Defaults to 8.
+    #[serde(default)]
+    pub min_struct_fields: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct DetectEarlyReturnsParams {
+    /// Report functions with more early returns than this. Defaults to 3.
+    #[serde(default)]
+    pub max_returns: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
+
+#[derive(Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindNestedWrappersParams {
+    /// Report `Result`/`Option` nesting deeper than this. Defaults to 2, so
+    /// `Result<Option<T>, E>` is fine and `Result<Option<Option<T>>, E>` is reported.
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(flatten)]
+    pub position_options: PositionOptions,
+}
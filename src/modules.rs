@@ -0,0 +1,339 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+use crate::graph::{strongly_connected_components, topological_sort};
+use crate::models::{ModDeclaration, ModuleNode};
+
+/// Derives a module path from a file path, e.g. `src/tools/get_docs.rs` -> `crate::tools::get_docs`.
+/// Paths outside a `src` directory use the file name alone.
+pub fn module_path_for_file(file: &str) -> String {
+    let components: Vec<String> = Path::new(file)
+        .with_extension("")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let relative = match components.iter().rposition(|component| component == "src") {
+        Some(src) => &components[src + 1..],
+        None => &components[components.len().saturating_sub(1)..],
+    };
+
+    let mut segments = vec!["crate"];
+    segments.extend(relative.iter().map(String::as_str));
+    if matches!(segments.last(), Some(&"mod")) || (segments.len() == 2 && matches!(segments[1], "lib" | "main")) {
+        segments.pop();
+    }
+    segments.join("::")
+}
+
+/// Name of the crate `file` belongs to: the `[package] name` of the closest `Cargo.toml` above
+/// it, or else the directory holding its `src`, with `-` written as `_`.
+fn crate_name(file: &str) -> String {
+    let path = Path::new(file);
+    let manifest_name = path.ancestors().skip(1).find_map(crate::manifest::package_name);
+    let name = manifest_name.or_else(|| {
+        let package_dir = path.ancestors().find(|dir| dir.file_name().is_some_and(|name| name == "src"))?.parent()?;
+        Some(package_dir.file_name()?.to_string_lossy().to_string())
+    });
+    name.unwrap_or_else(|| "crate".to_string()).replace('-', "_")
+}
+
+/// Resolves a `use` path written inside `module` to the workspace module it imports from:
+/// the longest prefix of the absolute path that names a module in `known`.
+///
+/// `crate::`, `self::` and `super::` are resolved against `module`, and paths starting with the
+/// name of another crate root in `known` against that root; other paths are tried as children
+/// of `module`, and are otherwise assumed to name an external crate.
+pub fn resolve_use_path(module: &str, use_path: &str, known: &HashSet<String>) -> Option<String> {
+    let mut segments = use_path.split("::").peekable();
+    let mut absolute: Vec<&str> = match segments.peek() {
+        Some(&"crate") => {
+            segments.next();
+            module.split("::").take(1).collect()
+        }
+        Some(first) if known.contains(*first) => Vec::new(),
+        _ => module.split("::").collect(),
+    };
+    if segments.peek() == Some(&"self") {
+        segments.next();
+    }
+    while segments.peek() == Some(&"super") {
+        segments.next();
+        if absolute.len() > 1 {
+            absolute.pop();
+        }
+    }
+    absolute.extend(segments);
+
+    (1..=absolute.len())
+        .rev()
+        .map(|len| absolute[..len].join("::"))
+        .find(|candidate| known.contains(candidate))
+}
+
+/// Reports groups of modules that import from each other in a cycle: every strongly connected
+/// component of the module graph with more than one module.
+///
+/// `use_paths` maps each workspace module to the `use` paths written in it.
+pub fn circular_modules(use_paths: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    strongly_connected_components(&module_graph(use_paths))
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .collect()
+}
+
+/// Orders the workspace modules so each comes after every module it imports from, or
+/// returns a cycle of modules importing from each other that rules such an order out.
+///
+/// `use_paths` maps each workspace module to the `use` paths written in it.
+pub fn module_topo_order(use_paths: &HashMap<String, Vec<String>>) -> Result<Vec<String>, Vec<String>> {
+    topological_sort(&module_graph(use_paths))
+}
+
+/// Module -> modules it imports from, leaving out external crates and self-imports.
+fn module_graph(use_paths: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
+    let known: HashSet<String> = use_paths.keys().cloned().collect();
+    use_paths
+        .iter()
+        .map(|(module, paths)| {
+            let targets = paths
+                .iter()
+                .filter_map(|path| resolve_use_path(module, path, &known))
+                .filter(|target| target != module)
+                .collect();
+            (module.clone(), targets)
+        })
+        .collect()
+}
+
+/// Builds the module hierarchy from a file -> module path map.
+/// Missing intermediate modules are filled in without a file; children are sorted by name.
+pub fn module_tree(module_paths: &BTreeMap<String, String>) -> Vec<ModuleNode> {
+    let mut roots: Vec<ModuleNode> = Vec::new();
+    for (file, module_path) in module_paths {
+        let segments: Vec<&str> = module_path.split("::").collect();
+
+        let mut level = &mut roots;
+        for depth in 0..segments.len() {
+            let index = match level.iter().position(|node| node.name == segments[depth]) {
+                Some(index) => index,
+                None => {
+                    level.push(ModuleNode {
+                        name: segments[depth].to_string(),
+                        path: segments[..=depth].join("::"),
+                        file: None,
+                        children: Vec::new(),
+                    });
+                    level.len() - 1
+                }
+            };
+            if depth == segments.len() - 1 {
+                level[index].file.get_or_insert_with(|| file.clone());
+            }
+            level = &mut level[index].children;
+        }
+    }
+
+    sort_tree(&mut roots);
+    roots
+}
+
+fn sort_tree(nodes: &mut [ModuleNode]) {
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+    for node in nodes {
+        sort_tree(&mut node.children);
+    }
+}
+
+/// Lists the out-of-line `mod` declarations of `file`, with the files each may be loaded from:
+///
+/// - `#[path = "..."]`, relative to the declaring file's directory (or, inside an inline
+///   module, to that module's directory);
+/// - otherwise `foo.rs`, then `foo/mod.rs`, next to `lib.rs`/`main.rs`/`mod.rs` files and inside
+///   a directory named after the file for any other file (`src/a.rs` looks in `src/a/`).
+pub fn mod_declarations(file: &str, ast: &syn::File) -> Vec<ModDeclaration> {
+    let file = Path::new(file);
+    let file_dir = file.parent().unwrap_or(Path::new(""));
+    let is_mod_rs = file
+        .file_stem()
+        .is_some_and(|stem| matches!(stem.to_str(), Some("mod" | "lib" | "main")));
+    let module_dir = match file.file_stem() {
+        Some(stem) if !is_mod_rs => file_dir.join(stem),
+        _ => file_dir.to_path_buf(),
+    };
+
+    let mut declarations = Vec::new();
+    collect_mod_declarations(&ast.items, file_dir, &module_dir, &mut Vec::new(), true, &mut declarations);
+    declarations
+}
+
+fn collect_mod_declarations(
+    items: &[syn::Item],
+    file_dir: &Path,
+    module_dir: &Path,
+    inline_path: &mut Vec<String>,
+    inline_public: bool,
+    out: &mut Vec<ModDeclaration>,
+) {
+    for item in items {
+        let syn::Item::Mod(item_mod) = item else { continue };
+        let name = crate::visitors::ident_name(&item_mod.ident);
+        let path_attr = path_attribute(&item_mod.attrs);
+        let public = inline_public && matches!(item_mod.vis, syn::Visibility::Public(_));
+
+        match &item_mod.content {
+            Some((_, items)) => {
+                let child_dir = module_dir.join(path_attr.as_deref().unwrap_or(&name));
+                inline_path.push(name);
+                collect_mod_declarations(items, file_dir, &child_dir, inline_path, public, out);
+                inline_path.pop();
+            }
+            None => {
+                let candidates = match path_attr {
+                    Some(path) if inline_path.is_empty() => vec![file_dir.join(path)],
+                    Some(path) => vec![module_dir.join(path)],
+                    None => vec![module_dir.join(format!("{}.rs", name)), module_dir.join(&name).join("mod.rs")],
+                };
+                let module = inline_path.iter().cloned().chain(std::iter::once(name)).collect::<Vec<_>>().join("::");
+                out.push(ModDeclaration {
+                    module,
+                    candidates: candidates.iter().map(|path| normalize(path).to_string_lossy().to_string()).collect(),
+                    public,
+                });
+            }
+        }
+    }
+}
+
+fn path_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().find(|attr| attr.path().is_ident("path")).and_then(|attr| match &attr.meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(path), .. }),
+            ..
+        }) => Some(path.value()),
+        _ => None,
+    })
+}
+
+/// Lexically removes `.` and `..` components, so `src/../other.rs` matches a walked `other.rs`.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normalized.components().next_back(), Some(Component::Normal(_))) => {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Assigns a module path to every file in `declarations` (file -> its `mod` declarations).
+///
+/// Files named `lib.rs` or `main.rs` are crate roots; modules are then followed through their
+/// declarations, so `#[path]` and `mod.rs` layouts resolve the way rustc loads them. Files no
+/// declaration reaches fall back to `module_path_for_file`.
+///
+/// A lone crate root is `crate`. With several, each gets its own root named after its crate,
+/// with `(bin)` appended for `main.rs`, so a package's library and binary, or the members of a
+/// workspace, don't merge into one tree; unreached files then sit under their crate's name.
+pub fn resolve_module_paths(declarations: &HashMap<String, Vec<ModDeclaration>>) -> BTreeMap<String, String> {
+    let by_normalized: HashMap<PathBuf, &String> = declarations
+        .keys()
+        .map(|file| (normalize(Path::new(file)), file))
+        .collect();
+
+    let mut module_paths = BTreeMap::new();
+    let mut queue: VecDeque<&String> = VecDeque::new();
+    let mut roots: Vec<&String> = declarations
+        .keys()
+        .filter(|file| matches!(Path::new(file).file_name().and_then(|name| name.to_str()), Some("lib.rs" | "main.rs")))
+        .collect();
+    roots.sort();
+    let multiple_roots = roots.len() > 1;
+    let mut root_names = HashSet::new();
+    for root in roots {
+        let name = match multiple_roots {
+            true => unique_root_name(root, &mut root_names),
+            false => "crate".to_string(),
+        };
+        module_paths.insert(root.clone(), name);
+        queue.push_back(root);
+    }
+
+    while let Some(file) = queue.pop_front() {
+        let parent = module_paths[file].clone();
+        for declaration in &declarations[file] {
+            let Some(&target) = declaration.candidates.iter().find_map(|candidate| by_normalized.get(Path::new(candidate))) else {
+                continue;
+            };
+            if !module_paths.contains_key(target) {
+                module_paths.insert(target.clone(), format!("{}::{}", parent, declaration.module));
+                queue.push_back(target);
+            }
+        }
+    }
+
+    for file in declarations.keys() {
+        module_paths.entry(file.clone()).or_insert_with(|| {
+            let module_path = module_path_for_file(file);
+            match multiple_roots {
+                true => format!("{}{}", crate_name(file), &module_path["crate".len()..]),
+                false => module_path,
+            }
+        });
+    }
+    module_paths
+}
+
+/// Root module name for the crate root `file` among several: its crate's name, `(bin)` for a
+/// `main.rs`, and a `#2`, `#3`, ... suffix when another root already took that name.
+fn unique_root_name(file: &str, taken: &mut HashSet<String>) -> String {
+    let mut name = crate_name(file);
+    if Path::new(file).file_name().is_some_and(|name| name == "main.rs") {
+        name.push_str("(bin)");
+    }
+    let mut unique = name.clone();
+    for suffix in 2.. {
+        if taken.insert(unique.clone()) {
+            break;
+        }
+        unique = format!("{}#{}", name, suffix);
+    }
+    unique
+}
+
+/// Module paths declared without `pub` somewhere along the way, given each file's module path
+/// and `mod` declarations. Items under these modules aren't part of the public API.
+pub fn private_modules(
+    module_paths: &BTreeMap<String, String>,
+    declarations: &HashMap<String, Vec<ModDeclaration>>,
+) -> HashSet<String> {
+    declarations
+        .iter()
+        .flat_map(|(file, declarations)| {
+            declarations
+                .iter()
+                .filter(|declaration| !declaration.public)
+                .map(move |declaration| format!("{}::{}", module_paths[file], declaration.module))
+        })
+        .collect()
+}
+
+/// Whether `module_path` or any of its ancestors is in `private_modules`.
+pub fn is_under_private_module(module_path: &str, private_modules: &HashSet<String>) -> bool {
+    let segments: Vec<&str> = module_path.split("::").collect();
+    (1..=segments.len()).any(|len| private_modules.contains(&segments[..len].join("::")))
+}
+
+/// Removes inline `mod` blocks not declared `pub`, recursing into the ones that remain.
+pub fn strip_private_inline_modules(items: &mut Vec<syn::Item>) {
+    items.retain(|item| {
+        !matches!(item, syn::Item::Mod(item_mod) if item_mod.content.is_some() && !matches!(item_mod.vis, syn::Visibility::Public(_)))
+    });
+    for item in items {
+        if let syn::Item::Mod(syn::ItemMod { content: Some((_, items)), .. }) = item {
+            strip_private_inline_modules(items);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+};
+use crate::models::*;
+use crate::cache::*;
+
+pub async fn cache_stats(server: &MyServer) -> Result<CallToolResult, McpError> {
+    let stats = collect_stats(&server.cache).await;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&stats).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+pub(crate) async fn collect_stats(cache: &AstCache) -> CacheStats {
+    let total_bytes = cache.get_all().await.values().map(|code| code.len()).sum();
+    CacheStats {
+        file_count: cache.len().await,
+        total_bytes,
+    }
+}
@@ -0,0 +1,106 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use crate::models::*;
+use crate::cache::*;
+use crate::analysis::analyze_source_with_options;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+pub async fn call_path(
+    server: &MyServer,
+    Parameters(CallPathParams { from, to, position_options }): Parameters<CallPathParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut call_sites = Vec::new();
+    let mut functions = BTreeSet::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        let Ok(analysis) = analyze_source_with_options(path, code, position_options) else { continue };
+        functions.extend(analysis.calls.into_keys());
+        call_sites.extend(analysis.call_sites);
+    }
+    call_sites.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    let starts = matching_functions(&functions, &from);
+    let targets = matching_functions(&functions, &to);
+    for (name, matches) in [(&from, &starts), (&to, &targets)] {
+        if matches.is_empty() {
+            return Err(McpError::invalid_params("Unknown function", Some(json!({ "name": name }))));
+        }
+    }
+
+    let mut edges: HashMap<&str, Vec<(&str, &CallSite)>> = HashMap::new();
+    for site in &call_sites {
+        for callee in resolve_callee(&functions, &site.caller, &site.callee) {
+            edges.entry(site.caller.as_str()).or_default().push((callee, site));
+        }
+    }
+
+    // Breadth-first from every start at once, so the first target reached is the closest
+    let mut previous: HashMap<&str, Option<(&str, &CallSite)>> = starts.iter().map(|start| (*start, None)).collect();
+    let mut queue: VecDeque<&str> = starts.iter().copied().collect();
+    let mut reached = None;
+    while let Some(function) = queue.pop_front() {
+        if targets.contains(&function) {
+            reached = Some(function);
+            break;
+        }
+        for &(callee, site) in edges.get(function).into_iter().flatten() {
+            if !previous.contains_key(callee) {
+                previous.insert(callee, Some((function, site)));
+                queue.push_back(callee);
+            }
+        }
+    }
+
+    let Some(mut function) = reached else {
+        return Ok(CallToolResult::success(vec![Content::text(format!(
+            "No call path from `{}` to `{}`.",
+            from, to
+        ))]));
+    };
+    let mut path = CallPath { functions: vec![function.to_string()], hops: Vec::new() };
+    while let Some(&Some((caller, site))) = previous.get(function) {
+        path.functions.push(caller.to_string());
+        path.hops.push(site.clone());
+        function = caller;
+    }
+    path.functions.reverse();
+    path.hops.reverse();
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&path).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// Call graph keys named `name`: the exact key, or keys ending in `::name`.
+fn matching_functions<'a>(functions: &'a BTreeSet<String>, name: &str) -> Vec<&'a str> {
+    let suffix = format!("::{}", name);
+    functions
+        .iter()
+        .filter(|function| *function == name || function.ends_with(&suffix))
+        .map(String::as_str)
+        .collect()
+}
+
+/// Call graph keys a callee path (as written at the call site) may refer to. An exact key
+/// or a sibling in the caller's module wins; otherwise every key with a matching suffix.
+fn resolve_callee<'a>(functions: &'a BTreeSet<String>, caller: &str, callee: &str) -> Vec<&'a str> {
+    if let Some(function) = functions.get(callee) {
+        return vec![function.as_str()];
+    }
+    if let Some((module_prefix, _)) = caller.rsplit_once("::") {
+        if let Some(function) = functions.get(&format!("{}::{}", module_prefix, callee)) {
+            return vec![function.as_str()];
+        }
+    }
+    // `crate::a::f` and `a::f` are keyed without the file's module path
+    let name = callee.rsplit("::").next().unwrap_or(callee);
+    matching_functions(functions, name)
+        .into_iter()
+        .filter(|function| callee.ends_with(function) || function.ends_with(callee) || !callee.contains("::"))
+        .collect()
+}
@@ -0,0 +1,142 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde::Deserialize;
+use serde_json::json;
+use proc_macro2::LineColumn;
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::{locations_to_range, LineIndex};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// One line of `cargo check --message-format=json` output; only compiler messages matter.
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcDiagnostic>,
+}
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    level: String,
+    code: Option<RustcCode>,
+    spans: Vec<RustcSpan>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+/// Lines and columns are 1-based, columns counted in chars.
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+}
+
+pub async fn cargo_check(
+    _server: &MyServer,
+    Parameters(CargoCheckParams { manifest_dir, position_options }): Parameters<CargoCheckParams>,
+) -> Result<CallToolResult, McpError> {
+    let diagnostics = run_cargo_diagnostics("check", &manifest_dir, position_options).await?;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&diagnostics).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// Runs `cargo <subcommand> --message-format=json` in `manifest_dir` and maps the compiler
+/// messages to diagnostics. Fails only when cargo can't run or exits with an error that
+/// produced no diagnostics (e.g. a broken manifest).
+pub(crate) async fn run_cargo_diagnostics(
+    subcommand: &str,
+    manifest_dir: &str,
+    position_options: PositionOptions,
+) -> Result<Vec<Diagnostic>, McpError> {
+    let manifest_dir = PathBuf::from(manifest_dir);
+    if !manifest_dir.join("Cargo.toml").is_file() {
+        return Err(McpError::invalid_params(
+            "No Cargo.toml found",
+            Some(json!({ "manifest_dir": manifest_dir.to_string_lossy() })),
+        ));
+    }
+
+    let output = tokio::process::Command::new("cargo")
+        .args([subcommand, "--message-format=json"])
+        .current_dir(&manifest_dir)
+        .output()
+        .await
+        .map_err(|e| McpError::internal_error("Failed to run cargo", Some(json!({ "error": e.to_string() }))))?;
+
+    let mut sources = HashMap::new();
+    let mut diagnostics = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(CargoMessage { reason, message: Some(message) }) = serde_json::from_str(line) else {
+            continue;
+        };
+        if reason != "compiler-message" {
+            continue;
+        }
+        // Summaries like "aborting due to 2 previous errors" have no span to point at
+        let Some(span) = message.spans.iter().find(|span| span.is_primary) else {
+            continue;
+        };
+
+        let file = resolve_span_file(&manifest_dir, &span.file_name);
+        // Re-encoding positions needs the file text; the defaults match rustc's columns
+        if !position_options.is_default() && !sources.contains_key(&file) {
+            let source = tokio::fs::read_to_string(&file).await.unwrap_or_default();
+            sources.insert(file.clone(), source);
+        }
+        let source = sources.get(&file).map(String::as_str).unwrap_or_default();
+        diagnostics.push(Diagnostic {
+            message: message.message,
+            range: span_range(span, position_options.line_index(source).as_ref()),
+            severity: severity(&message.level),
+            file: Some(file.to_string_lossy().to_string()),
+            code: message.code.map(|code| code.code),
+        });
+    }
+
+    if !output.status.success() && diagnostics.is_empty() {
+        return Err(McpError::internal_error(
+            format!("cargo {subcommand} failed"),
+            Some(json!({ "stderr": String::from_utf8_lossy(&output.stderr) })),
+        ));
+    }
+    Ok(diagnostics)
+}
+
+fn severity(level: &str) -> Severity {
+    match level {
+        "warning" => Severity::Warning,
+        "note" => Severity::Info,
+        "help" => Severity::Hint,
+        _ => Severity::Error,
+    }
+}
+
+/// Span file names are relative to the workspace root, which may be an ancestor of the
+/// manifest directory; the closest ancestor containing the file wins.
+fn resolve_span_file(manifest_dir: &Path, file_name: &str) -> PathBuf {
+    manifest_dir
+        .ancestors()
+        .map(|dir| dir.join(file_name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| manifest_dir.join(file_name))
+}
+
+fn span_range(span: &RustcSpan, line_index: Option<&LineIndex>) -> Range {
+    let start = LineColumn { line: span.line_start, column: span.column_start.saturating_sub(1) };
+    let end = LineColumn { line: span.line_end, column: span.column_end.saturating_sub(1) };
+    locations_to_range(start, end, line_index)
+}
@@ -4,44 +4,133 @@ use rmcp::{
     handler::server::wrapper::Parameters,
 };
 use serde_json::json;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
 use crate::models::*;
+use crate::line_index::span_to_range;
 use crate::cache::*;
+use std::borrow::Cow;
 
 pub async fn check_file(
     server: &MyServer,
-    Parameters(CheckFileParams { path }): Parameters<CheckFileParams>,
+    Parameters(CheckFileParams { path, source, position_options }): Parameters<CheckFileParams>,
 ) -> Result<CallToolResult, McpError> {
-    let code = tokio::fs::read_to_string(&path).await
-        .map_err(|e| McpError::invalid_params("Failed to read file", Some(json!({ "error": e.to_string() }))))?;
-
-    // Parse the file and determine if it was successful
-    let parse_success = syn::parse_file(&code).is_ok();
-
-    let diagnostics = if parse_success {
-        // Only cache if parsing was successful
-        server.cache.insert(path.to_string(), code.clone()).await;
-        vec![]
-    } else {
-        // Re-parse to get error details (this is safe since we know it failed)
-        match syn::parse_file(&code) {
-            Ok(_) => unreachable!(), // We already checked this fails
-            Err(e) => {
-                let span = e.span();
-                let start = span.start();
-                let end = span.end();
-                vec![Diagnostic {
-                    message: e.to_string(),
-                    range: Range {
-                        start: Position { line: start.line, character: start.column },
-                        end: Position { line: end.line, character: end.column },
-                    },
-                    severity: "error".to_string(),
-                }]
-            }
-        }
+    let code = match (&source, &path) {
+        (Some(source), _) => source.clone(),
+        (None, Some(path)) => tokio::fs::read_to_string(path).await
+            .map_err(|e| McpError::invalid_params("Failed to read file", Some(json!({ "error": e.to_string() }))))?,
+        (None, None) => return Err(McpError::invalid_params("Either `path` or `source` must be provided", None)),
     };
 
+    let diagnostics = syntax_diagnostics(&code, position_options);
+
+    // Only cache if parsing was successful; inline source without a path has no cache key
+    if diagnostics.is_empty() {
+        if let Some(path) = &path {
+            server.cache.insert(path.to_string(), code.clone()).await;
+        }
+    }
+
     Ok(CallToolResult::success(vec![Content::text(
         serde_json::to_string(&diagnostics).map_err(|e| McpError::internal_error(e.to_string(), None))?
     )]))
-}
\ No newline at end of file
+}
+
+/// Syntax errors of `code`, empty when it parses.
+pub(crate) fn syntax_diagnostics(code: &str, position_options: PositionOptions) -> Vec<Diagnostic> {
+    let line_index = position_options.line_index(code);
+    match syn::parse_file(code) {
+        Ok(_) => vec![],
+        Err(e) => collect_syntax_errors(code, e)
+            .into_iter()
+            .map(|error| Diagnostic {
+                message: error.to_string(),
+                range: span_to_range(error.span(), line_index.as_ref()),
+                severity: Severity::Error,
+                file: None,
+                code: None,
+            })
+            .collect(),
+    }
+}
+
+/// Recovers from the first syntax error so that independent mistakes are all reported.
+///
+/// `syn` stops at the first error, so the token stream is split into top-level items
+/// (terminated by a `;` or a brace-delimited body) and each item is parsed on its own.
+/// `const`, `static`, `type` and `use` items only end at their `;`, since their values may
+/// hold braces, as in `const X: u8 = if A { 1 } else { 2 };`.
+/// Spans come from the original tokenization, so positions still refer to the whole file.
+/// If the source can't even be tokenized (e.g. unbalanced delimiters), only the original
+/// error is returned.
+///
+/// Like `syn::parse_file`, a leading byte order mark and a shebang line are skipped.
+fn collect_syntax_errors(code: &str, first_error: syn::Error) -> Vec<syn::Error> {
+    let Ok(tokens) = without_preamble(code).parse::<TokenStream>() else {
+        return first_error.into_iter().collect();
+    };
+
+    let mut errors = Vec::new();
+    let mut item = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        let ends_item = match &token {
+            TokenTree::Punct(punct) => punct.as_char() == ';',
+            TokenTree::Group(group) => group.delimiter() == Delimiter::Brace && !ends_at_semicolon(&item),
+            _ => false,
+        };
+        item.push(token);
+
+        if ends_item {
+            // Items like `const X: S = S { .. };` end with a brace group followed by `;`
+            if matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ';') {
+                item.extend(tokens.next());
+            }
+            if let Err(e) = syn::parse2::<syn::File>(item.drain(..).collect()) {
+                errors.extend(e);
+            }
+        }
+    }
+    if !item.is_empty() {
+        if let Err(e) = syn::parse2::<syn::File>(item.into_iter().collect()) {
+            errors.extend(e);
+        }
+    }
+
+    if errors.is_empty() {
+        // Splitting changed the meaning somewhere; fall back to the original error
+        return first_error.into_iter().collect();
+    }
+    errors
+}
+
+/// Whether the item begun by `tokens` runs until a `;` whatever braces it holds: a `const`
+/// (other than a `const fn`), `static`, `type` or `use` item. Attributes and the visibility's
+/// path are groups, so only the leading keywords are idents.
+fn ends_at_semicolon(tokens: &[TokenTree]) -> bool {
+    let mut keywords = tokens
+        .iter()
+        .filter_map(|token| match token {
+            TokenTree::Ident(ident) => Some(ident.to_string()),
+            _ => None,
+        })
+        .skip_while(|keyword| matches!(keyword.as_str(), "pub" | "unsafe" | "async" | "default" | "extern"));
+    match keywords.next().as_deref() {
+        Some("const") => !matches!(keywords.next().as_deref(), Some("fn" | "unsafe" | "async" | "extern")),
+        Some("static" | "type" | "use") => true,
+        _ => false,
+    }
+}
+
+/// `code` without a leading byte order mark and with a shebang line blanked out, neither of
+/// which plain tokenization accepts. Line and column numbers stay those of the original.
+fn without_preamble(code: &str) -> Cow<'_, str> {
+    let code = code.strip_prefix('\u{feff}').unwrap_or(code);
+    match code.strip_prefix("#!") {
+        // `#![attr]` is an inner attribute, not a shebang
+        Some(rest) if !rest.trim_start().starts_with('[') => {
+            let end = code.find('\n').unwrap_or(code.len());
+            Cow::Owned(" ".repeat(code[..end].chars().count()) + &code[end..])
+        }
+        _ => Cow::Borrowed(code),
+    }
+}
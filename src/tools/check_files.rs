@@ -0,0 +1,59 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use futures::stream::{self, StreamExt};
+use crate::models::*;
+use crate::cache::*;
+use crate::tools::check_file::syntax_diagnostics;
+use std::collections::BTreeMap;
+
+/// Files read and parsed at the same time.
+const CONCURRENCY: usize = 16;
+
+pub async fn check_files(
+    server: &MyServer,
+    Parameters(CheckFilesParams { paths, position_options }): Parameters<CheckFilesParams>,
+) -> Result<CallToolResult, McpError> {
+    let results: BTreeMap<String, Vec<Diagnostic>> = stream::iter(paths)
+        .map(|path| async move {
+            let diagnostics = match tokio::fs::read_to_string(&path).await {
+                Ok(code) => {
+                    let diagnostics = syntax_diagnostics(&code, position_options);
+                    // Like check_file, only files that parse are cached
+                    if diagnostics.is_empty() {
+                        server.cache.insert(path.clone(), code).await;
+                    }
+                    diagnostics
+                }
+                Err(e) => vec![read_error(&e, position_options)],
+            };
+            (path, diagnostics)
+        })
+        .buffer_unordered(CONCURRENCY)
+        .collect()
+        .await;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&results).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// A file that can't be read is reported as one error at its start, so one bad path doesn't
+/// fail the whole batch.
+fn read_error(error: &std::io::Error, position_options: PositionOptions) -> Diagnostic {
+    let start = Position { line: if position_options.zero_based_lines { 0 } else { 1 }, character: 0 };
+    Diagnostic {
+        message: format!("Failed to read file: {}", error),
+        range: Range {
+            start: start.clone(),
+            end: start,
+            start_byte: position_options.byte_offsets.then_some(0),
+            end_byte: position_options.byte_offsets.then_some(0),
+        },
+        severity: Severity::Error,
+        file: None,
+        code: None,
+    }
+}
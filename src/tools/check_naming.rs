@@ -0,0 +1,71 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn check_naming(
+    server: &MyServer,
+    Parameters(CheckNamingParams { allowlist, position_options }): Parameters<CheckNamingParams>,
+) -> Result<CallToolResult, McpError> {
+    let allowlist = allowlist.unwrap_or_default();
+    let mut violations = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut collector = SymbolCollector::new(path.clone(), line_index.as_ref());
+            collector.visit_file(&ast);
+
+            for symbol in collector.out {
+                if allowlist.contains(&symbol.name) {
+                    continue;
+                }
+                let expected = expected_convention(symbol.kind);
+                if !follows(&symbol.name, expected) {
+                    violations.push(NamingViolation {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        expected,
+                        file: symbol.file,
+                        range: symbol.range,
+                    });
+                }
+            }
+        }
+    }
+    violations.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&violations).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+fn expected_convention(kind: SymbolKind) -> NamingConvention {
+    match kind {
+        SymbolKind::Fn | SymbolKind::Method | SymbolKind::Module | SymbolKind::Macro | SymbolKind::Field => NamingConvention::SnakeCase,
+        SymbolKind::Const | SymbolKind::Static => NamingConvention::ScreamingSnakeCase,
+        SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Union | SymbolKind::Trait | SymbolKind::TypeAlias | SymbolKind::Variant => {
+            NamingConvention::UpperCamelCase
+        }
+    }
+}
+
+/// Checks a name the way rustc's `non_snake_case`, `non_camel_case_types` and
+/// `non_upper_case_globals` lints do; leading and trailing underscores are ignored.
+fn follows(name: &str, convention: NamingConvention) -> bool {
+    let name = name.trim_matches('_');
+    if name.is_empty() {
+        return true;
+    }
+    match convention {
+        NamingConvention::SnakeCase => !name.chars().any(char::is_uppercase) && !name.contains("__"),
+        NamingConvention::ScreamingSnakeCase => !name.chars().any(char::is_lowercase) && !name.contains("__"),
+        NamingConvention::UpperCamelCase => !name.starts_with(char::is_lowercase) && !name.contains('_'),
+    }
+}
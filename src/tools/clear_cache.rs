@@ -0,0 +1,16 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+};
+use crate::cache::*;
+use super::cache_stats::collect_stats;
+
+/// Empties the cache and reports what was evicted.
+pub async fn clear_cache(server: &MyServer) -> Result<CallToolResult, McpError> {
+    let evicted = collect_stats(&server.cache).await;
+    server.cache.clear().await;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&evicted).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,23 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use crate::models::*;
+use crate::cache::*;
+use super::cargo_check::run_cargo_diagnostics;
+
+pub async fn clippy(
+    _server: &MyServer,
+    Parameters(ClippyParams { manifest_dir, min_severity, position_options }): Parameters<ClippyParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut diagnostics = run_cargo_diagnostics("clippy", &manifest_dir, position_options).await?;
+    if let Some(min_severity) = min_severity {
+        // Severities are declared most severe first
+        diagnostics.retain(|diagnostic| diagnostic.severity <= min_severity);
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&diagnostics).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
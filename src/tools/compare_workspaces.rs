@@ -0,0 +1,64 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use crate::models::*;
+use crate::cache::*;
+use crate::tools::diff_public_api::{by_key, tree_symbols, TreeSymbol};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub async fn compare_workspaces(
+    _server: &MyServer,
+    Parameters(CompareWorkspacesParams { old_root, new_root }): Parameters<CompareWorkspacesParams>,
+) -> Result<CallToolResult, McpError> {
+    let comparison = tokio::task::spawn_blocking(move || -> Result<WorkspaceComparison, String> {
+        let old = tree_symbols(Path::new(&old_root), false)?;
+        let new = tree_symbols(Path::new(&new_root), false)?;
+        Ok(compare(&old, &new))
+    })
+    .await
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?
+    .map_err(|e| McpError::invalid_params("Failed to read source tree", Some(json!({ "error": e }))))?;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&comparison).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+fn compare(old: &[TreeSymbol], new: &[TreeSymbol]) -> WorkspaceComparison {
+    let old_items = by_key(old);
+    let new_items = by_key(new);
+    let mut files = BTreeMap::new();
+    let qualified = |item: &TreeSymbol| QualifiedSymbol { kind: item.symbol.kind, name: item.qualified_name.clone() };
+
+    let (mut added, mut removed, mut unchanged) = (0, 0, 0);
+    for (key, new_item) in &new_items {
+        if old_items.contains_key(key) {
+            file_diff(&mut files, &new_item.symbol.file).unchanged.push(qualified(new_item));
+            unchanged += 1;
+        } else {
+            file_diff(&mut files, &new_item.symbol.file).added.push(qualified(new_item));
+            added += 1;
+        }
+    }
+    for (key, old_item) in &old_items {
+        if !new_items.contains_key(key) {
+            file_diff(&mut files, &old_item.symbol.file).removed.push(qualified(old_item));
+            removed += 1;
+        }
+    }
+
+    WorkspaceComparison { added, removed, unchanged, files: files.into_values().collect() }
+}
+
+fn file_diff<'a>(files: &'a mut BTreeMap<String, FileSymbolDiff>, file: &str) -> &'a mut FileSymbolDiff {
+    files.entry(file.to_string()).or_insert_with(|| FileSymbolDiff {
+        file: file.to_string(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        unchanged: Vec::new(),
+    })
+}
@@ -0,0 +1,35 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+const DEFAULT_MAX_RETURNS: usize = 3;
+
+pub async fn detect_early_returns(
+    server: &MyServer,
+    Parameters(DetectEarlyReturnsParams { max_returns, position_options }): Parameters<DetectEarlyReturnsParams>,
+) -> Result<CallToolResult, McpError> {
+    let max_returns = max_returns.unwrap_or(DEFAULT_MAX_RETURNS);
+    let mut functions = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = EarlyReturnFinder::new(path.clone(), line_index.as_ref(), max_returns);
+            finder.visit_file(&ast);
+            functions.extend(finder.out);
+        }
+    }
+
+    functions.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&functions).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
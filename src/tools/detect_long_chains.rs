@@ -0,0 +1,35 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+const DEFAULT_MAX_LENGTH: usize = 5;
+
+pub async fn detect_long_chains(
+    server: &MyServer,
+    Parameters(DetectLongChainsParams { max_length, position_options }): Parameters<DetectLongChainsParams>,
+) -> Result<CallToolResult, McpError> {
+    let max_length = max_length.unwrap_or(DEFAULT_MAX_LENGTH);
+    let mut chains = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = LongChainFinder::new(path.clone(), line_index.as_ref(), max_length);
+            finder.visit_file(&ast);
+            chains.extend(finder.out);
+        }
+    }
+
+    chains.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&chains).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,298 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use walkdir::WalkDir;
+use syn::visit::Visit;
+use serde_json::json;
+use crate::analysis::strip_cfg_test_items;
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::{span_to_range, LineIndex};
+use crate::manifest::library_root;
+use crate::visitors::*;
+use crate::modules::{is_under_private_module, mod_declarations, private_modules, resolve_module_paths, strip_private_inline_modules};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub async fn diff_public_api(
+    _server: &MyServer,
+    Parameters(DiffPublicApiParams { old_root, new_root }): Parameters<DiffPublicApiParams>,
+) -> Result<CallToolResult, McpError> {
+    let diff = tokio::task::spawn_blocking(move || -> Result<PublicApiDiff, String> {
+        let old = tree_symbols(Path::new(&old_root), true)?;
+        let new = tree_symbols(Path::new(&new_root), true)?;
+        Ok(diff_api(&old, &new))
+    })
+    .await
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?
+    .map_err(|e| McpError::invalid_params("Failed to read source tree", Some(json!({ "error": e }))))?;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&diff).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// A symbol of a source tree read straight from disk, bypassing the cache.
+pub(crate) struct TreeSymbol {
+    /// Module path, enclosing type or trait, and name, e.g. `crate::parser::Parser::new`.
+    pub qualified_name: String,
+    /// Carries its signature; `file` is relative to the tree's root.
+    pub symbol: SymbolInfo,
+    /// Public fields of a struct with named fields, or variants of an enum.
+    pub members: Vec<String>,
+    /// Whether other crates can build the struct with a literal or match the enum
+    /// exhaustively: no `#[non_exhaustive]`, and for structs, no private fields.
+    pub exhaustive: bool,
+    /// A trait method without a default body, which every implementor has to define.
+    pub required: bool,
+}
+
+/// Collects the symbols of every `.rs` file under `root`, skipping `target` and hidden
+/// directories and `#[cfg(test)]` items. With `public_only`, only the library target's files
+/// are read, and only items other crates can reach are kept: `pub`, and not inside a private module.
+pub(crate) fn tree_symbols(root: &Path, public_only: bool) -> Result<Vec<TreeSymbol>, String> {
+    if !root.is_dir() {
+        return Err(format!("{} is not a directory", root.display()));
+    }
+
+    let sources = match public_only {
+        true => library_sources(root)?,
+        false => tree_sources(root)
+            .into_iter()
+            .map(|path| {
+                let code = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                Ok((path.to_string_lossy().to_string(), code))
+            })
+            .collect::<Result<_, String>>()?,
+    };
+
+    let mut declarations = HashMap::new();
+    let mut symbols = Vec::new();
+    for (path, code) in &sources {
+        let Ok(mut ast) = syn::parse_file(code) else { continue };
+        declarations.insert(path.clone(), mod_declarations(path, &ast));
+        strip_cfg_test_items(&mut ast.items);
+        if public_only {
+            strip_private_inline_modules(&mut ast.items);
+        }
+
+        let relative = Path::new(path).strip_prefix(root).unwrap_or(Path::new(path)).to_string_lossy().to_string();
+        let source = LineIndex::new(code, PositionOptions::default());
+        let mut collector = SymbolCollector::new(relative, None).with_signatures(&source);
+        collector.visit_file(&ast);
+        let mut members = MemberCollector::default();
+        members.visit_file(&ast);
+        symbols.push((path.clone(), collector.out, members));
+    }
+
+    let module_paths = resolve_module_paths(&declarations);
+    let private_modules = private_modules(&module_paths, &declarations);
+    let mut tree = Vec::new();
+    for (path, file_symbols, mut collected) in symbols {
+        let module_path = &module_paths[&path];
+        if public_only && is_under_private_module(module_path, &private_modules) {
+            continue;
+        }
+        for symbol in file_symbols {
+            if public_only && symbol.visibility != "public" {
+                continue;
+            }
+            let qualified_name = match &symbol.container {
+                Some(container) => format!("{}::{}::{}", module_path, container, symbol.name),
+                None => format!("{}::{}", module_path, symbol.name),
+            };
+            let (members, exhaustive) = collected.members.remove(&symbol.range.start).unwrap_or((Vec::new(), true));
+            let required = collected.required_methods.contains(&symbol.range.start);
+            tree.push(TreeSymbol { qualified_name, symbol, members, exhaustive, required });
+        }
+    }
+    tree.sort_by(|a, b| (&a.symbol.file, &a.symbol.range.start).cmp(&(&b.symbol.file, &b.symbol.range.start)));
+    Ok(tree)
+}
+
+/// Source of every file of the library target, from its root through the out-of-line modules
+/// it declares; binaries, benches, tests, and examples aren't part of it.
+fn library_sources(root: &Path) -> Result<Vec<(String, String)>, String> {
+    let lib_root = library_root(root)?.ok_or_else(|| format!("{} has no library target", root.display()))?;
+    let mut queue = vec![lib_root.to_string_lossy().to_string()];
+    let mut seen: HashSet<String> = queue.iter().cloned().collect();
+    let mut sources = Vec::new();
+    while let Some(path) = queue.pop() {
+        let code = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+        if let Ok(ast) = syn::parse_file(&code) {
+            for declaration in mod_declarations(&path, &ast) {
+                // Modules gated out by `cfg` may have no file at all
+                let Some(file) = declaration.candidates.into_iter().find(|file| Path::new(file).is_file()) else { continue };
+                if seen.insert(file.clone()) {
+                    queue.push(file);
+                }
+            }
+        }
+        sources.push((path, code));
+    }
+    sources.sort();
+    Ok(sources)
+}
+
+fn tree_sources(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || (entry.file_name() != "target" && !entry.file_name().to_string_lossy().starts_with('.'))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "rs"))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Public fields and variants of each struct and enum, keyed by where its name starts.
+#[derive(Default)]
+struct MemberCollector {
+    members: HashMap<Position, (Vec<String>, bool)>,
+    /// Where the names of trait methods without a default body start.
+    required_methods: HashSet<Position>,
+}
+
+impl<'ast> Visit<'ast> for MemberCollector {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        let public = |field: &&syn::Field| matches!(field.vis, syn::Visibility::Public(_));
+        let names = match &i.fields {
+            syn::Fields::Named(fields) => fields.named.iter().filter(public).filter_map(|field| field.ident.as_ref().map(ident_name)).collect(),
+            _ => Vec::new(),
+        };
+        let exhaustive = !is_non_exhaustive(&i.attrs) && i.fields.iter().all(|field| public(&field));
+        self.members.insert(span_to_range(i.ident.span(), None).start, (names, exhaustive));
+        syn::visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        let names = i.variants.iter().map(|variant| ident_name(&variant.ident)).collect();
+        self.members.insert(span_to_range(i.ident.span(), None).start, (names, !is_non_exhaustive(&i.attrs)));
+        syn::visit::visit_item_enum(self, i);
+    }
+
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        for item in &i.items {
+            if let syn::TraitItem::Fn(method) = item {
+                if method.default.is_none() {
+                    self.required_methods.insert(span_to_range(method.sig.ident.span(), None).start);
+                }
+            }
+        }
+        syn::visit::visit_item_trait(self, i);
+    }
+}
+
+fn is_non_exhaustive(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("non_exhaustive"))
+}
+
+/// Compares two public API snapshots. Removed items and members, changed signatures, and
+/// newly non-exhaustive types are major; additions are minor unless they extend a type other
+/// crates build or match exhaustively, or add a trait method implementors must define;
+/// doc-only changes are patch.
+fn diff_api(old: &[TreeSymbol], new: &[TreeSymbol]) -> PublicApiDiff {
+    let old_items = by_key(old);
+    let new_items = by_key(new);
+
+    let mut changes = Vec::new();
+    let change = |change, impact, item: &TreeSymbol, detail: String| ApiChange {
+        change,
+        impact,
+        kind: item.symbol.kind,
+        name: item.qualified_name.clone(),
+        detail,
+        old_signature: None,
+        new_signature: None,
+        file: item.symbol.file.clone(),
+    };
+
+    for (key, old_item) in &old_items {
+        let Some(new_item) = new_items.get(key) else {
+            changes.push(ApiChange {
+                old_signature: old_item.symbol.signature.clone(),
+                ..change(ApiChangeKind::Removed, SemverImpact::Major, old_item, "removed".to_string())
+            });
+            continue;
+        };
+
+        let old_signature = old_item.symbol.signature.as_deref().map(normalize_whitespace);
+        let new_signature = new_item.symbol.signature.as_deref().map(normalize_whitespace);
+        let mut item_changes = Vec::new();
+        if old_signature != new_signature {
+            item_changes.push(ApiChange {
+                old_signature: old_item.symbol.signature.clone(),
+                new_signature: new_item.symbol.signature.clone(),
+                ..change(ApiChangeKind::Changed, SemverImpact::Major, new_item, "signature changed".to_string())
+            });
+        }
+
+        let member = if old_item.symbol.kind == SymbolKind::Enum { "variant" } else { "field" };
+        for removed in old_item.members.iter().filter(|name| !new_item.members.contains(name)) {
+            let detail = format!("{} `{}` removed", member, removed);
+            item_changes.push(change(ApiChangeKind::Changed, SemverImpact::Major, new_item, detail));
+        }
+        for added in new_item.members.iter().filter(|name| !old_item.members.contains(name)) {
+            let impact = if old_item.exhaustive { SemverImpact::Major } else { SemverImpact::Minor };
+            let detail = format!("{} `{}` added", member, added);
+            item_changes.push(change(ApiChangeKind::Changed, impact, new_item, detail));
+        }
+        if !old_item.required && new_item.required {
+            item_changes.push(change(ApiChangeKind::Changed, SemverImpact::Major, new_item, "default body removed".to_string()));
+        }
+        if old_item.exhaustive && !new_item.exhaustive {
+            let detail = if old_item.symbol.kind == SymbolKind::Enum {
+                "no longer matchable exhaustively"
+            } else {
+                "no longer constructible with a struct literal"
+            };
+            item_changes.push(change(ApiChangeKind::Changed, SemverImpact::Major, new_item, detail.to_string()));
+        }
+
+        if item_changes.is_empty() && old_item.symbol.docs != new_item.symbol.docs {
+            item_changes.push(change(ApiChangeKind::Changed, SemverImpact::Patch, new_item, "docs changed".to_string()));
+        }
+        changes.extend(item_changes);
+    }
+
+    for (key, new_item) in &new_items {
+        if !old_items.contains_key(key) {
+            // Every implementor of the trait would have to define it
+            let (impact, detail) = match new_item.required {
+                true => (SemverImpact::Major, "added without a default body"),
+                false => (SemverImpact::Minor, "added"),
+            };
+            changes.push(ApiChange {
+                new_signature: new_item.symbol.signature.clone(),
+                ..change(ApiChangeKind::Added, impact, new_item, detail.to_string())
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| b.impact.cmp(&a.impact).then_with(|| a.name.cmp(&b.name)));
+    PublicApiDiff {
+        required_bump: changes.iter().map(|change| change.impact).max(),
+        changes,
+    }
+}
+
+/// Symbols keyed by kind and qualified name.
+pub(crate) fn by_key(symbols: &[TreeSymbol]) -> BTreeMap<(SymbolKind, &str), &TreeSymbol> {
+    let mut map = BTreeMap::new();
+    for symbol in symbols {
+        // Items declared twice (e.g. under different `cfg`s) are compared by their first declaration
+        map.entry((symbol.symbol.kind, symbol.qualified_name.as_str())).or_insert(symbol);
+    }
+    map
+}
+
+/// Collapses whitespace runs so reformatting alone doesn't count as a signature change.
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,195 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use crate::models::*;
+use crate::cache::*;
+
+pub async fn file_comment_stats(
+    server: &MyServer,
+    Parameters(FileCommentStatsParams { path }): Parameters<FileCommentStatsParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut stats = match path {
+        Some(path) => {
+            let code = match server.cache.get(&path).await {
+                Some(code) => code,
+                None => tokio::fs::read_to_string(&path).await
+                    .map_err(|e| McpError::invalid_params("Failed to read file", Some(json!({ "error": e.to_string() }))))?,
+            };
+            vec![comment_stats(&path, &code)]
+        }
+        None => server.cache.get_all().await
+            .iter()
+            .map(|(path, code)| comment_stats(path, code))
+            .collect(),
+    };
+    stats.sort_by(|a, b| a.file.cmp(&b.file));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&stats).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// What the lexer is inside of at a given char.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Code,
+    LineComment,
+    /// Block comments nest in Rust, so the depth is tracked.
+    BlockComment(usize),
+    Str,
+    /// A raw string closed by `"` followed by this many `#`.
+    RawStr(usize),
+}
+
+/// Classifies every line of `code` as code, comment, or blank.
+///
+/// syn drops comments, so this is a small lexer of its own: it knows enough about string,
+/// raw string, and char literals not to mistake a `//` or `/*` inside them for a comment,
+/// and keeps track of nested block comments across lines. A line with both code and a
+/// trailing comment counts as code; whitespace inside a comment or string doesn't count.
+fn comment_stats(file: &str, code: &str) -> FileCommentStats {
+    let (mut code_lines, mut comment_lines, mut blank_lines) = (0, 0, 0);
+    let mut state = State::Code;
+    let chars: Vec<char> = code.strip_prefix('\u{feff}').unwrap_or(code).chars().collect();
+    let (mut has_code, mut has_comment) = (false, false);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c == '\n' {
+            match (has_code, has_comment) {
+                (true, _) => code_lines += 1,
+                (false, true) => comment_lines += 1,
+                (false, false) => blank_lines += 1,
+            }
+            (has_code, has_comment) = (false, false);
+            if state == State::LineComment {
+                state = State::Code;
+            }
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        match state {
+            State::Code => match (c, next) {
+                ('/', Some('/')) => {
+                    has_comment = true;
+                    state = State::LineComment;
+                    i += 2;
+                }
+                ('/', Some('*')) => {
+                    has_comment = true;
+                    state = State::BlockComment(1);
+                    i += 2;
+                }
+                ('"', _) => {
+                    has_code = true;
+                    state = State::Str;
+                    i += 1;
+                }
+                ('\'', _) => {
+                    has_code = true;
+                    i += char_literal_len(&chars[i..]);
+                }
+                _ => {
+                    has_code = true;
+                    match raw_string_hashes(&chars[i..]) {
+                        Some((prefix_len, hashes)) => {
+                            state = State::RawStr(hashes);
+                            i += prefix_len;
+                        }
+                        // Skip the whole identifier so an `r` inside one isn't taken for a raw string
+                        None if c.is_alphanumeric() || c == '_' => {
+                            while chars.get(i).is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+                                i += 1;
+                            }
+                        }
+                        None => i += 1,
+                    }
+                }
+            },
+            State::LineComment => i += 1,
+            State::BlockComment(depth) => {
+                has_comment = true;
+                match (c, next) {
+                    ('/', Some('*')) => {
+                        state = State::BlockComment(depth + 1);
+                        i += 2;
+                    }
+                    ('*', Some('/')) => {
+                        state = if depth == 1 { State::Code } else { State::BlockComment(depth - 1) };
+                        i += 2;
+                    }
+                    _ => i += 1,
+                }
+            }
+            State::Str => {
+                has_code = true;
+                match c {
+                    // The escaped char may be a quote or the newline of a line continuation
+                    '\\' if next != Some('\n') => i += 2,
+                    '"' => {
+                        state = State::Code;
+                        i += 1;
+                    }
+                    _ => i += 1,
+                }
+            }
+            State::RawStr(hashes) => {
+                has_code = true;
+                let closes = c == '"' && (1..=hashes).all(|k| chars.get(i + k) == Some(&'#'));
+                if closes {
+                    state = State::Code;
+                    i += 1 + hashes;
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+    // The last line has no newline after it when the file doesn't end in one
+    match (has_code, has_comment) {
+        (true, _) => code_lines += 1,
+        (false, true) => comment_lines += 1,
+        (false, false) if chars.last().is_some_and(|c| *c != '\n') => blank_lines += 1,
+        (false, false) => {}
+    }
+
+    FileCommentStats {
+        file: file.to_string(),
+        code_lines,
+        comment_lines,
+        blank_lines,
+        ratio: if code_lines == 0 { 0.0 } else { comment_lines as f64 / code_lines as f64 },
+    }
+}
+
+/// Length of the char literal starting at `chars[0] == '\''`, or 1 for a lifetime or label,
+/// whose quote is never closed.
+fn char_literal_len(chars: &[char]) -> usize {
+    match chars.get(1) {
+        Some('\\') => chars.iter().skip(3).position(|c| *c == '\'').map_or(1, |end| end + 4),
+        Some(_) if chars.get(2) == Some(&'\'') => 3,
+        _ => 1,
+    }
+}
+
+/// For a raw string opener (`r"`, `r#"`, `br##"`, ...) at the start of `chars`, the length
+/// of the opener and the number of `#`s that will close it.
+fn raw_string_hashes(chars: &[char]) -> Option<(usize, usize)> {
+    let prefix = match chars {
+        ['r', ..] => 1,
+        ['b' | 'c', 'r', ..] => 2,
+        _ => return None,
+    };
+    let hashes = chars[prefix..].iter().take_while(|c| **c == '#').count();
+    (chars.get(prefix + hashes) == Some(&'"')).then_some((prefix + hashes + 1, hashes))
+}
@@ -0,0 +1,32 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_await_in_loops(
+    server: &MyServer,
+    Parameters(FindAwaitInLoopsParams { position_options }): Parameters<FindAwaitInLoopsParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut awaits = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = AwaitInLoopFinder::new(path.clone(), line_index.as_ref());
+            finder.visit_file(&ast);
+            awaits.extend(finder.out);
+        }
+    }
+
+    awaits.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&awaits).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,37 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_blocking_in_async(
+    server: &MyServer,
+    Parameters(FindBlockingInAsyncParams { extra_blocking_calls, position_options }): Parameters<FindBlockingInAsyncParams>,
+) -> Result<CallToolResult, McpError> {
+    let blocking_calls: Vec<String> = DEFAULT_BLOCKING_CALLS
+        .iter()
+        .map(|call| call.to_string())
+        .chain(extra_blocking_calls.unwrap_or_default())
+        .collect();
+    let mut calls = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = BlockingCallFinder::new(path.clone(), line_index.as_ref(), blocking_calls.clone(), &ast);
+            finder.visit_file(&ast);
+            calls.extend(finder.out);
+        }
+    }
+
+    calls.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&calls).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,32 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use crate::models::*;
+use crate::cache::*;
+use crate::analysis::analyze_source_with_options;
+
+pub async fn find_callers(
+    server: &MyServer,
+    Parameters(FindCallersParams { name, position_options }): Parameters<FindCallersParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut callers = Vec::new();
+    let code_map = server.cache.get_all().await;
+    let suffix = format!("::{}", name);
+
+    for (path, code) in code_map.iter() {
+        let Ok(analysis) = analyze_source_with_options(path, code, position_options) else { continue };
+        // Callees are recorded as written, so `a::parse`, `Parser::parse` and `parse` all match `parse`
+        callers.extend(
+            analysis.call_sites
+                .into_iter()
+                .filter(|site| site.callee == name || site.callee.ends_with(&suffix)),
+        );
+    }
+    callers.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&callers).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,35 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+const DEFAULT_MAX_CLONES: usize = 5;
+
+pub async fn find_clone_hotspots(
+    server: &MyServer,
+    Parameters(FindCloneHotspotsParams { max_clones, position_options }): Parameters<FindCloneHotspotsParams>,
+) -> Result<CallToolResult, McpError> {
+    let max_clones = max_clones.unwrap_or(DEFAULT_MAX_CLONES);
+    let mut hotspots = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = CloneHotspotFinder::new(path.clone(), line_index.as_ref(), max_clones);
+            finder.visit_file(&ast);
+            hotspots.extend(finder.out);
+        }
+    }
+
+    hotspots.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&hotspots).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
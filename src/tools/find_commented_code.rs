@@ -0,0 +1,115 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use proc_macro2::LineColumn;
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::{locations_to_range, LineIndex};
+
+const DEFAULT_MIN_LINES: usize = 2;
+
+/// Keywords that open a statement or item; a comment line starting with one reads as code.
+const CODE_KEYWORDS: &[&str] = &[
+    "fn", "let", "use", "pub", "impl", "struct", "enum", "trait", "mod", "const", "static",
+    "if", "else", "for", "while", "loop", "match", "return", "break", "continue",
+];
+
+pub async fn find_commented_code(
+    server: &MyServer,
+    Parameters(FindCommentedCodeParams { min_lines, position_options }): Parameters<FindCommentedCodeParams>,
+) -> Result<CallToolResult, McpError> {
+    let min_lines = min_lines.unwrap_or(DEFAULT_MIN_LINES).max(1);
+    let mut blocks = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    // syn drops comments, so this works on the raw text and doesn't need the file to parse
+    for (path, code) in code_map.iter() {
+        let line_index = position_options.line_index(code);
+        blocks.extend(commented_code(path, code, min_lines, line_index.as_ref()));
+    }
+    blocks.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&blocks).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// Runs of consecutive `//` comment lines with at least `min_lines` code-like lines, making
+/// up at least half of the run's non-empty lines. Doc comments (`///`, `//!`) are skipped
+/// since their examples are meant to be code.
+fn commented_code(file: &str, code: &str, min_lines: usize, line_index: Option<&LineIndex>) -> Vec<CommentedCode> {
+    let mut blocks = Vec::new();
+    let mut run: Option<CommentRun> = None;
+    let code = code.strip_prefix('\u{feff}').unwrap_or(code);
+
+    for (number, line) in code.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let comment = trimmed.strip_prefix("//").filter(|_| !is_doc_comment(trimmed));
+        let Some(comment) = comment else {
+            if let Some(run) = run.take() {
+                blocks.extend(run.finish(file, min_lines, line_index));
+            }
+            continue;
+        };
+
+        let run = run.get_or_insert_with(|| CommentRun {
+            start: LineColumn { line: number + 1, column: line.chars().count() - trimmed.chars().count() },
+            end: LineColumn { line: number + 1, column: 0 },
+            lines: 0,
+            text_lines: 0,
+            code_lines: 0,
+        });
+        run.end = LineColumn { line: number + 1, column: line.chars().count() };
+        run.lines += 1;
+        let comment = comment.trim();
+        if !comment.is_empty() {
+            run.text_lines += 1;
+            if looks_like_code(comment) {
+                run.code_lines += 1;
+            }
+        }
+    }
+    if let Some(run) = run {
+        blocks.extend(run.finish(file, min_lines, line_index));
+    }
+    blocks
+}
+
+struct CommentRun {
+    start: LineColumn,
+    end: LineColumn,
+    lines: usize,
+    text_lines: usize,
+    code_lines: usize,
+}
+
+impl CommentRun {
+    fn finish(self, file: &str, min_lines: usize, line_index: Option<&LineIndex>) -> Option<CommentedCode> {
+        (self.code_lines >= min_lines && self.code_lines * 2 >= self.text_lines).then(|| CommentedCode {
+            file: file.to_string(),
+            range: locations_to_range(self.start, self.end, line_index),
+            lines: self.lines,
+            code_lines: self.code_lines,
+        })
+    }
+}
+
+/// `///` and `//!` are doc comments, but `////` and longer are plain comments.
+fn is_doc_comment(trimmed: &str) -> bool {
+    (trimmed.starts_with("///") && !trimmed.starts_with("////")) || trimmed.starts_with("//!")
+}
+
+/// Whether the text of one comment line reads like a line of Rust rather than prose.
+fn looks_like_code(text: &str) -> bool {
+    let first_word = text.split(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or("");
+    let starts_with_keyword = CODE_KEYWORDS.contains(&first_word)
+        && text[first_word.len()..].starts_with([' ', '(', '{', ';']);
+    starts_with_keyword
+        || text.ends_with(';')
+        || text.ends_with('{')
+        || text.starts_with('}')
+        || text.contains("=>")
+        || text.starts_with("#[")
+}
@@ -0,0 +1,49 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+use crate::modules::{mod_declarations, resolve_module_paths};
+use std::collections::{HashMap, HashSet};
+
+pub async fn find_deprecated_usages(
+    server: &MyServer,
+    Parameters(FindDeprecatedUsagesParams { position_options }): Parameters<FindDeprecatedUsagesParams>,
+) -> Result<CallToolResult, McpError> {
+    let code_map = server.cache.get_all().await;
+    let mut files = Vec::new();
+    let mut deprecated = Vec::new();
+    let mut undeprecated = HashSet::new();
+    let mut declarations = HashMap::new();
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut collector = DeprecatedCollector::new(path.clone(), line_index.as_ref());
+            collector.visit_file(&ast);
+            deprecated.extend(collector.out);
+            undeprecated.extend(collector.undeprecated);
+            declarations.insert(path.clone(), mod_declarations(path, &ast));
+            files.push((path, code, ast));
+        }
+    }
+    let module_paths = resolve_module_paths(&declarations);
+
+    let mut usages = Vec::new();
+    for (path, code, ast) in &files {
+        let line_index = position_options.line_index(code);
+        let mut finder = DeprecatedUseFinder::new((*path).clone(), line_index.as_ref(), &deprecated, &module_paths, &undeprecated);
+        finder.visit_file(ast);
+        for (index, range) in finder.out {
+            usages.push(DeprecatedUsage { item: deprecated[index].clone(), file: (*path).clone(), range });
+        }
+    }
+    usages.sort_by(|a, b| (&a.file, &a.range.start, &a.item.file, &a.item.range.start).cmp(&(&b.file, &b.range.start, &b.item.file, &b.item.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&usages).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
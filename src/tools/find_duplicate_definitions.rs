@@ -0,0 +1,53 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+use std::collections::BTreeMap;
+
+pub async fn find_duplicate_definitions(
+    server: &MyServer,
+    Parameters(FindDuplicateDefinitionsParams { public_only, position_options }): Parameters<FindDuplicateDefinitionsParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut groups: BTreeMap<(String, SymbolKind), Vec<ReferenceLocation>> = BTreeMap::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut collector = SymbolCollector::new(path.clone(), line_index.as_ref());
+            collector.visit_file(&ast);
+            for sym in collector.out {
+                if public_only && sym.visibility != "public" {
+                    continue;
+                }
+                // Methods of different types may share a name, so they only collide within one type
+                let name = match (&sym.kind, &sym.container) {
+                    (SymbolKind::Method, Some(container)) => format!("{}::{}", container, sym.name),
+                    _ => sym.name,
+                };
+                groups
+                    .entry((name, sym.kind))
+                    .or_default()
+                    .push(ReferenceLocation { file: sym.file, range: sym.range });
+            }
+        }
+    }
+
+    let duplicates: Vec<DuplicateDefinition> = groups
+        .into_iter()
+        .filter(|(_, definitions)| definitions.len() > 1)
+        .map(|((name, kind), mut definitions)| {
+            definitions.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+            DuplicateDefinition { name, kind, definitions }
+        })
+        .collect();
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&duplicates).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,37 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_empty_functions(
+    server: &MyServer,
+    Parameters(FindEmptyFunctionsParams { kinds, position_options }): Parameters<FindEmptyFunctionsParams>,
+) -> Result<CallToolResult, McpError> {
+    let kinds = kinds.unwrap_or_default();
+    let mut functions = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut collector = EmptyFunctionCollector {
+                file: path.clone(),
+                line_index: line_index.as_ref(),
+                out: Vec::new(),
+            };
+            collector.visit_file(&ast);
+            functions.extend(collector.out.into_iter().filter(|function| kinds.is_empty() || kinds.contains(&function.kind)));
+        }
+    }
+
+    functions.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&functions).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,31 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_field_references(
+    server: &MyServer,
+    Parameters(FindFieldReferencesParams { struct_name, field, position_options }): Parameters<FindFieldReferencesParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut refs = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = FieldReferenceFinder::new(path.clone(), line_index.as_ref(), &struct_name, &field);
+            finder.visit_file(&ast);
+            refs.extend(finder.matches);
+        }
+    }
+    refs.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&refs).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
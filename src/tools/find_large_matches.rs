@@ -0,0 +1,35 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+const DEFAULT_MAX_ARMS: usize = 10;
+
+pub async fn find_large_matches(
+    server: &MyServer,
+    Parameters(FindLargeMatchesParams { max_arms, position_options }): Parameters<FindLargeMatchesParams>,
+) -> Result<CallToolResult, McpError> {
+    let max_arms = max_arms.unwrap_or(DEFAULT_MAX_ARMS);
+    let mut issues = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = LargeMatchFinder::new(path.clone(), line_index.as_ref(), max_arms);
+            finder.visit_file(&ast);
+            issues.extend(finder.out);
+        }
+    }
+
+    issues.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&issues).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
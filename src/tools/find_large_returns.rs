@@ -0,0 +1,53 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+use std::collections::HashMap;
+
+const DEFAULT_MIN_STRUCT_FIELDS: usize = 8;
+
+pub async fn find_large_returns(
+    server: &MyServer,
+    Parameters(FindLargeReturnsParams { min_struct_fields, position_options }): Parameters<FindLargeReturnsParams>,
+) -> Result<CallToolResult, McpError> {
+    let min_struct_fields = min_struct_fields.unwrap_or(DEFAULT_MIN_STRUCT_FIELDS);
+    let code_map = server.cache.get_all().await;
+    let files: Vec<(&String, syn::File)> = code_map
+        .iter()
+        .filter_map(|(path, code)| syn::parse_file(code).ok().map(|ast| (path, ast)))
+        .collect();
+
+    // Struct sizes come from the whole workspace, since the returned type may live elsewhere
+    let mut struct_fields = HashMap::new();
+    for (path, ast) in &files {
+        let mut collector = TypeUsageCollector {
+            file: path.to_string(),
+            line_index: None,
+            definitions: HashMap::new(),
+            usages: HashMap::new(),
+            struct_info: HashMap::new(),
+            enum_info: HashMap::new(),
+        };
+        collector.visit_file(ast);
+        struct_fields.extend(collector.struct_info.into_values().map(|info| (info.name, info.field_count)));
+    }
+
+    let mut returns = Vec::new();
+    for (path, ast) in &files {
+        let line_index = position_options.line_index(&code_map[*path]);
+        let mut finder = LargeReturnFinder::new(path.to_string(), line_index.as_ref(), &struct_fields, min_struct_fields);
+        finder.visit_file(ast);
+        returns.extend(finder.out);
+    }
+
+    returns.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&returns).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,46 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+use crate::modules::{is_under_private_module, mod_declarations, private_modules, resolve_module_paths, strip_private_inline_modules};
+use std::collections::HashMap;
+
+pub async fn find_missing_docs(
+    server: &MyServer,
+    Parameters(FindMissingDocsParams { reachable_only, position_options }): Parameters<FindMissingDocsParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut missing = Vec::new();
+    let mut declarations = HashMap::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(mut ast) = syn::parse_file(code) {
+            declarations.insert(path.clone(), mod_declarations(path, &ast));
+            if reachable_only {
+                strip_private_inline_modules(&mut ast.items);
+            }
+
+            let line_index = position_options.line_index(code);
+            let mut collector = SymbolCollector::new(path.clone(), line_index.as_ref());
+            collector.visit_file(&ast);
+            missing.extend(collector.out.into_iter().filter(|sym| sym.visibility == "public" && sym.docs.is_none()));
+        }
+    }
+
+    if reachable_only {
+        // Whole files are unreachable when a `mod` declaration on their path isn't `pub`
+        let module_paths = resolve_module_paths(&declarations);
+        let private_modules = private_modules(&module_paths, &declarations);
+        missing.retain(|sym| !is_under_private_module(&module_paths[&sym.file], &private_modules));
+    }
+    missing.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&missing).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,35 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+const DEFAULT_MAX_DEPTH: usize = 2;
+
+pub async fn find_nested_wrappers(
+    server: &MyServer,
+    Parameters(FindNestedWrappersParams { max_depth, position_options }): Parameters<FindNestedWrappersParams>,
+) -> Result<CallToolResult, McpError> {
+    let max_depth = max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+    let mut wrappers = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = NestedWrapperFinder::new(path.clone(), line_index.as_ref(), max_depth);
+            finder.visit_file(&ast);
+            wrappers.extend(finder.out);
+        }
+    }
+
+    wrappers.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&wrappers).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,37 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_panics(
+    server: &MyServer,
+    Parameters(FindPanicsParams { kinds, position_options }): Parameters<FindPanicsParams>,
+) -> Result<CallToolResult, McpError> {
+    let kinds = kinds.unwrap_or_default();
+    let mut sites = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = PanicFinder {
+                file: path.clone(),
+                line_index: line_index.as_ref(),
+                sites: Vec::new(),
+            };
+            finder.visit_file(&ast);
+            sites.extend(finder.sites.into_iter().filter(|site| kinds.is_empty() || kinds.contains(&site.kind)));
+        }
+    }
+
+    sites.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&sites).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
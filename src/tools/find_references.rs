@@ -7,19 +7,29 @@ use syn::visit::Visit;
 use crate::models::*;
 use crate::cache::*;
 use crate::visitors::*;
+use crate::tools::goto_definition::name_at_position;
+use std::collections::HashSet;
 
 pub async fn find_references(
     server: &MyServer,
-    Parameters(FindReferencesParams { name }): Parameters<FindReferencesParams>,
+    Parameters(FindReferencesParams { name, file, position, position_options }): Parameters<FindReferencesParams>,
 ) -> Result<CallToolResult, McpError> {
+    let name = match (&file, &position) {
+        (Some(file), Some(position)) => name_at_position(server, file, position, position_options).await?,
+        (None, None) if !name.is_empty() => name,
+        (None, None) => return Err(McpError::invalid_params("Either name or file and position are required", None)),
+        _ => return Err(McpError::invalid_params("file and position must be given together", None)),
+    };
     let mut refs = Vec::new();
     let code_map = server.cache.get_all().await;
 
     for (path, code) in code_map.iter() {
         if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
             let mut finder = ReferenceFinder {
                 target_name: name.to_string(),
                 file: path.clone(),
+                line_index: line_index.as_ref(),
                 matches: Vec::new(),
             };
             finder.visit_file(&ast);
@@ -27,6 +37,11 @@ pub async fn find_references(
         }
     }
 
+    // Type paths are seen both as a path and as a bare ident, so drop repeated locations
+    let mut seen = HashSet::new();
+    refs.retain(|location| seen.insert(location.clone()));
+    refs.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
     Ok(CallToolResult::success(vec![Content::text(
         serde_json::to_string(&refs).map_err(|e| McpError::internal_error(e.to_string(), None))?
     )]))
@@ -0,0 +1,34 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::LineIndex;
+use crate::visitors::*;
+
+pub async fn find_shadowed_variables(
+    server: &MyServer,
+    Parameters(FindShadowedVariablesParams { type_changed, position_options }): Parameters<FindShadowedVariablesParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut shadowed = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let source = LineIndex::new(code, PositionOptions::default());
+            let mut walker = ScopedBindingWalker::new(ShadowingFinder::new(path.clone(), line_index.as_ref(), &source));
+            walker.visit_file(&ast);
+            shadowed.extend(walker.tracker.out.into_iter().filter(|s| type_changed.is_none() || s.type_changed == type_changed));
+        }
+    }
+
+    shadowed.sort_by(|a, b| (&a.file, &a.inner.start).cmp(&(&b.file, &b.inner.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&shadowed).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,32 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_tests_without_assertions(
+    server: &MyServer,
+    Parameters(FindTestsWithoutAssertionsParams { position_options }): Parameters<FindTestsWithoutAssertionsParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut tests = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut collector = TestCollector::new(path.clone(), line_index.as_ref());
+            collector.visit_file(&ast);
+            tests.extend(collector.out.into_iter().filter(|test| !test.has_assertions));
+        }
+    }
+
+    tests.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&tests).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,36 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_unreachable_code(
+    server: &MyServer,
+    Parameters(FindUnreachableCodeParams { position_options }): Parameters<FindUnreachableCodeParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut unreachable = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = UnreachableCodeFinder {
+                file: path.clone(),
+                line_index: line_index.as_ref(),
+                out: Vec::new(),
+            };
+            finder.visit_file(&ast);
+            unreachable.extend(finder.out);
+        }
+    }
+
+    unreachable.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&unreachable).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,80 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use walkdir::WalkDir;
+use syn::visit::Visit;
+use serde_json::json;
+use crate::models::*;
+use crate::cache::*;
+use crate::manifest;
+use crate::visitors::CrateReferenceCollector;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+pub async fn find_unused_dependencies(
+    _server: &MyServer,
+    Parameters(FindUnusedDependenciesParams { manifest_path }): Parameters<FindUnusedDependenciesParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut manifest_path = PathBuf::from(manifest_path);
+    if manifest_path.is_dir() {
+        manifest_path.push("Cargo.toml");
+    }
+
+    let unused = tokio::task::spawn_blocking(move || unused_dependencies(&manifest_path))
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .map_err(|e| McpError::invalid_params("Failed to read manifest", Some(json!({ "error": e }))))?;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&unused).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+fn unused_dependencies(manifest_path: &Path) -> Result<Vec<Dependency>, String> {
+    let dependencies = manifest::dependencies(manifest_path)?;
+    let package_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    let build_script = package_dir.join("build.rs");
+
+    // Build dependencies are only visible to the build script, everything else to the rest
+    let mut build_roots = HashSet::new();
+    let mut roots = HashSet::new();
+    for path in package_sources(package_dir) {
+        let Ok(code) = std::fs::read_to_string(&path) else { continue };
+        let Ok(ast) = syn::parse_file(&code) else { continue };
+        let mut collector = CrateReferenceCollector::default();
+        collector.visit_file(&ast);
+        if path == build_script {
+            build_roots.extend(collector.roots);
+        } else {
+            roots.extend(collector.roots);
+        }
+    }
+
+    Ok(dependencies
+        .into_iter()
+        .filter(|dependency| {
+            // The declared name is what the source uses, even for renamed packages
+            let crate_name = dependency.name.replace('-', "_");
+            let referenced_from = if dependency.kind == DependencyKind::Build { &build_roots } else { &roots };
+            !referenced_from.contains(&crate_name)
+        })
+        .collect())
+}
+
+/// Rust files of the package, skipping `target` and nested packages with their own manifest.
+fn package_sources(package_dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(package_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || (entry.file_name() != "target" && !entry.path().join("Cargo.toml").is_file())
+        })
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|ext| ext == "rs"))
+        .map(|entry| entry.into_path())
+        .collect()
+}
@@ -0,0 +1,32 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_unused_variables(
+    server: &MyServer,
+    Parameters(FindUnusedVariablesParams { position_options }): Parameters<FindUnusedVariablesParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut unused = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut walker = ScopedBindingWalker::new(UnusedVariableFinder::new(path.clone(), line_index.as_ref()));
+            walker.visit_file(&ast);
+            unused.extend(walker.tracker.out);
+        }
+    }
+
+    unused.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&unused).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,31 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn find_variant_references(
+    server: &MyServer,
+    Parameters(FindVariantReferencesParams { enum_name, variant_name, position_options }): Parameters<FindVariantReferencesParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut refs = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut finder = VariantReferenceFinder::new(path.clone(), line_index.as_ref(), &enum_name, &variant_name);
+            finder.visit_file(&ast);
+            refs.extend(finder.matches);
+        }
+    }
+    refs.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&refs).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,50 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::LineIndex;
+use crate::visitors::*;
+use crate::modules::module_path_for_file;
+use std::collections::BTreeMap;
+
+pub async fn generate_api_docs(
+    server: &MyServer,
+    Parameters(GenerateApiDocsParams { include_private }): Parameters<GenerateApiDocsParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut modules: BTreeMap<String, Vec<SymbolInfo>> = BTreeMap::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let source = LineIndex::new(code, PositionOptions::default());
+            let mut collector = SymbolCollector::new(path.clone(), None).with_signatures(&source);
+            collector.visit_file(&ast);
+            let items = collector.out.into_iter().filter(|sym| include_private || sym.visibility == "public");
+            modules.entry(module_path_for_file(path)).or_default().extend(items);
+        }
+    }
+
+    let mut markdown = String::from("# API Reference\n");
+    for (module, mut items) in modules {
+        if items.is_empty() {
+            continue;
+        }
+        items.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+        markdown.push_str(&format!("\n## `{}`\n\n", module));
+        for item in items {
+            // Keep each entry on one line, even for signatures spread over several
+            let signature = item.signature.as_deref().unwrap_or(&item.name).split_whitespace().collect::<Vec<_>>().join(" ");
+            match item.docs.as_deref().and_then(|docs| docs.lines().next()) {
+                Some(summary) if !summary.is_empty() => markdown.push_str(&format!("- `{}` — {}\n", signature, summary)),
+                _ => markdown.push_str(&format!("- `{}`\n", signature)),
+            }
+        }
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(markdown)]))
+}
@@ -0,0 +1,45 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::LineIndex;
+use crate::visitors::*;
+
+pub async fn get_docs(
+    server: &MyServer,
+    Parameters(GetDocsParams { name }): Parameters<GetDocsParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut symbols = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let source = LineIndex::new(code, PositionOptions::default());
+            let mut collector = SymbolCollector::new(path.clone(), None).with_signatures(&source);
+            collector.visit_file(&ast);
+            symbols.extend(collector.out.into_iter().filter(|sym| sym.name == unraw_name(&name)));
+        }
+    }
+    symbols.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    let markdown = if symbols.is_empty() {
+        format!("No symbol named `{}` found in the cached files.", name)
+    } else {
+        symbols.iter().map(render_docs).collect::<Vec<_>>().join("\n\n---\n\n")
+    };
+
+    Ok(CallToolResult::success(vec![Content::text(markdown)]))
+}
+
+fn render_docs(symbol: &SymbolInfo) -> String {
+    let signature = symbol.signature.as_deref().unwrap_or(&symbol.name);
+    let docs = symbol.docs.as_deref().unwrap_or("_No documentation._");
+    format!(
+        "`{}` ({}:{})\n\n```rust\n{}\n```\n\n{}",
+        symbol.name, symbol.file, symbol.range.start.line, signature, docs
+    )
+}
@@ -0,0 +1,77 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::LineIndex;
+use crate::visitors::*;
+
+pub async fn get_source(
+    server: &MyServer,
+    Parameters(GetSourceParams { name, path, line, position_options }): Parameters<GetSourceParams>,
+) -> Result<CallToolResult, McpError> {
+    if name.is_none() && (path.is_none() || line.is_none()) {
+        return Err(McpError::invalid_params("Either `name`, or `path` and `line`, must be provided", None));
+    }
+    // `Parser::new` names `new` inside `Parser`
+    let (container, name) = match name.as_deref().map(|name| name.rsplit_once("::")) {
+        Some(Some((container, name))) => (Some(container.rsplit("::").next().unwrap_or(container)), Some(name)),
+        Some(None) => (None, name.as_deref()),
+        None => (None, None),
+    };
+
+    let mut snippets = Vec::new();
+    let code_map = server.cache.get_all().await;
+    for (file, code) in code_map.iter() {
+        if path.as_ref().is_some_and(|path| path != file) {
+            continue;
+        }
+        let Ok(ast) = syn::parse_file(code) else { continue };
+        // Byte offsets are always needed to cut the text out
+        let line_index = LineIndex::new(code, PositionOptions { byte_offsets: true, ..position_options });
+        let mut collector = SymbolCollector::new(file.clone(), Some(&line_index));
+        collector.visit_file(&ast);
+
+        let mut symbols: Vec<SymbolInfo> = collector.out
+            .into_iter()
+            .filter(|sym| match name {
+                Some(name) => sym.name == unraw_name(name) && container.is_none_or(|container| sym.container.as_deref() == Some(unraw_name(container))),
+                None => line.is_some_and(|line| sym.full_range.start.line <= line && line <= sym.full_range.end.line),
+            })
+            .collect();
+        if name.is_none() {
+            // Items nest, so the one starting last is the innermost
+            symbols.sort_by(|a, b| a.full_range.start.cmp(&b.full_range.start));
+            symbols.drain(..symbols.len().saturating_sub(1));
+        }
+
+        snippets.extend(symbols.into_iter().map(|sym| {
+            let (start, end) = (sym.full_range.start_byte.unwrap_or(0), sym.full_range.end_byte.unwrap_or(0));
+            let mut range = sym.full_range;
+            if !position_options.byte_offsets {
+                (range.start_byte, range.end_byte) = (None, None);
+            }
+            SourceSnippet {
+                name: sym.name,
+                kind: sym.kind,
+                container: sym.container,
+                file: file.clone(),
+                range,
+                source: code[start..end].to_string(),
+            }
+        }));
+    }
+
+    if snippets.is_empty() {
+        return Err(McpError::invalid_params("No matching item found in the cached files", Some(json!({ "name": name, "path": path, "line": line }))));
+    }
+    snippets.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&snippets).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -4,33 +4,92 @@ use rmcp::{
     handler::server::wrapper::Parameters,
 };
 use syn::visit::Visit;
+use serde_json::json;
 use crate::models::*;
 use crate::cache::*;
+use crate::line_index::LineIndex;
 use crate::visitors::*;
+use crate::modules::{mod_declarations, resolve_module_paths};
+use std::collections::HashMap;
 
 pub async fn goto_definition(
     server: &MyServer,
-    Parameters(GotoDefinitionParams { name }): Parameters<GotoDefinitionParams>,
+    Parameters(GotoDefinitionParams { name, file, position, context_path, position_options }): Parameters<GotoDefinitionParams>,
 ) -> Result<CallToolResult, McpError> {
+    let name = match (&file, &position) {
+        (Some(file), Some(position)) => name_at_position(server, file, position, position_options).await?,
+        (None, None) if !name.is_empty() => name,
+        (None, None) => return Err(McpError::invalid_params("Either name or file and position are required", None)),
+        _ => return Err(McpError::invalid_params("file and position must be given together", None)),
+    };
     let mut results = Vec::new();
+    let mut declarations = HashMap::new();
     let code_map = server.cache.get_all().await;
     
     for (path, code) in code_map.iter() {
         if let Ok(ast) = syn::parse_file(code) {
-            let mut collector = SymbolCollector {
-                file: path.clone(),
-                out: Vec::new(),
-            };
+            let line_index = position_options.line_index(code);
+            let mut collector = SymbolCollector::new(path.clone(), line_index.as_ref());
             collector.visit_file(&ast);
             for sym in collector.out {
-                if sym.name == name {
+                if sym.name == unraw_name(&name) {
                     results.push(sym);
                 }
             }
+            declarations.insert(path.clone(), mod_declarations(path, &ast));
         }
     }
-    
+
+    results.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+    let module_paths = resolve_module_paths(&declarations);
+    // Looking up from a cursor, definitions closest to the cursor's own module come first
+    let context_path = context_path.or_else(|| file.and_then(|file| module_paths.get(&file).cloned()));
+    if let Some(context_path) = context_path {
+        // Stable sort, so equally close definitions keep the positional order
+        results.sort_by_key(|sym| module_distance(&module_paths[&sym.file], &context_path));
+    }
+
     Ok(CallToolResult::success(vec![Content::text(
         serde_json::to_string(&results).map_err(|e| McpError::internal_error(e.to_string(), None))?
     )]))
-}
\ No newline at end of file
+}
+
+/// Name of the identifier under `position` in `file`, read from the cache or else from disk.
+pub(crate) async fn name_at_position(
+    server: &MyServer,
+    file: &str,
+    position: &Position,
+    position_options: PositionOptions,
+) -> Result<String, McpError> {
+    let code = match server.cache.get(file).await {
+        Some(code) => code,
+        None => tokio::fs::read_to_string(file).await
+            .map_err(|e| McpError::invalid_params("Failed to read file", Some(json!({ "error": e.to_string() }))))?,
+    };
+    let ast = syn::parse_file(&code)
+        .map_err(|e| McpError::invalid_params("Failed to parse file", Some(json!({ "error": e.to_string() }))))?;
+
+    let line_index = LineIndex::new(&code, position_options);
+    let mut finder = IdentAtOffset { offset: line_index.position_offset(position), line_index: &line_index, found: None };
+    finder.visit_file(&ast);
+    finder
+        .found
+        .map(|ident| ident_name(&ident))
+        .ok_or_else(|| McpError::invalid_params("No identifier at position", Some(json!({ "file": file, "position": position }))))
+}
+
+/// Ranks how far `module` is from `context` in the module tree: a shared prefix first
+/// (longer is closer), then the number of steps between them.
+fn module_distance(module: &str, context: &str) -> (std::cmp::Reverse<usize>, usize) {
+    let module: Vec<&str> = module.split("::").collect();
+    let context: Vec<&str> = context.split("::").collect();
+    // Read `crate` as, and accept context paths written without, the root `module` sits under
+    let context = match context.first() {
+        Some(&"crate") => [&module[..1], &context[1..]].concat(),
+        Some(first) if *first == module[0] => context,
+        _ => [&module[..1], &context[..]].concat(),
+    };
+
+    let shared = module.iter().zip(&context).take_while(|(a, b)| a == b).count();
+    (std::cmp::Reverse(shared), module.len() + context.len() - 2 * shared)
+}
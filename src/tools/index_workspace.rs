@@ -4,117 +4,407 @@ use rmcp::{
     handler::server::wrapper::Parameters,
 };
 use walkdir::WalkDir;
-use syn::visit::Visit;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde_json::json;
+use futures::stream::{self, Stream, StreamExt};
 use crate::models::*;
 use crate::cache::*;
-use crate::visitors::{SymbolCollector, CallGraphCollector, TypeUsageCollector, ModuleDependencyCollector};
-use std::collections::HashMap;
+use crate::graph::strongly_connected_components;
+use crate::analysis::AnalysisOptions;
+use crate::config::Config;
+use crate::modules::{circular_modules, module_tree, resolve_module_paths};
+use super::progress::ProgressReporter;
+use tokio_util::sync::CancellationToken;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Files larger than this are skipped unless the request sets `max_file_bytes`.
+const DEFAULT_MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Files read from disk at the same time while earlier ones are parsed.
+const READ_CONCURRENCY: usize = 16;
 
 pub async fn index_workspace(
     server: &MyServer,
-    Parameters(IndexWorkspaceParams { root }): Parameters<IndexWorkspaceParams>,
+    Parameters(IndexWorkspaceParams { root, roots, files, include, exclude, ignore_cfg_test, thresholds, kinds, max_file_bytes, position_options }): Parameters<IndexWorkspaceParams>,
+    progress: Option<&ProgressReporter>,
+    cancellation: Option<&CancellationToken>,
 ) -> Result<CallToolResult, McpError> {
+    let include_set = build_glob_set(include.as_deref().unwrap_or_default())?;
+    let exclude_set = build_glob_set(exclude.as_deref().unwrap_or_default())?;
+    let kinds = kinds.map(|kinds| parse_kinds(&kinds)).transpose()?;
+
+    let roots: Vec<String> = root.into_iter().chain(roots.unwrap_or_default()).collect();
+    let files = files.unwrap_or_default();
+    if roots.is_empty() && files.is_empty() {
+        return Err(McpError::invalid_params("Either `root`, `roots`, or `files` must be provided", None));
+    }
+
+    let config = match roots.first() {
+        Some(root) => Config::load(Path::new(root))
+            .map_err(|e| McpError::invalid_params("Invalid config file", Some(json!({ "error": e }))))?
+            .unwrap_or_default(),
+        None => Config::default(),
+    };
+    let thresholds = Thresholds::default()
+        .with_overrides(&config.thresholds)
+        .with_overrides(&thresholds.unwrap_or_default());
+
+    let analysis_options = AnalysisOptions { position_options, ignore_cfg_test };
+    let file_filter = FileFilter { roots, files, include: include_set, exclude: exclude_set };
+    let candidate_files = file_filter.candidates();
+
+    let max_file_bytes = max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES);
+    let mut analyses = BTreeMap::new();
+    let mut skipped_files = BTreeMap::new();
+    let total_files = candidate_files.len();
+    let mut sources = load_sources(&server.cache, candidate_files, max_file_bytes, READ_CONCURRENCY);
+    let mut processed = 0;
+    while let Some((path, source)) = sources.next().await {
+        // The previous index is kept; files read so far stay cached
+        if cancellation.is_some_and(CancellationToken::is_cancelled) {
+            return Err(McpError::internal_error(
+                "Indexing was cancelled",
+                Some(json!({ "processed_files": processed, "total_files": total_files })),
+            ));
+        }
+        if let Some(progress) = progress {
+            progress.report(processed, total_files).await;
+        }
+        processed += 1;
+
+        let code = match source {
+            FileSource::Cached(code) => code,
+            FileSource::Read(code) => {
+                // Reads finish in any order, but only this loop writes to the cache
+                server.cache.insert(path.clone(), code.clone()).await;
+                code
+            }
+            FileSource::Oversized(reason) => {
+                skipped_files.insert(path, reason);
+                continue;
+            }
+            FileSource::Unreadable => continue,
+        };
+        let Ok(analysis) = server.cache.analyze(&path, &code, analysis_options).await else { continue };
+        analyses.insert(path, analysis);
+    }
+    if let Some(progress) = progress {
+        progress.report(total_files, total_files).await;
+    }
+
+    let mut graphs = build_graphs(&analyses, &thresholds);
+    graphs.skipped_files = skipped_file_list(&skipped_files);
+    if let Some(kinds) = &kinds {
+        retain_kinds(&mut graphs, kinds);
+    }
+    *server.workspace_index.write().await = Some(WorkspaceIndex {
+        analyses,
+        options: analysis_options,
+        thresholds,
+        max_file_bytes,
+        skipped_files,
+        file_filter,
+        kinds,
+    });
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&graphs).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// What the read stage found for one candidate file.
+pub enum FileSource {
+    /// Already in the cache; nothing was read.
+    Cached(String),
+    /// Read from disk, not yet cached.
+    Read(String),
+    /// Too large to index, with the reason.
+    Oversized(String),
+    /// Missing or not valid UTF-8.
+    Unreadable,
+}
+
+/// Fetches up to `concurrency` files at once, yielding each as soon as it's read so the caller
+/// can parse it while the rest are still in flight. Results arrive in completion order, and
+/// nothing is written to the cache.
+pub fn load_sources<'a>(
+    cache: &'a AstCache,
+    paths: Vec<String>,
+    max_file_bytes: u64,
+    concurrency: usize,
+) -> impl Stream<Item = (String, FileSource)> + 'a {
+    stream::iter(paths)
+        .map(move |path| async move {
+            let source = load_source(cache, &path, max_file_bytes).await;
+            (path, source)
+        })
+        .buffer_unordered(concurrency.max(1))
+}
+
+async fn load_source(cache: &AstCache, path: &str, max_file_bytes: u64) -> FileSource {
+    let cached = cache.get(path).await;
+    let size = match &cached {
+        Some(code) => Some(code.len() as u64),
+        None => tokio::fs::metadata(path).await.ok().map(|metadata| metadata.len()),
+    };
+    if let Some(reason) = size.and_then(|size| oversized_reason(size, max_file_bytes)) {
+        return FileSource::Oversized(reason);
+    }
+    if let Some(code) = cached {
+        return FileSource::Cached(code);
+    }
+    match tokio::fs::read_to_string(path).await {
+        Ok(code) => FileSource::Read(code),
+        Err(_) => FileSource::Unreadable,
+    }
+}
+
+/// Aggregates per-file analyses into the workspace graphs and code smell reports.
+pub(crate) fn build_graphs(analyses: &BTreeMap<String, FileAnalysis>, thresholds: &Thresholds) -> WorkspaceGraphs {
     let mut call_graph = HashMap::new();
+    let mut type_definitions = HashMap::new();
     let mut type_usage = HashMap::new();
-    let mut module_deps = HashMap::new();
-    let mut all_symbols = Vec::new();
+    let mut file_dependencies = HashMap::new();
+    let mut mod_declarations = HashMap::new();
     let mut all_functions = Vec::new();
     let mut all_structs = Vec::new();
     let mut all_enums = Vec::new();
+    let mut tail_calls = HashMap::new();
 
-    for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
-        if !entry.file_type().is_file() { continue; }
-        let path = entry.path().to_string_lossy().to_string();
-        if !path.ends_with(".rs") { continue; }
+    // Module paths follow `mod` declarations from the crate roots, so they're resolved up
+    // front; each file's callers are keyed under its module so same-named functions don't merge
+    for (path, analysis) in analyses {
+        mod_declarations.insert(path.clone(), analysis.mod_declarations.clone());
+    }
+    let module_paths = resolve_module_paths(&mod_declarations);
 
-        let code_opt = if let Some(code) = server.cache.get(&path).await {
-            Some(code)
-        } else {
-            if let Ok(code) = tokio::fs::read_to_string(&path).await {
-                server.cache.insert(path.clone(), code.clone()).await;
-                Some(code)
-            } else {
-                None
-            }
-        };
+    for (path, analysis) in analyses {
+        let prefix = caller_prefix(&module_paths[path]);
+        let qualify = |name: &str| format!("{}{}", prefix, name);
+        all_functions.extend(analysis.functions.iter().map(|func| FunctionInfo { qualified_name: qualify(&func.qualified_name), ..func.clone() }));
+        all_structs.extend(analysis.structs.iter().cloned());
+        all_enums.extend(analysis.enums.iter().cloned());
+        tail_calls.extend(analysis.tail_calls.iter().map(|(caller, callee)| (qualify(caller), callee.clone())));
+        for (caller, callees) in &analysis.calls {
+            call_graph.entry(qualify(caller)).or_insert(Vec::new()).extend(callees.iter().cloned());
+        }
+        for (type_name, locations) in &analysis.type_definitions {
+            type_definitions.entry(type_name.clone()).or_insert(Vec::new()).extend(locations.iter().cloned());
+        }
+        for (type_name, locations) in &analysis.type_usages {
+            type_usage.entry(type_name.clone()).or_insert(Vec::new()).extend(locations.iter().cloned());
+        }
+        file_dependencies.insert(path.clone(), analysis.module_dependencies.clone());
+    }
 
-        if let Some(code) = code_opt {
-            if let Ok(ast) = syn::parse_file(&code) {
-                // Collect symbols
-                let mut symbol_collector = SymbolCollector {
-                    file: path.clone(),
-                    out: Vec::new(),
-                };
-                symbol_collector.visit_file(&ast);
-                all_symbols.extend(symbol_collector.out);
-
-                // Collect call graph and function info
-                let mut call_collector = CallGraphCollector {
-                    file: path.clone(),
-                    current_function: None,
-                    calls: HashMap::new(),
-                    function_info: HashMap::new(),
-                };
-                call_collector.visit_file(&ast);
-                for (caller, callees) in call_collector.calls {
-                    call_graph.entry(caller).or_insert(Vec::new()).extend(callees);
-                }
-                all_functions.extend(call_collector.function_info.values().cloned());
-
-                // Collect type usage and struct/enum info
-                let mut type_collector = TypeUsageCollector {
-                    file: path.clone(),
-                    usages: HashMap::new(),
-                    struct_info: HashMap::new(),
-                    enum_info: HashMap::new(),
-                };
-                type_collector.visit_file(&ast);
-                for (type_name, locations) in type_collector.usages {
-                    type_usage.entry(type_name).or_insert(Vec::new()).extend(locations);
-                }
-                all_structs.extend(type_collector.struct_info.values().cloned());
-                all_enums.extend(type_collector.enum_info.values().cloned());
-
-                // Collect module dependencies
-                let mut mod_collector = ModuleDependencyCollector {
-                    file: path.clone(),
-                    dependencies: HashMap::new(),
-                };
-                mod_collector.visit_file(&ast);
-                for (module, deps) in mod_collector.dependencies {
-                    module_deps.entry(module).or_insert(Vec::new()).extend(deps);
-                }
-            }
+    let mut module_deps = HashMap::new();
+    for (path, dependencies) in file_dependencies {
+        if !dependencies.is_empty() {
+            module_deps.entry(module_paths[&path].clone()).or_insert(Vec::new()).extend(dependencies);
         }
     }
+    let module_use_paths = module_use_paths(analyses, &module_paths);
+
+    // Files are walked in sorted order, but sort by position too so output is stable across runs
+    all_functions.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+    all_structs.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+    all_enums.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    // Fan-in and fan-out depend on the whole call graph, so they're filled in once it's assembled
+    let coupling = fan_in_and_out(&call_graph);
+    for func in &mut all_functions {
+        if let Some(&(fan_in, fan_out)) = coupling.get(&func.qualified_name) {
+            func.fan_in = fan_in;
+            func.fan_out = fan_out;
+        }
+    }
+
+    let diverging_functions = detect_diverging_functions(&all_functions, &tail_calls, &call_graph);
+    for func in &mut all_functions {
+        func.diverges = diverging_functions.binary_search(&func.qualified_name).is_ok();
+    }
 
     // Advanced code smell detection
     let unused_functions = detect_unused_functions(&all_functions, &call_graph);
-    let refactoring_suggestions = generate_refactoring_suggestions(&all_functions, &all_structs, &all_enums, &call_graph, &type_usage);
+    let recursive_functions = detect_recursive_functions(&call_graph);
+    let circular_modules = circular_modules(&module_use_paths);
+    let entry_points = detect_entry_points(&call_graph);
+    let leaf_functions = detect_leaf_functions(&call_graph);
+    let refactoring_suggestions = generate_refactoring_suggestions(&all_functions, &all_structs, &all_enums, &call_graph, &type_usage, thresholds);
 
-    let graphs = WorkspaceGraphs {
-        call_graph: CallGraph { calls: call_graph },
-        type_usage_graph: TypeUsageGraph { usages: type_usage },
-        module_dependency_graph: ModuleDependencyGraph { dependencies: module_deps },
+    WorkspaceGraphs {
+        call_graph: CallGraph { calls: call_graph.into_iter().collect() },
+        type_usage_graph: TypeUsageGraph {
+            definitions: type_definitions.into_iter().collect(),
+            usages: type_usage.into_iter().collect(),
+        },
+        module_dependency_graph: ModuleDependencyGraph { dependencies: module_deps.into_iter().collect() },
         unused_functions,
+        recursive_functions,
+        circular_modules,
+        entry_points,
+        leaf_functions,
+        diverging_functions,
+        module_tree: module_tree(&module_paths),
         refactoring_suggestions,
         function_info: all_functions,
         struct_info: all_structs,
         enum_info: all_enums,
-    };
+        skipped_files: Vec::new(),
+    }
+}
 
-    Ok(CallToolResult::success(vec![Content::text(
-        serde_json::to_string(&graphs).map_err(|e| McpError::internal_error(e.to_string(), None))?
-    )]))
+/// Why a file of `size` bytes is left out of the index, if it is.
+pub(crate) fn oversized_reason(size: u64, max_file_bytes: u64) -> Option<String> {
+    (size > max_file_bytes).then(|| format!("file is {} bytes, larger than the {} byte limit", size, max_file_bytes))
+}
+
+pub(crate) fn skipped_file_list(skipped_files: &BTreeMap<String, String>) -> Vec<SkippedFile> {
+    skipped_files
+        .iter()
+        .map(|(file, reason)| SkippedFile { file: file.clone(), reason: reason.clone() })
+        .collect()
+}
+
+/// Groups the `use` paths of each file by the module it resolves to; every module gets an
+/// entry, even one without imports.
+pub(crate) fn module_use_paths(
+    analyses: &BTreeMap<String, FileAnalysis>,
+    module_paths: &BTreeMap<String, String>,
+) -> HashMap<String, Vec<String>> {
+    let mut module_use_paths: HashMap<String, Vec<String>> = HashMap::new();
+    for (path, analysis) in analyses {
+        module_use_paths.entry(module_paths[path].clone()).or_default().extend(analysis.use_paths.iter().cloned());
+    }
+    module_use_paths
+}
+
+/// Parses the requested kind names with the same spelling `SymbolKind` serializes to.
+fn parse_kinds(kinds: &[String]) -> Result<HashSet<SymbolKind>, McpError> {
+    kinds
+        .iter()
+        .map(|kind| {
+            serde_json::from_value(json!(kind)).map_err(|_| {
+                McpError::invalid_params("Unknown symbol kind", Some(json!({ "kind": kind })))
+            })
+        })
+        .collect()
+}
+
+/// Which files an `index_workspace` call covers, kept so `reindex_changed` picks up the
+/// same files, under the same keys.
+#[derive(Clone, Debug)]
+pub struct FileFilter {
+    pub roots: Vec<String>,
+    pub files: Vec<String>,
+    /// Matched against paths relative to their root; `files` aren't filtered.
+    pub include: Option<GlobSet>,
+    pub exclude: Option<GlobSet>,
+}
+
+impl FileFilter {
+    /// Every `.rs` file under the roots that passes the globs, then the explicit files,
+    /// each spelled with the root or file it was found through.
+    fn candidates(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut candidates = Vec::new();
+        let mut push_unique = |path: &Path| {
+            // Canonicalize so overlapping roots don't yield the same file twice
+            if seen.insert(canonical(path)) {
+                candidates.push(path.to_string_lossy().to_string());
+            }
+        };
+
+        for root in &self.roots {
+            for entry in WalkDir::new(root).sort_by_file_name().into_iter().filter_map(|e| e.ok()) {
+                if !entry.file_type().is_file() { continue; }
+                // Globs are matched against the path relative to the root
+                let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+                if self.matches(entry.path(), relative) {
+                    push_unique(entry.path());
+                }
+            }
+        }
+
+        for file in &self.files {
+            push_unique(Path::new(file));
+        }
+
+        candidates
+    }
+
+    fn matches(&self, path: &Path, relative: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "rs")
+            && !self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(relative))
+            && self.include.as_ref().is_none_or(|include| include.is_match(relative))
+    }
+
+    /// The key `candidates` would give `path`, which need not exist anymore, or `None` when
+    /// the filter doesn't cover it. The first root or file containing it wins, as in `candidates`.
+    pub fn key(&self, path: &Path) -> Option<String> {
+        let path = canonical(path);
+        for root in &self.roots {
+            if let Ok(relative) = path.strip_prefix(canonical(Path::new(root))) {
+                if self.matches(&path, relative) {
+                    return Some(Path::new(root).join(relative).to_string_lossy().to_string());
+                }
+            }
+        }
+        self.files.iter().find(|file| canonical(Path::new(file)) == path).cloned()
+    }
+}
+
+/// `path` with symlinks and `.`/`..` resolved; a missing file is resolved through its directory.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(path) = std::fs::canonicalize(path) {
+        return path;
+    }
+    match (path.parent().map(std::fs::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Drops the per-item lists of `graphs` that none of `kinds` asked for.
+pub(crate) fn retain_kinds(graphs: &mut WorkspaceGraphs, kinds: &HashSet<SymbolKind>) {
+    // Functions and methods share `function_info`, so either kind keeps it
+    if !kinds.contains(&SymbolKind::Fn) && !kinds.contains(&SymbolKind::Method) {
+        graphs.function_info.clear();
+    }
+    if !kinds.contains(&SymbolKind::Struct) {
+        graphs.struct_info.clear();
+    }
+    if !kinds.contains(&SymbolKind::Enum) {
+        graphs.enum_info.clear();
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>, McpError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| McpError::invalid_params("Invalid glob pattern", Some(json!({ "pattern": pattern, "error": e.to_string() }))))?;
+        builder.add(glob);
+    }
+
+    builder.build()
+        .map(Some)
+        .map_err(|e| McpError::invalid_params("Invalid glob pattern", Some(json!({ "error": e.to_string() }))))
 }
 
 fn detect_unused_functions(functions: &[FunctionInfo], call_graph: &HashMap<String, Vec<String>>) -> Vec<String> {
     let mut used_functions = std::collections::HashSet::new();
     
-    // Mark functions that are called
+    // Mark functions that are called; callees are paths, so compare by their final segment
     for callees in call_graph.values() {
         for callee in callees {
-            used_functions.insert(callee.clone());
+            let name = callee.rsplit("::").next().unwrap_or(callee);
+            used_functions.insert(name.to_string());
         }
     }
     
@@ -132,77 +422,282 @@ fn detect_unused_functions(functions: &[FunctionInfo], call_graph: &HashMap<Stri
         .collect()
 }
 
+/// Functions nothing in the call graph calls, other than `main`.
+fn detect_entry_points(call_graph: &HashMap<String, Vec<String>>) -> Vec<String> {
+    // Callees are paths as written, so compare by their final segment like unused detection does
+    let called: HashSet<&str> = call_graph
+        .values()
+        .flatten()
+        .map(|callee| callee.rsplit("::").next().unwrap_or(callee))
+        .collect();
+
+    let mut entry_points: Vec<String> = call_graph
+        .keys()
+        .filter(|caller| {
+            let name = caller.rsplit("::").next().unwrap_or(caller);
+            name != "main" && !called.contains(name)
+        })
+        .cloned()
+        .collect();
+    entry_points.sort();
+    entry_points
+}
+
+/// Functions whose call graph entry has no callees.
+fn detect_leaf_functions(call_graph: &HashMap<String, Vec<String>>) -> Vec<String> {
+    let mut leaves: Vec<String> = call_graph
+        .iter()
+        .filter(|(_, callees)| callees.is_empty())
+        .map(|(caller, _)| caller.clone())
+        .collect();
+    leaves.sort();
+    leaves
+}
+
+/// Functions that diverge on their own, plus those whose body ends in a call to a diverging
+/// function, repeated until nothing changes. Sorted by qualified name.
+fn detect_diverging_functions(
+    functions: &[FunctionInfo],
+    tail_calls: &HashMap<String, String>,
+    call_graph: &HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    let mut diverging: HashSet<String> = functions
+        .iter()
+        .filter(|func| func.diverges)
+        .map(|func| func.qualified_name.clone())
+        .collect();
+    // Resolve tail callees like call graph edges; every caller key must be present for that
+    let tail_graph: HashMap<String, Vec<String>> = call_graph
+        .keys()
+        .map(|caller| (caller.clone(), tail_calls.get(caller).cloned().into_iter().collect()))
+        .collect();
+    let resolved = resolve_call_graph(&tail_graph);
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (caller, callees) in &resolved {
+            if callees.iter().any(|callee| diverging.contains(callee)) && !diverging.contains(caller) {
+                diverging.insert(caller.clone());
+                changed = true;
+            }
+        }
+    }
+
+    let mut diverging: Vec<String> = diverging.into_iter().collect();
+    diverging.sort();
+    diverging
+}
+
+/// Prefix for the caller keys of the file with module path `module`: the path followed by
+/// `::`, leaving out a lone crate's `crate` root (`crate::net` -> `net::`, `crate` -> ``).
+fn caller_prefix(module: &str) -> String {
+    match module.strip_prefix("crate") {
+        Some("") => String::new(),
+        Some(rest) if rest.starts_with("::") => format!("{}::", &rest[2..]),
+        _ => format!("{}::", module),
+    }
+}
+
+/// Maps each caller's callees, which are paths as written, to the caller keys they name:
+/// the path itself or, for a bare name, a function in the caller's own module.
+fn resolve_call_graph(call_graph: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
+    call_graph
+        .iter()
+        .map(|(caller, callees)| {
+            let module_prefix = caller.rsplit_once("::").map(|(prefix, _)| prefix);
+            let targets = callees
+                .iter()
+                .filter_map(|callee| {
+                    if call_graph.contains_key(callee) {
+                        return Some(callee.clone());
+                    }
+                    let sibling = format!("{}::{}", module_prefix?, callee);
+                    call_graph.contains_key(&sibling).then_some(sibling)
+                })
+                .collect();
+            (caller.clone(), targets)
+        })
+        .collect()
+}
+
+/// `(fan_in, fan_out)` for every caller key: distinct callers resolved to it, and distinct
+/// callees it calls.
+fn fan_in_and_out(call_graph: &HashMap<String, Vec<String>>) -> HashMap<String, (usize, usize)> {
+    let mut callers: HashMap<&str, HashSet<&str>> = HashMap::new();
+    let resolved = resolve_call_graph(call_graph);
+    for (caller, callees) in &resolved {
+        for callee in callees {
+            callers.entry(callee.as_str()).or_default().insert(caller.as_str());
+        }
+    }
+
+    call_graph
+        .iter()
+        .map(|(key, callees)| {
+            let fan_in = callers.get(key.as_str()).map_or(0, HashSet::len);
+            let fan_out = callees.iter().collect::<HashSet<_>>().len();
+            (key.clone(), (fan_in, fan_out))
+        })
+        .collect()
+}
+
+/// Reports call graph cycles: every strongly connected component with more than one
+/// function, plus functions that call themselves directly.
+fn detect_recursive_functions(call_graph: &HashMap<String, Vec<String>>) -> Vec<Vec<String>> {
+    // Callees are paths as written, so resolve them to caller keys before looking for cycles
+    let resolved = resolve_call_graph(call_graph);
+
+    strongly_connected_components(&resolved)
+        .into_iter()
+        .filter(|component| component.len() > 1 || resolved[&component[0]].contains(&component[0]))
+        .collect()
+}
+
 fn generate_refactoring_suggestions(
     functions: &[FunctionInfo], 
     structs: &[StructInfo], 
     enums: &[EnumInfo],
     call_graph: &HashMap<String, Vec<String>>,
-    type_usage: &HashMap<String, Vec<ReferenceLocation>>
-) -> Vec<String> {
+    type_usage: &HashMap<String, Vec<ReferenceLocation>>,
+    thresholds: &Thresholds,
+) -> Vec<RefactoringSuggestion> {
     let mut suggestions = Vec::new();
+    let function_suggestion = |kind, func: &FunctionInfo, metric, threshold, message: String| RefactoringSuggestion {
+        kind,
+        target: func.name.clone(),
+        file: Some(func.file.clone()),
+        range: Some(func.range.clone()),
+        metric,
+        threshold,
+        message,
+    };
     
-    // 1. Long functions (>50 lines)
+    // 1. Long functions
     for func in functions {
-        if func.line_count > 50 {
-            suggestions.push(format!(
-                "Function '{}' in {} is too long ({} lines). Consider breaking it into smaller functions.",
-                func.name, func.file, func.line_count
+        if func.line_count > thresholds.max_function_lines {
+            suggestions.push(function_suggestion(
+                RefactoringKind::LongFunction, func, func.line_count, thresholds.max_function_lines,
+                format!(
+                    "Function '{}' in {} is too long ({} lines). Consider breaking it into smaller functions.",
+                    func.name, func.file, func.line_count
+                ),
             ));
         }
     }
     
-    // 2. High complexity functions (>10 cyclomatic complexity)
+    // 2. High complexity functions (cyclomatic complexity)
     for func in functions {
-        if func.complexity > 10 {
-            suggestions.push(format!(
-                "Function '{}' in {} has high complexity ({}). Consider simplifying the logic.",
-                func.name, func.file, func.complexity
+        if func.complexity > thresholds.max_complexity {
+            suggestions.push(function_suggestion(
+                RefactoringKind::HighComplexity, func, func.complexity, thresholds.max_complexity,
+                format!(
+                    "Function '{}' in {} has high complexity ({}). Consider simplifying the logic.",
+                    func.name, func.file, func.complexity
+                ),
             ));
         }
     }
     
-    // 3. Functions with too many parameters (>5)
+    // 3. Hard-to-read functions (cognitive complexity)
     for func in functions {
-        if func.param_count > 5 {
-            suggestions.push(format!(
-                "Function '{}' in {} has too many parameters ({}). Consider using a struct or builder pattern.",
-                func.name, func.file, func.param_count
+        if func.cognitive_complexity > thresholds.max_cognitive_complexity {
+            suggestions.push(function_suggestion(
+                RefactoringKind::HighCognitiveComplexity, func, func.cognitive_complexity, thresholds.max_cognitive_complexity,
+                format!(
+                    "Function '{}' in {} has high cognitive complexity ({}). Consider flattening nested logic with early returns or helper functions.",
+                    func.name, func.file, func.cognitive_complexity
+                ),
             ));
         }
     }
     
-    // 4. Large structs (>10 fields)
-    for struct_info in structs {
-        if struct_info.field_count > 10 {
-            suggestions.push(format!(
-                "Struct '{}' in {} has too many fields ({}). Consider splitting into smaller structs.",
-                struct_info.name, struct_info.file, struct_info.field_count
+    // 4. Deeply nested functions
+    for func in functions {
+        if func.max_nesting_depth > thresholds.max_nesting_depth {
+            suggestions.push(function_suggestion(
+                RefactoringKind::DeepNesting, func, func.max_nesting_depth, thresholds.max_nesting_depth,
+                format!(
+                    "Function '{}' in {} is deeply nested ({} levels). Consider extracting inner blocks or using early returns.",
+                    func.name, func.file, func.max_nesting_depth
+                ),
             ));
         }
     }
     
-    // 5. Large enums (>10 variants)
-    for enum_info in enums {
-        if enum_info.variant_count > 10 {
-            suggestions.push(format!(
-                "Enum '{}' in {} has too many variants ({}). Consider using separate enums or structs.",
-                enum_info.name, enum_info.file, enum_info.variant_count
+    // 5. Functions with too many parameters
+    for func in functions {
+        if func.param_count > thresholds.max_params {
+            suggestions.push(function_suggestion(
+                RefactoringKind::TooManyParams, func, func.param_count, thresholds.max_params,
+                format!(
+                    "Function '{}' in {} has too many parameters ({}). Consider using a struct or builder pattern.",
+                    func.name, func.file, func.param_count
+                ),
             ));
         }
     }
     
-    // 6. Functions that call many other functions (>10 callees)
-    for (caller, callees) in call_graph {
-        if callees.len() > 10 {
-            suggestions.push(format!(
-                "Function '{}' calls too many other functions ({}). Consider reducing coupling.",
-                caller, callees.len()
-            ));
+    // 6. Large structs
+    for struct_info in structs {
+        if struct_info.field_count > thresholds.max_struct_fields {
+            suggestions.push(RefactoringSuggestion {
+                kind: RefactoringKind::LargeStruct,
+                target: struct_info.name.clone(),
+                file: Some(struct_info.file.clone()),
+                range: Some(struct_info.range.clone()),
+                metric: struct_info.field_count,
+                threshold: thresholds.max_struct_fields,
+                message: format!(
+                    "Struct '{}' in {} has too many fields ({}). Consider splitting into smaller structs.",
+                    struct_info.name, struct_info.file, struct_info.field_count
+                ),
+            });
         }
     }
     
-    // 7. Functions that are called by many others (>10 callers)
-    let mut caller_counts = HashMap::new();
+    // 7. Large enums
+    for enum_info in enums {
+        if enum_info.variant_count > thresholds.max_enum_variants {
+            suggestions.push(RefactoringSuggestion {
+                kind: RefactoringKind::LargeEnum,
+                target: enum_info.name.clone(),
+                file: Some(enum_info.file.clone()),
+                range: Some(enum_info.range.clone()),
+                metric: enum_info.variant_count,
+                threshold: thresholds.max_enum_variants,
+                message: format!(
+                    "Enum '{}' in {} has too many variants ({}). Consider using separate enums or structs.",
+                    enum_info.name, enum_info.file, enum_info.variant_count
+                ),
+            });
+        }
+    }
+    
+    // 8. Functions that call many other functions
+    let mut callers: Vec<_> = call_graph.iter().collect();
+    callers.sort();
+    for (caller, callees) in callers {
+        if callees.len() > thresholds.max_callees {
+            // Callers are keyed by qualified name; methods have no FunctionInfo to locate them by
+            let location = functions.iter().find(|func| func.qualified_name == *caller);
+            suggestions.push(RefactoringSuggestion {
+                kind: RefactoringKind::TooManyCallees,
+                target: caller.clone(),
+                file: location.map(|func| func.file.clone()),
+                range: location.map(|func| func.range.clone()),
+                metric: callees.len(),
+                threshold: thresholds.max_callees,
+                message: format!(
+                    "Function '{}' calls too many other functions ({}). Consider reducing coupling.",
+                    caller, callees.len()
+                ),
+            });
+        }
+    }
+    
+    // 9. Functions that are called by many others
+    let mut caller_counts = std::collections::BTreeMap::new();
     for callees in call_graph.values() {
         for callee in callees {
             *caller_counts.entry(callee.clone()).or_insert(0) += 1;
@@ -210,24 +705,68 @@ fn generate_refactoring_suggestions(
     }
     
     for (callee, count) in caller_counts {
-        if count > 10 {
-            suggestions.push(format!(
-                "Function '{}' is called by too many functions ({}). Consider introducing an interface or facade.",
-                callee, count
-            ));
+        if count > thresholds.max_callers {
+            suggestions.push(RefactoringSuggestion {
+                kind: RefactoringKind::TooManyCallers,
+                message: format!(
+                    "Function '{}' is called by too many functions ({}). Consider introducing an interface or facade.",
+                    callee, count
+                ),
+                target: callee,
+                file: None,
+                range: None,
+                metric: count,
+                threshold: thresholds.max_callers,
+            });
         }
     }
     
-    // 8. God object detection (structs used in many places)
+    // 10. Hubs: functions many others depend on that themselves depend on many
+    for func in functions {
+        let coupling = func.fan_in.min(func.fan_out);
+        if coupling > thresholds.max_fan_in_and_out {
+            suggestions.push(function_suggestion(
+                RefactoringKind::HubFunction, func, coupling, thresholds.max_fan_in_and_out,
+                format!(
+                    "Function '{}' in {} has both high fan-in ({}) and high fan-out ({}). Consider splitting it so callers depend on a narrower piece.",
+                    func.name, func.file, func.fan_in, func.fan_out
+                ),
+            ));
+        }
+    }
+
+    // 11. Functions whose generics are hard to follow
+    for func in functions {
+        let generics = func.generic_param_count + func.where_predicate_count;
+        if generics > thresholds.max_generics {
+            suggestions.push(function_suggestion(
+                RefactoringKind::ComplexGenerics, func, generics, thresholds.max_generics,
+                format!(
+                    "Function '{}' in {} has {} generic parameters and {} where-clause predicates. Consider grouping bounds behind a trait or using concrete types.",
+                    func.name, func.file, func.generic_param_count, func.where_predicate_count
+                ),
+            ));
+        }
+    }
+
+    // 12. God object detection (structs used in many places, not counting their declaration)
     for struct_info in structs {
         let usage_count = type_usage.get(&struct_info.name).map(|locs| locs.len()).unwrap_or(0);
-        if usage_count > 10 {
-            suggestions.push(format!(
-                "Struct '{}' in {} is used in too many places ({}). Consider breaking it into smaller components.",
-                struct_info.name, struct_info.file, usage_count
-            ));
+        if usage_count > thresholds.max_type_usages {
+            suggestions.push(RefactoringSuggestion {
+                kind: RefactoringKind::GodObject,
+                target: struct_info.name.clone(),
+                file: Some(struct_info.file.clone()),
+                range: Some(struct_info.range.clone()),
+                metric: usage_count,
+                threshold: thresholds.max_type_usages,
+                message: format!(
+                    "Struct '{}' in {} is used in too many places ({}). Consider breaking it into smaller components.",
+                    struct_info.name, struct_info.file, usage_count
+                ),
+            });
         }
     }
     
     suggestions
-}
\ No newline at end of file
+}
@@ -0,0 +1,50 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+const DEFAULT_MAX_LINES: usize = 500;
+const DEFAULT_MAX_ITEMS: usize = 50;
+
+pub async fn largest_files(
+    server: &MyServer,
+    Parameters(LargestFilesParams { max_lines, max_items, limit }): Parameters<LargestFilesParams>,
+) -> Result<CallToolResult, McpError> {
+    let max_lines = max_lines.unwrap_or(DEFAULT_MAX_LINES);
+    let max_items = max_items.unwrap_or(DEFAULT_MAX_ITEMS);
+    let mut sizes = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        let line_count = code.lines().count();
+        let item_count = match syn::parse_file(code) {
+            Ok(ast) => {
+                let mut collector = SymbolCollector::new(path.clone(), None);
+                collector.visit_file(&ast);
+                collector.out.len()
+            }
+            Err(_) => 0,
+        };
+        sizes.push(FileSize {
+            file: path.clone(),
+            line_count,
+            item_count,
+            oversized: line_count > max_lines || item_count > max_items,
+        });
+    }
+
+    // Largest first; files of equal size stay in path order
+    sizes.sort_by(|a, b| (b.line_count, b.item_count).cmp(&(a.line_count, a.item_count)).then_with(|| a.file.cmp(&b.file)));
+    if let Some(limit) = limit {
+        sizes.truncate(limit);
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&sizes).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
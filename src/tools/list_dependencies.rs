@@ -0,0 +1,29 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use crate::models::*;
+use crate::cache::*;
+use crate::manifest;
+use std::path::PathBuf;
+
+pub async fn list_dependencies(
+    _server: &MyServer,
+    Parameters(ListDependenciesParams { manifest_path }): Parameters<ListDependenciesParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut manifest_path = PathBuf::from(manifest_path);
+    if manifest_path.is_dir() {
+        manifest_path.push("Cargo.toml");
+    }
+
+    let dependencies = tokio::task::spawn_blocking(move || manifest::dependencies(&manifest_path))
+        .await
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .map_err(|e| McpError::invalid_params("Failed to read manifest", Some(json!({ "error": e }))))?;
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&dependencies).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
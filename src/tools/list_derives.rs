@@ -0,0 +1,36 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn list_derives(
+    server: &MyServer,
+    Parameters(ListDerivesParams { derive, position_options }): Parameters<ListDerivesParams>,
+) -> Result<CallToolResult, McpError> {
+    let mut types = Vec::new();
+    let code_map = server.cache.get_all().await;
+
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut collector = DeriveCollector { file: path.clone(), line_index: line_index.as_ref(), out: Vec::new() };
+            collector.visit_file(&ast);
+            types.extend(collector.out);
+        }
+    }
+
+    if let Some(derive) = derive {
+        let wanted = derive.rsplit("::").next().unwrap_or(&derive).to_string();
+        types.retain(|ty| ty.derives.iter().any(|name| name.rsplit("::").next() == Some(wanted.as_str())));
+    }
+    types.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&types).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -1,7 +1,54 @@
 pub mod check_file;
+pub mod check_files;
+pub mod cargo_check;
+pub mod clippy;
+pub mod list_dependencies;
+pub mod find_unused_dependencies;
 pub mod index_workspace;
+pub mod reindex_changed;
 pub mod goto_definition;
+pub mod search_symbols;
 pub mod find_references;
+pub mod find_variant_references;
+pub mod find_field_references;
+pub mod find_deprecated_usages;
+pub mod find_duplicate_definitions;
+pub mod find_callers;
+pub mod call_path;
+pub mod module_topo_order;
+pub mod find_panics;
+pub mod find_empty_functions;
+pub mod find_large_matches;
+pub mod find_clone_hotspots;
+pub mod detect_long_chains;
+pub mod find_large_returns;
+pub mod detect_early_returns;
+pub mod find_nested_wrappers;
+pub mod find_shadowed_variables;
+pub mod find_unused_variables;
+pub mod find_unreachable_code;
+pub mod find_commented_code;
+pub mod file_comment_stats;
+pub mod largest_files;
+pub mod list_tests;
+pub mod find_tests_without_assertions;
+pub mod find_blocking_in_async;
+pub mod find_await_in_loops;
+pub mod list_derives;
+pub mod suggest_derives;
+pub mod semantic_tokens;
+pub mod organize_imports;
+pub mod check_naming;
+pub mod find_missing_docs;
+pub mod get_docs;
+pub mod get_source;
+pub mod generate_api_docs;
+pub mod diff_public_api;
+pub mod compare_workspaces;
+pub mod cache_stats;
+pub mod clear_cache;
+pub mod ping;
+pub mod progress;
 pub mod server_handler;
 
 use rmcp::{
@@ -10,11 +57,15 @@ use rmcp::{
     tool_router,
     ErrorData as McpError,
     handler::server::wrapper::Parameters,
+    Peer,
+    RoleServer,
 };
 use crate::models::*;
 use crate::cache::MyServer;
+use progress::ProgressReporter;
+use tokio_util::sync::CancellationToken;
 
-#[tool_router]
+#[tool_router(vis = "pub(crate)")]
 impl MyServer {
     #[tool(description = "Parse and check a Rust file for syntax errors")]
     pub async fn check_file(
@@ -24,15 +75,75 @@ impl MyServer {
         check_file::check_file(self, params).await
     }
 
-    #[tool(description = "Index all Rust files in a directory and build call graph, type usage graph, and module dependency graph for AI navigation and code analysis")]
+    #[tool(description = "Check several Rust files for syntax errors in one call, reading and parsing them concurrently; returns a map of path to diagnostics. Files that parse are cached, like with check_file")]
+    pub async fn check_files(
+        &self,
+        params: Parameters<CheckFilesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        check_files::check_files(self, params).await
+    }
+
+    #[tool(description = "Run `cargo check` in a manifest directory and return the compiler's errors and warnings as diagnostics, catching type errors that check_file's syntax check can't")]
+    pub async fn cargo_check(
+        &self,
+        params: Parameters<CargoCheckParams>,
+    ) -> Result<CallToolResult, McpError> {
+        cargo_check::cargo_check(self, params).await
+    }
+
+    #[tool(description = "Run `cargo clippy` in a manifest directory and return its lints as diagnostics with the lint name in `code`, optionally filtered by minimum severity")]
+    pub async fn clippy(
+        &self,
+        params: Parameters<ClippyParams>,
+    ) -> Result<CallToolResult, McpError> {
+        clippy::clippy(self, params).await
+    }
+
+    #[tool(description = "List the normal, dev and build dependencies of a Cargo.toml with their version requirements and features, resolving `workspace = true` entries against the workspace root")]
+    pub async fn list_dependencies(
+        &self,
+        params: Parameters<ListDependenciesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        list_dependencies::list_dependencies(self, params).await
+    }
+
+    #[tool(description = "List dependencies declared in a Cargo.toml that no source file of the package refers to, by `use`, path, `extern crate`, or inside macros and attributes")]
+    pub async fn find_unused_dependencies(
+        &self,
+        params: Parameters<FindUnusedDependenciesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_unused_dependencies::find_unused_dependencies(self, params).await
+    }
+
+    #[tool(name = "index_workspace", description = "Index all Rust files in a directory and build call graph, type usage graph, and module dependency graph for AI navigation and code analysis. Sends progress notifications when the request carries a progress token and stops early if the request is cancelled")]
+    async fn index_workspace_with_progress(
+        &self,
+        meta: Meta,
+        peer: Peer<RoleServer>,
+        cancellation: CancellationToken,
+        params: Parameters<IndexWorkspaceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let progress = ProgressReporter::new(&meta, peer);
+        index_workspace::index_workspace(self, params, progress.as_ref(), Some(&cancellation)).await
+    }
+
+    /// Same as the `index_workspace` tool, for callers without an MCP request context.
     pub async fn index_workspace(
         &self,
         params: Parameters<IndexWorkspaceParams>,
     ) -> Result<CallToolResult, McpError> {
-        index_workspace::index_workspace(self, params).await
+        index_workspace::index_workspace(self, params, None, None).await
+    }
+
+    #[tool(description = "Reanalyze only the .rs files that differ from a git ref (default HEAD) under a root, or are untracked, and return the updated graphs of the last index_workspace run. Deleted files are evicted from the cache")]
+    pub async fn reindex_changed(
+        &self,
+        params: Parameters<ReindexChangedParams>,
+    ) -> Result<CallToolResult, McpError> {
+        reindex_changed::reindex_changed(self, params).await
     }
 
-    #[tool(description = "Find definition of a symbol")]
+    #[tool(description = "Find definition of a symbol, by name or by the identifier at a file position")]
     pub async fn goto_definition(
         &self,
         params: Parameters<GotoDefinitionParams>,
@@ -40,13 +151,321 @@ impl MyServer {
         goto_definition::goto_definition(self, params).await
     }
 
-    #[tool(description = "Find references of a symbol")]
+    #[tool(description = "Find symbols whose names match a regular expression, sorted by name")]
+    pub async fn search_symbols(
+        &self,
+        params: Parameters<SearchSymbolsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        search_symbols::search_symbols(self, params).await
+    }
+
+    #[tool(description = "Find references of a symbol, by name or by the identifier at a file position")]
     pub async fn find_references(
         &self,
         params: Parameters<FindReferencesParams>,
     ) -> Result<CallToolResult, McpError> {
         find_references::find_references(self, params).await
     }
+
+    #[tool(description = "Find uses of one enum variant written as `Enum::Variant` (or `Self::Variant` inside the enum's impl) in expressions, match/if-let patterns, and use items. More precise than find_references, which matches the bare name anywhere")]
+    pub async fn find_variant_references(
+        &self,
+        params: Parameters<FindVariantReferencesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_variant_references::find_variant_references(self, params).await
+    }
+
+    #[tool(description = "Find uses of one struct field: `value.field` accesses, struct literal inits, and destructuring patterns. Each result says whether the struct was identified (`exact`) or the access only matched by field name because the receiver's type is unknown")]
+    pub async fn find_field_references(
+        &self,
+        params: Parameters<FindFieldReferencesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_field_references::find_field_references(self, params).await
+    }
+
+    #[tool(description = "Find every use of an item marked `#[deprecated]` in cached files, reporting the use's location along with the deprecated item, its `since` version, and its note, as a migration checklist")]
+    pub async fn find_deprecated_usages(
+        &self,
+        params: Parameters<FindDeprecatedUsagesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_deprecated_usages::find_deprecated_usages(self, params).await
+    }
+
+    #[tool(description = "Find names defined more than once with the same kind across cached files (e.g. two `pub fn parse` in different modules), listing every definition; methods only collide within the same type. Optionally only public items")]
+    pub async fn find_duplicate_definitions(
+        &self,
+        params: Parameters<FindDuplicateDefinitionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_duplicate_definitions::find_duplicate_definitions(self, params).await
+    }
+
+    #[tool(description = "Find the functions that call a given function, with the location of each call site in the cached files")]
+    pub async fn find_callers(
+        &self,
+        params: Parameters<FindCallersParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_callers::find_callers(self, params).await
+    }
+
+    #[tool(description = "Find panic points (unwrap, expect, panic!, unreachable!, todo!, unimplemented!) in cached files, optionally filtered by kind")]
+    pub async fn find_panics(
+        &self,
+        params: Parameters<FindPanicsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_panics::find_panics(self, params).await
+    }
+
+    #[tool(description = "Find functions, methods, and default trait methods in cached files whose body is empty or only a `todo!()`/`unimplemented!()`, which usually mark stubs; each result says which, so placeholders can be told from intentionally empty bodies")]
+    pub async fn find_empty_functions(
+        &self,
+        params: Parameters<FindEmptyFunctionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_empty_functions::find_empty_functions(self, params).await
+    }
+
+    #[tool(description = "Find `match` expressions in cached files with more arms than a threshold (default 10), and matches over enum variants whose `_ => {}` catch-all may hide missed variants, with the enclosing function and arm count")]
+    pub async fn find_large_matches(
+        &self,
+        params: Parameters<FindLargeMatchesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_large_matches::find_large_matches(self, params).await
+    }
+
+    #[tool(description = "Find functions in cached files with more `.clone()` calls than a threshold (default 5), with the count and each call's location; a concentration of clones is a hotspot worth reviewing for performance")]
+    pub async fn find_clone_hotspots(
+        &self,
+        params: Parameters<FindCloneHotspotsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_clone_hotspots::find_clone_hotspots(self, params).await
+    }
+
+    #[tool(description = "Find method chains in cached files with more consecutive calls than a threshold (default 5), e.g. `a.b().c().d().e().f().g()`, with the chain's length, methods, enclosing function, and location")]
+    pub async fn detect_long_chains(
+        &self,
+        params: Parameters<DetectLongChainsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        detect_long_chains::detect_long_chains(self, params).await
+    }
+
+    #[tool(description = "Find functions and methods in cached files that return an owned collection (`Vec`, `HashMap`, ...) or a struct with many fields (default 8) by value, looking through `Result` and `Option`, and note which take references so a borrow might do instead")]
+    pub async fn find_large_returns(
+        &self,
+        params: Parameters<FindLargeReturnsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_large_returns::find_large_returns(self, params).await
+    }
+
+    #[tool(description = "Find functions and methods in cached files with more explicit `return` expressions than a threshold (default 3), with the location of each return. A `return` ending the body isn't counted, nor are returns inside closures and async blocks")]
+    pub async fn detect_early_returns(
+        &self,
+        params: Parameters<DetectEarlyReturnsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        detect_early_returns::detect_early_returns(self, params).await
+    }
+
+    #[tool(description = "Find parameter, return, and field types in cached files that nest `Result` and `Option` deeper than a threshold (default 2), e.g. `Result<Option<Result<T, E>>, E>`, with the offending type and its location")]
+    pub async fn find_nested_wrappers(
+        &self,
+        params: Parameters<FindNestedWrappersParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_nested_wrappers::find_nested_wrappers(self, params).await
+    }
+
+    #[tool(description = "Check cached files for names that break Rust conventions: snake_case functions, methods and modules, UpperCamelCase types and traits, SCREAMING_SNAKE_CASE consts and statics")]
+    pub async fn check_naming(
+        &self,
+        params: Parameters<CheckNamingParams>,
+    ) -> Result<CallToolResult, McpError> {
+        check_naming::check_naming(self, params).await
+    }
+
+    #[tool(description = "List pub items (functions, methods, types, traits, consts, modules) in cached files that have no doc comment, optionally only those reachable as public API")]
+    pub async fn find_missing_docs(
+        &self,
+        params: Parameters<FindMissingDocsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_missing_docs::find_missing_docs(self, params).await
+    }
+
+    #[tool(description = "Find `let` bindings in cached files that shadow a variable or parameter of the same name still in scope, with both locations. Informational; can be filtered by whether the type changed")]
+    pub async fn find_shadowed_variables(
+        &self,
+        params: Parameters<FindShadowedVariablesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_shadowed_variables::find_shadowed_variables(self, params).await
+    }
+
+    #[tool(description = "Find `let` bindings in cached files that are never read, like rustc's unused_variables warning; names starting with `_` are skipped")]
+    pub async fn find_unused_variables(
+        &self,
+        params: Parameters<FindUnusedVariablesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_unused_variables::find_unused_variables(self, params).await
+    }
+
+    #[tool(description = "Find statements in cached files that follow an unconditional return, break, continue, or panic!/unreachable!/todo!/unimplemented! in the same block")]
+    pub async fn find_unreachable_code(
+        &self,
+        params: Parameters<FindUnreachableCodeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_unreachable_code::find_unreachable_code(self, params).await
+    }
+
+    #[tool(description = "Find runs of `//` comment lines in cached files that look like commented-out Rust code (statements ending in `;`, braces, `fn`/`let`/`use` lines, ...), returning each run's file and line range. Works on the raw text, so files need not parse")]
+    pub async fn find_commented_code(
+        &self,
+        params: Parameters<FindCommentedCodeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_commented_code::find_commented_code(self, params).await
+    }
+
+    #[tool(description = "Count the code, comment, and blank lines of one file or of every cached file, with the comment-to-code ratio, for documentation audits. Computed lexically: block comments spanning lines and trailing comments are handled, and a line with both code and a comment counts as code")]
+    pub async fn file_comment_stats(
+        &self,
+        params: Parameters<FileCommentStatsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        file_comment_stats::file_comment_stats(self, params).await
+    }
+
+    #[tool(description = "List cached files by size, largest first, with each file's line and item counts; files over the line (default 500) or item (default 50) threshold are flagged as oversized and worth splitting")]
+    pub async fn largest_files(
+        &self,
+        params: Parameters<LargestFilesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        largest_files::largest_files(self, params).await
+    }
+
+    #[tool(description = "List test functions (#[test], #[tokio::test], #[test_case(..)] and similar) in cached files with their module-qualified names, locations, and whether they're #[ignore]d")]
+    pub async fn list_tests(
+        &self,
+        params: Parameters<ListTestsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        list_tests::list_tests(self, params).await
+    }
+
+    #[tool(description = "Find test functions in cached files that can't fail: no assert!/assert_eq!/panic!-style macro, no `?`, no unwrap/expect, and no #[should_panic]")]
+    pub async fn find_tests_without_assertions(
+        &self,
+        params: Parameters<FindTestsWithoutAssertionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_tests_without_assertions::find_tests_without_assertions(self, params).await
+    }
+
+    #[tool(description = "Find calls to blocking APIs (std::thread::sleep, std::fs, std::net, ...) inside async functions and async blocks in cached files; the list can be extended with extra_blocking_calls")]
+    pub async fn find_blocking_in_async(
+        &self,
+        params: Parameters<FindBlockingInAsyncParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_blocking_in_async::find_blocking_in_async(self, params).await
+    }
+
+    #[tool(description = "Find `.await`s inside for/while/loop bodies in cached files, where join_all or buffer_unordered may run the futures concurrently; reports the enclosing function and loop kind")]
+    pub async fn find_await_in_loops(
+        &self,
+        params: Parameters<FindAwaitInLoopsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        find_await_in_loops::find_await_in_loops(self, params).await
+    }
+
+    #[tool(description = "Find the shortest chain of calls from one function to another across cached files, returning the functions along the way and each hop's call site")]
+    pub async fn call_path(
+        &self,
+        params: Parameters<CallPathParams>,
+    ) -> Result<CallToolResult, McpError> {
+        call_path::call_path(self, params).await
+    }
+
+    #[tool(description = "Topologically sort the modules of the cached files so each comes after the modules it imports from, or report the import cycle that prevents such an order")]
+    pub async fn module_topo_order(&self) -> Result<CallToolResult, McpError> {
+        module_topo_order::module_topo_order(self).await
+    }
+
+    #[tool(description = "List the `#[derive(...)]` traits and other attributes of every struct, enum, and union in cached files, optionally only the types deriving a given trait")]
+    pub async fn list_derives(
+        &self,
+        params: Parameters<ListDerivesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        list_derives::list_derives(self, params).await
+    }
+
+    #[tool(description = "For structs and enums in cached files, suggest which of Debug, Clone, PartialEq, and Default they could derive but don't, judging heuristically from their field types (references, trait objects, and types known not to implement the trait block a derive). Each suggestion and each blocked trait comes with a reason")]
+    pub async fn suggest_derives(
+        &self,
+        params: Parameters<SuggestDerivesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        suggest_derives::suggest_derives(self, params).await
+    }
+
+    #[tool(description = "Classify every identifier in a file as function, method, struct, enum, interface, variable, parameter, etc. using LSP semantic token type names, for syntax highlighting")]
+    pub async fn semantic_tokens(
+        &self,
+        params: Parameters<SemanticTokensParams>,
+    ) -> Result<CallToolResult, McpError> {
+        semantic_tokens::semantic_tokens(self, params).await
+    }
+
+    #[tool(description = "Organize the top-level imports of a file: drop unused `use` imports, merge imports from the same module into `use a::{b, c}`, and sort them into std, external, and crate groups. Returns a WorkspaceEdit replacing the import block, plus a unified diff with `preview`; nothing is written. `pub use`, `#[cfg]`-gated, and documented imports are kept as they are")]
+    pub async fn organize_imports(
+        &self,
+        params: Parameters<OrganizeImportsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        organize_imports::organize_imports(self, params).await
+    }
+
+    #[tool(description = "Get the doc comment of a symbol as markdown, headed by its signature; symbols with the same name in several files are all returned")]
+    pub async fn get_docs(
+        &self,
+        params: Parameters<GetDocsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        get_docs::get_docs(self, params).await
+    }
+
+    #[tool(description = "Get the exact source text of an item from the cached files, attributes and body included: by name (optionally `Type::method`), or as the innermost item spanning a line of a file. Saves reading the whole file to see one definition")]
+    pub async fn get_source(
+        &self,
+        params: Parameters<GetSourceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        get_source::get_source(self, params).await
+    }
+
+    #[tool(description = "Generate a markdown API reference of the cached files: every pub item grouped by module, with its signature and the first line of its docs")]
+    pub async fn generate_api_docs(
+        &self,
+        params: Parameters<GenerateApiDocsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        generate_api_docs::generate_api_docs(self, params).await
+    }
+
+    #[tool(description = "Compare the public API of two source trees read from disk (e.g. the last release and the working copy): added, removed, and changed pub items, signature changes, and removed or added variants and fields, each classified as a major, minor, or patch change")]
+    pub async fn diff_public_api(
+        &self,
+        params: Parameters<DiffPublicApiParams>,
+    ) -> Result<CallToolResult, McpError> {
+        diff_public_api::diff_public_api(self, params).await
+    }
+
+    #[tool(description = "Compare every symbol of two source trees read from disk, keyed by kind and module path: which were added, removed, or kept, grouped by file. Useful to review large refactors for accidental deletions")]
+    pub async fn compare_workspaces(
+        &self,
+        params: Parameters<CompareWorkspacesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        compare_workspaces::compare_workspaces(self, params).await
+    }
+
+    #[tool(description = "Report the number of cached files and the total cached bytes")]
+    pub async fn cache_stats(&self) -> Result<CallToolResult, McpError> {
+        cache_stats::cache_stats(self).await
+    }
+
+    #[tool(description = "Empty the source cache so the next analysis re-reads files from disk; returns the stats of what was evicted")]
+    pub async fn clear_cache(&self) -> Result<CallToolResult, McpError> {
+        clear_cache::clear_cache(self).await
+    }
+
+    #[tool(description = "Check that the server is alive: returns its name, version, uptime in seconds, number of cached files, and the names of all available tools")]
+    pub async fn ping(&self) -> Result<CallToolResult, McpError> {
+        ping::ping(self).await
+    }
 }
 
 include!("server_handler.rs");
\ No newline at end of file
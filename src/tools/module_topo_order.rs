@@ -0,0 +1,32 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+};
+use crate::models::*;
+use crate::cache::*;
+use crate::analysis::analyze_source;
+use crate::modules::{module_topo_order as topo_order, resolve_module_paths};
+use super::index_workspace::module_use_paths;
+use std::collections::{BTreeMap, HashMap};
+
+pub async fn module_topo_order(server: &MyServer) -> Result<CallToolResult, McpError> {
+    let code_map = server.cache.get_all().await;
+    let analyses: BTreeMap<String, FileAnalysis> = code_map
+        .iter()
+        .filter_map(|(path, code)| Some((path.clone(), analyze_source(path, code).ok()?)))
+        .collect();
+
+    let mod_declarations: HashMap<String, Vec<ModDeclaration>> = analyses
+        .iter()
+        .map(|(path, analysis)| (path.clone(), analysis.mod_declarations.clone()))
+        .collect();
+    let module_paths = resolve_module_paths(&mod_declarations);
+    let result = match topo_order(&module_use_paths(&analyses, &module_paths)) {
+        Ok(order) => ModuleTopoOrder { order: Some(order), cycle: None },
+        Err(cycle) => ModuleTopoOrder { order: None, cycle: Some(cycle) },
+    };
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&result).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
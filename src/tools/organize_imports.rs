@@ -0,0 +1,315 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use quote::ToTokens;
+use proc_macro2::{LineColumn, TokenStream, TokenTree};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use crate::models::*;
+use crate::cache::*;
+use crate::edits::{apply_edits, preview_diff};
+use crate::line_index::{locations_to_range, LineIndex};
+use crate::visitors::SymbolCollector;
+
+/// Modules and types of `std` (paths after `std`/`core`/`alloc`) known not to be traits, so
+/// an unused import of them can be dropped safely.
+const STD_NON_TRAITS: &[&str] = &[
+    "collections", "collections::HashMap", "collections::HashSet", "collections::BTreeMap",
+    "collections::BTreeSet", "collections::VecDeque", "collections::BinaryHeap", "collections::LinkedList",
+    "sync", "sync::Arc", "sync::Mutex", "sync::RwLock", "sync::Once", "sync::mpsc", "rc", "rc::Rc",
+    "cell", "cell::Cell", "cell::RefCell", "path", "path::Path", "path::PathBuf", "time",
+    "time::Duration", "time::Instant", "fs", "fs::File", "io", "fmt", "env", "mem", "ptr",
+    "thread", "process", "net", "ffi", "ffi::OsStr", "ffi::OsString", "str", "string", "vec",
+];
+
+pub async fn organize_imports(
+    server: &MyServer,
+    Parameters(OrganizeImportsParams { path, preview, position_options }): Parameters<OrganizeImportsParams>,
+) -> Result<CallToolResult, McpError> {
+    let code = match server.cache.get(&path).await {
+        Some(code) => code,
+        None => tokio::fs::read_to_string(&path).await
+            .map_err(|e| McpError::invalid_params("Failed to read file", Some(json!({ "error": e.to_string() }))))?,
+    };
+    let code_map = server.cache.get_all().await;
+    let ast = syn::parse_file(&code)
+        .map_err(|e| McpError::invalid_params("Failed to parse file", Some(json!({ "error": e.to_string() }))))?;
+
+    let mut workspace_items = WorkspaceItems::default();
+    for (file, cached) in code_map.iter().filter(|(file, _)| **file != path) {
+        if let Ok(cached_ast) = syn::parse_file(cached) {
+            workspace_items.add(file, &cached_ast);
+        }
+    }
+    workspace_items.add(&path, &ast);
+
+    let line_index = position_options.line_index(&code);
+    let mut edit = WorkspaceEdit::default();
+    if let Some(text_edit) = organize(&code, &ast, &workspace_items, line_index.as_ref()) {
+        let edits = vec![text_edit];
+        // An import block that is already organized comes out unchanged
+        let organized = apply_edits(&code, &edits, position_options)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        if organized != code {
+            edit.changes.insert(path.clone(), edits);
+        }
+    }
+
+    let diff = match preview {
+        true => Some(preview_diff(&edit, &HashMap::from([(path, code)]), position_options)
+            .map_err(|e| McpError::internal_error(e, None))?),
+        false => None,
+    };
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&EditResult { edit, diff }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// Names of the traits and of the other items declared in the workspace.
+#[derive(Default)]
+struct WorkspaceItems {
+    traits: HashSet<String>,
+    others: HashSet<String>,
+}
+
+impl WorkspaceItems {
+    fn add(&mut self, file: &str, ast: &syn::File) {
+        let mut symbols = SymbolCollector::new(file.to_string(), None);
+        symbols.visit_file(ast);
+        for symbol in symbols.out.into_iter().filter(|symbol| symbol.container.is_none()) {
+            match symbol.kind {
+                SymbolKind::Trait => self.traits.insert(symbol.name),
+                _ => self.others.insert(symbol.name),
+            };
+        }
+    }
+}
+
+/// One imported name, flattened out of its use tree.
+struct Import {
+    /// Path of the module the name is imported from; empty for `use serde;`.
+    parent: Vec<String>,
+    /// The name as written in the tree: `Read`, `Read as R`, `self`, or `*`.
+    leaf: String,
+    /// Name the import binds, checked for uses; `None` for globs and `as _` imports, which are always kept.
+    binds: Option<String>,
+}
+
+/// Replaces the lines from the first to the last organizable top-level `use` with the
+/// organized imports, followed by any other lines in between (attributed imports, comments,
+/// other items) in their original order. Unused imports are dropped when they can't be
+/// traits, see `provably_not_trait`. Returns `None` when there is nothing to organize.
+///
+/// Only private `use` items without attributes that sit alone on their lines are organized;
+/// `pub use` re-exports and imports with `#[cfg]` or doc comments are preserved verbatim.
+fn organize(code: &str, ast: &syn::File, workspace_items: &WorkspaceItems, line_index: Option<&LineIndex>) -> Option<TextEdit> {
+    let lines: Vec<&str> = code.lines().collect();
+    let line_text = |line: usize| lines.get(line - 1).copied().unwrap_or("");
+    let alone_on_lines = |start: LineColumn, end: LineColumn| {
+        line_text(start.line).chars().take(start.column).all(char::is_whitespace)
+            && line_text(end.line).chars().skip(end.column).all(char::is_whitespace)
+    };
+
+    let mut organizable = Vec::new();
+    let mut used = HashSet::new();
+    collect_idents(ast.attrs.iter().map(|attr| attr.to_token_stream()).collect(), &mut used);
+    for item in &ast.items {
+        match item {
+            syn::Item::Use(item_use)
+                if item_use.attrs.is_empty()
+                    && matches!(item_use.vis, syn::Visibility::Inherited)
+                    && alone_on_lines(item_use.span().start(), item_use.span().end()) =>
+            {
+                organizable.push(item_use);
+            }
+            // Preserved imports may name an organized one, as in `use crate::model;` + `pub use model::Record;`
+            item => collect_idents(item.to_token_stream(), &mut used),
+        }
+    }
+    let first_line = organizable.first()?.span().start().line;
+    let last_line = organizable.last()?.span().end().line;
+
+    let mut imports = Vec::new();
+    for item_use in &organizable {
+        let mut prefix = Vec::new();
+        if item_use.leading_colon.is_some() {
+            prefix.push(String::new());
+        }
+        flatten(&item_use.tree, &mut prefix, &mut imports);
+    }
+    // An organized import may start from another, as in `use crate::model;` + `use model::Record;`
+    used.extend(imports.iter().filter_map(|import| import.parent.first().cloned()));
+    // A trait imported for its methods never appears by name, so only names that provably
+    // aren't traits can be dropped
+    imports.retain(|import| match &import.binds {
+        Some(name) => used.contains(name) || !provably_not_trait(import, workspace_items),
+        None => true,
+    });
+
+    // Lines in the block that aren't part of an organized `use` keep their place after it
+    let organized_lines: HashSet<usize> = organizable
+        .iter()
+        .flat_map(|item_use| item_use.span().start().line..=item_use.span().end().line)
+        .collect();
+    let mut kept: Vec<&str> = Vec::new();
+    for line in (first_line..=last_line).filter(|line| !organized_lines.contains(line)).map(line_text) {
+        let blank = line.trim().is_empty();
+        // Blank lines only separate kept lines, one at a time
+        if !blank || kept.last().is_some_and(|last| !last.trim().is_empty()) {
+            kept.push(line);
+        }
+    }
+    if kept.last().is_some_and(|last| last.trim().is_empty()) {
+        kept.pop();
+    }
+
+    let mut new_text = render(imports);
+    if !kept.is_empty() {
+        if !new_text.is_empty() {
+            new_text.push('\n');
+        }
+        for line in kept {
+            new_text.push_str(line);
+            new_text.push('\n');
+        }
+    }
+
+    Some(TextEdit {
+        range: locations_to_range(
+            LineColumn { line: first_line, column: 0 },
+            LineColumn { line: last_line + 1, column: 0 },
+            line_index,
+        ),
+        new_text,
+    })
+}
+
+/// Whether an import names a module or a non-trait item: `self` imports, known `std` modules and
+/// types, and `crate`/`self`/`super` imports of names the workspace only declares as non-traits.
+fn provably_not_trait(import: &Import, workspace_items: &WorkspaceItems) -> bool {
+    if import.leaf == "self" {
+        return true;
+    }
+    let name = import.leaf.split(" as ").next().unwrap_or(&import.leaf);
+    match import.parent.first().map(String::as_str) {
+        Some("std" | "core" | "alloc") => {
+            let path = import.parent[1..].iter().map(String::as_str).chain([name]).collect::<Vec<_>>().join("::");
+            STD_NON_TRAITS.contains(&path.as_str())
+        }
+        Some("crate" | "self" | "super") => {
+            workspace_items.others.contains(name) && !workspace_items.traits.contains(name)
+        }
+        _ => false,
+    }
+}
+
+fn flatten(tree: &syn::UseTree, prefix: &mut Vec<String>, out: &mut Vec<Import>) {
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            flatten(&path.tree, prefix, out);
+            prefix.pop();
+        }
+        syn::UseTree::Name(name) => {
+            let binds = match name.ident == "self" {
+                true => prefix.last().cloned(),
+                false => Some(name.ident.to_string()),
+            };
+            out.push(Import { parent: prefix.clone(), leaf: name.ident.to_string(), binds });
+        }
+        syn::UseTree::Rename(rename) => {
+            let binds = (rename.rename != "_").then(|| rename.rename.to_string());
+            out.push(Import { parent: prefix.clone(), leaf: format!("{} as {}", rename.ident, rename.rename), binds });
+        }
+        syn::UseTree::Glob(_) => out.push(Import { parent: prefix.clone(), leaf: "*".to_string(), binds: None }),
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                flatten(tree, prefix, out);
+            }
+        }
+    }
+}
+
+/// Every identifier in the tokens, including those inside macro invocations.
+fn collect_idents(tokens: TokenStream, out: &mut HashSet<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                out.insert(ident.to_string());
+            }
+            TokenTree::Group(group) => collect_idents(group.stream(), out),
+            _ => {}
+        }
+    }
+}
+
+/// `std`/`core`/`alloc` first, then external crates, then `crate`/`self`/`super`.
+fn import_group(parent: &[String], leaf: &str) -> u8 {
+    let root = parent.iter().find(|segment| !segment.is_empty()).map(String::as_str).unwrap_or(leaf);
+    match root {
+        "std" | "core" | "alloc" => 0,
+        "crate" | "self" | "super" => 2,
+        _ => 1,
+    }
+}
+
+/// One `use` per module, with the names imported from it merged into a group, sorted within
+/// each of the three import groups, which are separated by blank lines.
+fn render(imports: Vec<Import>) -> String {
+    let mut modules: BTreeMap<Vec<String>, BTreeSet<String>> = BTreeMap::new();
+    for import in imports {
+        modules.entry(import.parent).or_default().insert(import.leaf);
+    }
+    // `use std::io;` next to `use std::io::Read;` becomes `use std::io::{self, Read};`
+    let merged: Vec<(Vec<String>, String)> = modules
+        .iter()
+        .flat_map(|(parent, leaves)| leaves.iter().map(move |leaf| (parent, leaf)))
+        .filter_map(|(parent, leaf)| {
+            let mut module = parent.clone();
+            module.push(leaf.clone());
+            modules.contains_key(&module).then_some((parent.clone(), leaf.clone()))
+        })
+        .collect();
+    for (parent, leaf) in merged {
+        if let Some(leaves) = modules.get_mut(&parent) {
+            leaves.remove(&leaf);
+        }
+        let mut module = parent;
+        module.push(leaf);
+        modules.entry(module).or_default().insert("self".to_string());
+    }
+
+    let mut groups: BTreeMap<u8, Vec<String>> = BTreeMap::new();
+    for (parent, leaves) in modules {
+        let mut leaves: Vec<String> = leaves.into_iter().collect();
+        leaves.sort_by_key(|leaf| (leaf != "self", leaf == "*", leaf.clone()));
+        if parent.is_empty() {
+            for leaf in leaves {
+                groups.entry(import_group(&parent, &leaf)).or_default().push(format!("use {};", leaf));
+            }
+            continue;
+        }
+        let path = parent.join("::");
+        let line = match leaves.as_slice() {
+            [] => continue,
+            [leaf] if leaf == "self" => format!("use {};", path),
+            [leaf] => format!("use {}::{};", path, leaf),
+            _ => format!("use {}::{{{}}};", path, leaves.join(", ")),
+        };
+        groups.entry(import_group(&parent, "")).or_default().push(line);
+    }
+
+    groups
+        .into_values()
+        .map(|mut lines| {
+            lines.sort();
+            lines.into_iter().map(|line| line + "\n").collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
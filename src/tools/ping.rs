@@ -0,0 +1,23 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+};
+use crate::models::*;
+use crate::cache::*;
+
+pub async fn ping(server: &MyServer) -> Result<CallToolResult, McpError> {
+    let Implementation { name, version, .. } = server_implementation();
+    let mut tools: Vec<String> = server.tool_router.list_all().into_iter().map(|tool| tool.name.to_string()).collect();
+    tools.sort();
+    let response = PingResponse {
+        name,
+        version,
+        uptime_seconds: server.started_at.elapsed().as_secs(),
+        cached_files: server.cache.len().await,
+        tools,
+    };
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&response).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
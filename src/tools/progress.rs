@@ -0,0 +1,34 @@
+use rmcp::{
+    model::*,
+    Peer,
+    RoleServer,
+};
+
+/// Sends MCP progress notifications for a request that carried a progress token.
+pub struct ProgressReporter {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+}
+
+impl ProgressReporter {
+    /// Returns `None` when the client didn't ask for progress.
+    pub fn new(meta: &Meta, peer: Peer<RoleServer>) -> Option<Self> {
+        meta.get_progress_token().map(|token| Self { peer, token })
+    }
+
+    /// Reports `processed` out of `total` items, throttled to roughly one notification per percent.
+    pub async fn report(&self, processed: usize, total: usize) {
+        let step = (total / 100).max(1);
+        if !processed.is_multiple_of(step) && processed != total {
+            return;
+        }
+
+        // Progress is best-effort; a client that stopped listening shouldn't fail the tool call
+        let _ = self.peer.notify_progress(ProgressNotificationParam {
+            progress_token: self.token.clone(),
+            progress: processed as f64,
+            total: Some(total as f64),
+            message: None,
+        }).await;
+    }
+}
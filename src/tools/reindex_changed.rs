@@ -0,0 +1,93 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use crate::models::*;
+use crate::cache::*;
+use crate::analysis::analyze_source_with_options;
+use super::index_workspace::{build_graphs, oversized_reason, retain_kinds, skipped_file_list};
+use std::path::Path;
+
+pub async fn reindex_changed(
+    server: &MyServer,
+    Parameters(ReindexChangedParams { root, git_ref }): Parameters<ReindexChangedParams>,
+) -> Result<CallToolResult, McpError> {
+    let git_ref = git_ref.unwrap_or_else(|| "HEAD".to_string());
+    // Git would read a leading `-` as an option, such as `--output=<file>`
+    if git_ref.starts_with('-') {
+        return Err(McpError::invalid_params("git_ref must not start with '-'", Some(json!({ "git_ref": git_ref }))));
+    }
+    let root = Path::new(&root);
+
+    // `--relative` limits the diff to `root` and reports paths relative to it; new files
+    // aren't in the diff until they're tracked, so untracked ones are listed separately
+    let mut changed = git_lines(root, &["diff", "--name-only", "--relative", &git_ref, "--"]).await?;
+    changed.extend(git_lines(root, &["ls-files", "--others", "--exclude-standard"]).await?);
+    changed.sort();
+    changed.dedup();
+
+    let mut workspace_index = server.workspace_index.write().await;
+    let Some(index) = workspace_index.as_mut() else {
+        return Err(McpError::invalid_params("No workspace has been indexed yet; call index_workspace first", None));
+    };
+
+    for relative in &changed {
+        // Only files the original index covered, keyed the way it keyed them
+        let Some(path) = index.file_filter.key(&root.join(relative)) else { continue };
+        match tokio::fs::read_to_string(&path).await {
+            Ok(code) => {
+                index.skipped_files.remove(&path);
+                if let Some(reason) = oversized_reason(code.len() as u64, index.max_file_bytes) {
+                    index.analyses.remove(&path);
+                    index.skipped_files.insert(path, reason);
+                    continue;
+                }
+                match analyze_source_with_options(&path, &code, index.options) {
+                    Ok(analysis) => index.analyses.insert(path.clone(), analysis),
+                    Err(_) => index.analyses.remove(&path),
+                };
+                server.cache.insert(path, code).await;
+            }
+            // Deleted (or unreadable) files drop out of both the cache and the graphs
+            Err(_) => {
+                index.analyses.remove(&path);
+                index.skipped_files.remove(&path);
+                server.cache.remove(&path).await;
+            }
+        }
+    }
+
+    let mut graphs = build_graphs(&index.analyses, &index.thresholds);
+    graphs.skipped_files = skipped_file_list(&index.skipped_files);
+    if let Some(kinds) = &index.kinds {
+        retain_kinds(&mut graphs, kinds);
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&graphs).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// Runs git in `dir` and returns its non-empty output lines.
+async fn git_lines(dir: &Path, args: &[&str]) -> Result<Vec<String>, McpError> {
+    let output = tokio::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .map_err(|e| McpError::internal_error("Failed to run git", Some(json!({ "error": e.to_string() }))))?;
+    if !output.status.success() {
+        return Err(McpError::invalid_params(
+            "git command failed",
+            Some(json!({ "args": args, "stderr": String::from_utf8_lossy(&output.stderr) })),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
@@ -0,0 +1,44 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use regex::Regex;
+use serde_json::json;
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+/// Matches returned when the caller doesn't set `max_results`.
+const DEFAULT_MAX_RESULTS: usize = 200;
+
+pub async fn search_symbols(
+    server: &MyServer,
+    Parameters(SearchSymbolsParams { pattern, max_results, position_options }): Parameters<SearchSymbolsParams>,
+) -> Result<CallToolResult, McpError> {
+    let regex = Regex::new(&pattern).map_err(|e| {
+        McpError::invalid_params(
+            "Invalid regex pattern",
+            Some(json!({ "pattern": pattern, "error": e.to_string() })),
+        )
+    })?;
+
+    let mut results = Vec::new();
+    let code_map = server.cache.get_all().await;
+    for (path, code) in code_map.iter() {
+        if let Ok(ast) = syn::parse_file(code) {
+            let line_index = position_options.line_index(code);
+            let mut collector = SymbolCollector::new(path.clone(), line_index.as_ref());
+            collector.visit_file(&ast);
+            results.extend(collector.out.into_iter().filter(|sym| regex.is_match(&sym.name)));
+        }
+    }
+
+    results.sort_by(|a, b| (&a.name, &a.file, &a.range.start).cmp(&(&b.name, &b.file, &b.range.start)));
+    results.truncate(max_results.unwrap_or(DEFAULT_MAX_RESULTS));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&results).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
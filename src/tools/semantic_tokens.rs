@@ -0,0 +1,41 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use serde_json::json;
+use syn::visit::Visit;
+use crate::models::*;
+use crate::cache::*;
+use crate::visitors::*;
+
+pub async fn semantic_tokens(
+    server: &MyServer,
+    Parameters(SemanticTokensParams { path, position_options }): Parameters<SemanticTokensParams>,
+) -> Result<CallToolResult, McpError> {
+    let code = match server.cache.get(&path).await {
+        Some(code) => code,
+        None => tokio::fs::read_to_string(&path).await
+            .map_err(|e| McpError::invalid_params("Failed to read file", Some(json!({ "error": e.to_string() }))))?,
+    };
+    let ast = syn::parse_file(&code)
+        .map_err(|e| McpError::invalid_params("Failed to parse file", Some(json!({ "error": e.to_string() }))))?;
+
+    // The file's own declarations tell `Foo::new()` on a struct apart from one on an enum
+    let mut symbols = SymbolCollector::new(path.clone(), None);
+    symbols.visit_file(&ast);
+    let definitions = symbols.out.into_iter().map(|sym| (sym.name, sym.kind)).collect();
+
+    let line_index = position_options.line_index(&code);
+    let mut collector = SemanticTokenCollector::new(line_index.as_ref(), definitions);
+    collector.visit_file(&ast);
+
+    // Contexts are visited outside-in, so the first classification of a token is the most specific
+    let mut tokens = collector.out;
+    tokens.sort_by(|a, b| a.range.start.cmp(&b.range.start));
+    tokens.dedup_by(|later, earlier| later.range.start == earlier.range.start);
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&tokens).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
@@ -0,0 +1,273 @@
+use rmcp::{
+    model::*,
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+};
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use std::collections::{HashMap, HashSet};
+use crate::models::*;
+use crate::cache::*;
+use crate::line_index::{span_to_range, LineIndex};
+use crate::visitors::{derived_traits, ident_name};
+
+/// The traits suggested, in the order they're reported.
+const CANDIDATES: [&str; 4] = ["Debug", "Clone", "PartialEq", "Default"];
+
+/// Std types that don't implement some of the candidates whatever their type arguments.
+const NOT_IMPLEMENTED: &[(&str, &[&str])] = &[
+    ("Mutex", &["Clone", "PartialEq"]),
+    ("RwLock", &["Clone", "PartialEq"]),
+    ("Condvar", &["Clone", "PartialEq"]),
+    ("Barrier", &["Clone", "PartialEq", "Default"]),
+    ("File", &["Clone", "PartialEq", "Default"]),
+    ("TcpStream", &["Clone", "PartialEq", "Default"]),
+    ("TcpListener", &["Clone", "PartialEq", "Default"]),
+    ("UdpSocket", &["Clone", "PartialEq", "Default"]),
+    ("JoinHandle", &["Clone", "PartialEq", "Default"]),
+    ("Child", &["Clone", "PartialEq", "Default"]),
+    ("Instant", &["Default"]),
+    ("SystemTime", &["Default"]),
+];
+
+/// Std types that implement some of the candidates whatever their type arguments.
+const IMPLEMENTED_FOR_ANY: &[(&str, &[&str])] = &[
+    ("PhantomData", &["Debug", "Clone", "PartialEq", "Default"]),
+    ("Arc", &["Clone"]),
+    ("Rc", &["Clone"]),
+    ("Option", &["Default"]),
+    ("Vec", &["Default"]),
+    ("VecDeque", &["Default"]),
+    ("HashMap", &["Default"]),
+    ("HashSet", &["Default"]),
+    ("BTreeMap", &["Default"]),
+    ("BTreeSet", &["Default"]),
+];
+
+pub async fn suggest_derives(
+    server: &MyServer,
+    Parameters(SuggestDerivesParams { name, position_options }): Parameters<SuggestDerivesParams>,
+) -> Result<CallToolResult, McpError> {
+    let code_map = server.cache.get_all().await;
+    let files: Vec<(&String, &String, syn::File)> = code_map
+        .iter()
+        .filter_map(|(path, code)| syn::parse_file(code).ok().map(|ast| (path, code, ast)))
+        .collect();
+
+    // A field whose type is declared in the workspace needs that type to support the trait too
+    let mut implemented = ImplementedTraits::default();
+    for (_, _, ast) in &files {
+        implemented.visit_file(ast);
+    }
+
+    let mut suggestions = Vec::new();
+    for (path, code, ast) in &files {
+        let line_index = position_options.line_index(code);
+        let mut collector = DeriveSuggestionCollector {
+            file: path.to_string(),
+            line_index: line_index.as_ref(),
+            source: LineIndex::new(code, PositionOptions::default()),
+            implemented: &implemented.by_type,
+            out: Vec::new(),
+        };
+        collector.visit_file(ast);
+        suggestions.extend(collector.out);
+    }
+
+    if let Some(name) = name {
+        suggestions.retain(|suggestion| suggestion.name == name);
+    }
+    suggestions.sort_by(|a, b| (&a.file, &a.range.start).cmp(&(&b.file, &b.range.start)));
+
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string(&suggestions).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    )]))
+}
+
+/// Traits each workspace type derives or implements by hand, by the last segment of their names.
+#[derive(Default)]
+struct ImplementedTraits {
+    by_type: HashMap<String, HashSet<String>>,
+}
+
+impl ImplementedTraits {
+    fn add(&mut self, ident: &syn::Ident, attrs: &[syn::Attribute]) {
+        let traits = derived_traits(attrs).into_iter().map(|path| last_segment(&path).to_string());
+        self.by_type.entry(ident_name(ident)).or_default().extend(traits);
+    }
+}
+
+impl<'ast> Visit<'ast> for ImplementedTraits {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.add(&i.ident, &i.attrs);
+        syn::visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        self.add(&i.ident, &i.attrs);
+        syn::visit::visit_item_enum(self, i);
+    }
+
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        self.add(&i.ident, &i.attrs);
+        syn::visit::visit_item_union(self, i);
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        if let (Some((_, trait_path, _)), syn::Type::Path(self_ty)) = (&i.trait_, &*i.self_ty) {
+            if let (Some(trait_seg), Some(type_seg)) = (trait_path.segments.last(), self_ty.path.segments.last()) {
+                self.by_type.entry(ident_name(&type_seg.ident)).or_default().insert(ident_name(&trait_seg.ident));
+            }
+        }
+        syn::visit::visit_item_impl(self, i);
+    }
+}
+
+struct DeriveSuggestionCollector<'a> {
+    file: String,
+    line_index: Option<&'a LineIndex<'a>>,
+    /// Always built, to quote field types in reasons.
+    source: LineIndex<'a>,
+    implemented: &'a HashMap<String, HashSet<String>>,
+    out: Vec<DeriveSuggestion>,
+}
+
+impl DeriveSuggestionCollector<'_> {
+    /// `fields` pairs a description of each field with its type. `default_variant` is `None`
+    /// for structs and, for enums, the variant marked `#[default]` if any.
+    fn check(&mut self, kind: SymbolKind, ident: &syn::Ident, attrs: &[syn::Attribute], fields: Vec<(String, &syn::Type)>, default_variant: Option<Option<String>>) {
+        let derived: HashSet<String> = derived_traits(attrs).iter().map(|path| last_segment(path).to_string()).collect();
+        let name = ident_name(ident);
+        let (mut derivable, mut blocked) = (Vec::new(), Vec::new());
+
+        for trait_name in CANDIDATES {
+            let implemented_by_hand = self.implemented.get(&name).is_some_and(|traits| traits.contains(trait_name));
+            if derived.contains(trait_name) || implemented_by_hand {
+                continue;
+            }
+            let result = match (&default_variant, trait_name) {
+                (Some(Some(variant)), "Default") => Ok(format!("variant `{}` is marked `#[default]`", variant)),
+                (Some(None), "Default") => Err("an enum needs a unit variant marked `#[default]`".to_string()),
+                _ => self.check_fields(&name, &fields, trait_name),
+            };
+            let trait_name = trait_name.to_string();
+            match result {
+                Ok(reason) => derivable.push(DeriveReason { trait_name, reason }),
+                Err(reason) => blocked.push(DeriveReason { trait_name, reason }),
+            }
+        }
+
+        if !derivable.is_empty() || !blocked.is_empty() {
+            self.out.push(DeriveSuggestion {
+                name,
+                kind,
+                derivable,
+                blocked,
+                file: self.file.clone(),
+                range: span_to_range(ident.span(), self.line_index),
+            });
+        }
+    }
+
+    fn check_fields(&self, this_type: &str, fields: &[(String, &syn::Type)], trait_name: &str) -> Result<String, String> {
+        for (field, ty) in fields {
+            if let Some(problem) = blocker(ty, trait_name, this_type, self.implemented) {
+                let text = self.source.text(ty.span().start(), ty.span().end());
+                return Err(format!("{} of type `{}` {}", field, text, problem));
+            }
+        }
+        Ok(match fields.len() {
+            0 => "has no fields".to_string(),
+            _ => format!("every field type implements {}", trait_name),
+        })
+    }
+}
+
+impl<'ast> Visit<'ast> for DeriveSuggestionCollector<'_> {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        let fields = i.fields.iter().enumerate().map(|(n, field)| (field_label(None, n, field), &field.ty)).collect();
+        self.check(SymbolKind::Struct, &i.ident, &i.attrs, fields, None);
+        syn::visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        let fields = i.variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter().enumerate().map(move |(n, field)| (field_label(Some(&variant.ident), n, field), &field.ty)))
+            .collect();
+        let default_variant = i.variants
+            .iter()
+            .find(|variant| variant.attrs.iter().any(|attr| attr.path().is_ident("default")))
+            .map(|variant| ident_name(&variant.ident));
+        self.check(SymbolKind::Enum, &i.ident, &i.attrs, fields, Some(default_variant));
+        syn::visit::visit_item_enum(self, i);
+    }
+}
+
+/// "field `name`", "field `0`", or "variant `Circle` field `radius`".
+fn field_label(variant: Option<&syn::Ident>, index: usize, field: &syn::Field) -> String {
+    let field_name = field.ident.as_ref().map(ident_name).unwrap_or_else(|| index.to_string());
+    match variant {
+        Some(variant) => format!("variant `{}` field `{}`", ident_name(variant), field_name),
+        None => format!("field `{}`", field_name),
+    }
+}
+
+fn last_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Why a field of type `ty` keeps `trait_name` from being derived for `this_type`, if it does.
+/// Generic type parameters, unknown types, and `this_type` itself are assumed to be fine.
+fn blocker(ty: &syn::Type, trait_name: &str, this_type: &str, implemented: &HashMap<String, HashSet<String>>) -> Option<String> {
+    match ty {
+        syn::Type::Reference(reference) => match trait_name {
+            "Default" => Some("is a reference, which has no default".to_string()),
+            "Clone" if reference.mutability.is_some() => Some("is a mutable reference, which can't be cloned".to_string()),
+            "Clone" => None,
+            _ => blocker(&reference.elem, trait_name, this_type, implemented),
+        },
+        syn::Type::TraitObject(object) => {
+            let bounds: Vec<String> = object.bounds
+                .iter()
+                .filter_map(|bound| match bound {
+                    syn::TypeParamBound::Trait(bound) => bound.path.segments.last().map(|seg| ident_name(&seg.ident)),
+                    _ => None,
+                })
+                .collect();
+            // `dyn Error` is Debug since Error requires it
+            let bounded = bounds.iter().any(|bound| bound == trait_name || (trait_name == "Debug" && bound == "Error"));
+            (!bounded).then(|| format!("holds a trait object, which doesn't implement {}", trait_name))
+        }
+        syn::Type::Ptr(_) | syn::Type::BareFn(_) if trait_name == "Default" => {
+            Some("is a pointer, which has no default".to_string())
+        }
+        syn::Type::Tuple(tuple) => tuple.elems.iter().find_map(|elem| blocker(elem, trait_name, this_type, implemented)),
+        syn::Type::Array(array) => blocker(&array.elem, trait_name, this_type, implemented),
+        syn::Type::Slice(slice) => blocker(&slice.elem, trait_name, this_type, implemented),
+        syn::Type::Paren(paren) => blocker(&paren.elem, trait_name, this_type, implemented),
+        syn::Type::Group(group) => blocker(&group.elem, trait_name, this_type, implemented),
+        syn::Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+            let name = ident_name(&segment.ident);
+            let listed = |table: &[(&str, &[&str])]| table.iter().any(|(ty, traits)| *ty == name && traits.contains(&trait_name));
+            if listed(NOT_IMPLEMENTED) || (name.starts_with("Atomic") && matches!(trait_name, "Clone" | "PartialEq")) {
+                return Some(format!("holds `{}`, which doesn't implement {}", name, trait_name));
+            }
+            if name != this_type && implemented.get(&name).is_some_and(|traits| !traits.contains(trait_name)) {
+                return Some(format!("holds `{}`, which doesn't derive or implement {}", name, trait_name));
+            }
+            if listed(IMPLEMENTED_FOR_ANY) {
+                return None;
+            }
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => blocker(ty, trait_name, this_type, implemented),
+                    _ => None,
+                }),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
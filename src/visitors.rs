@@ -1,116 +1,300 @@
 use syn::visit::Visit;
 use crate::models::*;
-use std::collections::HashMap;
+use crate::line_index::{span_to_range, LineIndex};
+use std::collections::{HashMap, HashSet};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use quote::ToTokens;
+use proc_macro2::Span;
+use once_cell::sync::Lazy;
+use regex::Regex;
 
-pub struct SymbolCollector {
+/// Identifier text without the `r#` prefix, so `r#type` and `type` compare equal.
+pub fn ident_name(ident: &syn::Ident) -> String {
+    ident.unraw().to_string()
+}
+
+/// Strips the `r#` prefix from a user-supplied name.
+pub fn unraw_name(name: &str) -> &str {
+    name.strip_prefix("r#").unwrap_or(name)
+}
+
+/// A visitor that keeps state for the function it's in. `visit_fn` gives each function,
+/// method and nested function fresh `FnState`, visits it, then hands its state to
+/// `finish_fn` and restores the enclosing function's.
+pub trait PerFunction: Sized {
+    type FnState;
+
+    fn fn_state(&mut self) -> &mut Self::FnState;
+
+    /// The state a function with signature `sig` starts with.
+    fn start_fn(&mut self, sig: &syn::Signature) -> Self::FnState;
+
+    /// Called with a function's state once it has been visited.
+    fn finish_fn(&mut self, _sig: &syn::Signature, _body: Option<&syn::Block>, _state: Self::FnState) {}
+
+    fn visit_fn(&mut self, sig: &syn::Signature, body: Option<&syn::Block>, visit: impl FnOnce(&mut Self)) {
+        let state = self.start_fn(sig);
+        let outer = std::mem::replace(self.fn_state(), state);
+        visit(self);
+        let state = std::mem::replace(self.fn_state(), outer);
+        self.finish_fn(sig, body, state);
+    }
+}
+
+pub struct SymbolCollector<'a> {
     pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    /// When set, each symbol's declaration text (without attributes or body) is recorded too.
+    pub signature_source: Option<&'a LineIndex<'a>>,
+    /// Impl type, trait, or inline module enclosing the current item.
+    pub container: Option<String>,
     pub out: Vec<SymbolInfo>,
 }
 
-impl<'ast> Visit<'ast> for SymbolCollector {
-    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
-        let span = i.sig.ident.span();
-        let start = span.start();
-        let end = span.end();
-        
+impl<'a> SymbolCollector<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>) -> Self {
+        Self { file, line_index, signature_source: None, container: None, out: Vec::new() }
+    }
+
+    /// Records each symbol's declaration text, read from `source`.
+    pub fn with_signatures(mut self, source: &'a LineIndex<'a>) -> Self {
+        self.signature_source = Some(source);
+        self
+    }
+}
+
+impl SymbolCollector<'_> {
+    fn push(
+        &mut self,
+        kind: SymbolKind,
+        item: &impl Spanned,
+        ident: &syn::Ident,
+        vis: &syn::Visibility,
+        attrs: &[syn::Attribute],
+        signature: Option<String>,
+    ) {
         self.out.push(SymbolInfo {
-            kind: "fn".to_string(),
-            name: i.sig.ident.to_string(),
+            kind,
+            name: ident_name(ident),
             file: self.file.clone(),
-            range: Range {
-                start: Position { line: start.line, character: start.column },
-                end: Position { line: end.line, character: end.column },
-            },
+            range: span_to_range(ident.span(), self.line_index),
+            full_range: span_to_range(item.span(), self.line_index),
+            docs: doc_comment(attrs),
+            signature,
+            visibility: visibility_label(vis).to_string(),
+            container: self.container.clone(),
         });
+    }
+
+    /// Source text from the end of the item's attributes up to `body_start` (or the item's end).
+    fn signature(&self, item: &impl Spanned, attrs: &[syn::Attribute], body_start: Option<Span>) -> Option<String> {
+        let source = self.signature_source?;
+        let start = attrs.last().map(|attr| attr.span().end()).unwrap_or_else(|| item.span().start());
+        let end = body_start.map(|span| span.start()).unwrap_or_else(|| item.span().end());
+        let text = source.text(start, end).trim();
+        Some(text.strip_suffix(';').unwrap_or(text).trim_end().to_string())
+    }
+}
+
+impl<'ast> Visit<'ast> for SymbolCollector<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        let signature = self.signature(i, &i.attrs, Some(i.block.brace_token.span.open()));
+        self.push(SymbolKind::Fn, i, &i.sig.ident, &i.vis, &i.attrs, signature);
         syn::visit::visit_item_fn(self, i);
     }
 
     fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
-        let span = i.ident.span();
-        let start = span.start();
-        let end = span.end();
-
-        self.out.push(SymbolInfo {
-            kind: "struct".to_string(),
-            name: i.ident.to_string(),
-            file: self.file.clone(),
-            range: Range {
-                start: Position { line: start.line, character: start.column },
-                end: Position { line: end.line, character: end.column },
-            },
-        });
+        let body_start = match &i.fields {
+            syn::Fields::Named(fields) => Some(fields.brace_token.span.open()),
+            _ => None,
+        };
+        let signature = self.signature(i, &i.attrs, body_start);
+        self.push(SymbolKind::Struct, i, &i.ident, &i.vis, &i.attrs, signature);
         syn::visit::visit_item_struct(self, i);
     }
 
     fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
-        let span = i.ident.span();
-        let start = span.start();
-        let end = span.end();
-
-        self.out.push(SymbolInfo {
-            kind: "enum".to_string(),
-            name: i.ident.to_string(),
-            file: self.file.clone(),
-            range: Range {
-                start: Position { line: start.line, character: start.column },
-                end: Position { line: end.line, character: end.column },
-            },
-        });
+        let signature = self.signature(i, &i.attrs, Some(i.brace_token.span.open()));
+        self.push(SymbolKind::Enum, i, &i.ident, &i.vis, &i.attrs, signature);
         syn::visit::visit_item_enum(self, i);
     }
 
     fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
-        let span = i.ident.span();
-        let start = span.start();
-        let end = span.end();
-
-        self.out.push(SymbolInfo {
-            kind: "trait".to_string(),
-            name: i.ident.to_string(),
-            file: self.file.clone(),
-            range: Range {
-                start: Position { line: start.line, character: start.column },
-                end: Position { line: end.line, character: end.column },
-            },
-        });
+        let signature = self.signature(i, &i.attrs, Some(i.brace_token.span.open()));
+        self.push(SymbolKind::Trait, i, &i.ident, &i.vis, &i.attrs, signature);
+        let outer_container = self.container.replace(ident_name(&i.ident));
+        // Trait methods are as visible as the trait itself
+        for item in &i.items {
+            if let syn::TraitItem::Fn(method) = item {
+                let body_start = method.default.as_ref().map(|block| block.brace_token.span.open());
+                let signature = self.signature(method, &method.attrs, body_start);
+                self.push(SymbolKind::Method, method, &method.sig.ident, &i.vis, &method.attrs, signature);
+            }
+        }
         syn::visit::visit_item_trait(self, i);
+        self.container = outer_container;
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let impl_type = match &*i.self_ty {
+            syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| ident_name(&seg.ident)),
+            _ => None,
+        };
+        let outer_container = self.container.clone();
+        if impl_type.is_some() {
+            self.container = impl_type;
+        }
+        // Trait impl methods are named and documented by their trait, so only inherent methods count
+        if i.trait_.is_none() {
+            for item in &i.items {
+                if let syn::ImplItem::Fn(method) = item {
+                    let signature = self.signature(method, &method.attrs, Some(method.block.brace_token.span.open()));
+                    self.push(SymbolKind::Method, method, &method.sig.ident, &method.vis, &method.attrs, signature);
+                }
+            }
+        }
+        syn::visit::visit_item_impl(self, i);
+        self.container = outer_container;
+    }
+
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        let signature = self.signature(i, &i.attrs, Some(i.fields.brace_token.span.open()));
+        self.push(SymbolKind::Union, i, &i.ident, &i.vis, &i.attrs, signature);
+        syn::visit::visit_item_union(self, i);
+    }
+
+    fn visit_item_type(&mut self, i: &'ast syn::ItemType) {
+        let signature = self.signature(i, &i.attrs, None);
+        self.push(SymbolKind::TypeAlias, i, &i.ident, &i.vis, &i.attrs, signature);
+        syn::visit::visit_item_type(self, i);
+    }
+
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
+        let signature = self.signature(i, &i.attrs, Some(i.eq_token.span));
+        self.push(SymbolKind::Const, i, &i.ident, &i.vis, &i.attrs, signature);
+        syn::visit::visit_item_const(self, i);
+    }
+
+    fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
+        let signature = self.signature(i, &i.attrs, Some(i.eq_token.span));
+        self.push(SymbolKind::Static, i, &i.ident, &i.vis, &i.attrs, signature);
+        syn::visit::visit_item_static(self, i);
+    }
+
+    fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+        let body_start = i.content.as_ref().map(|(brace, _)| brace.span.open());
+        let signature = self.signature(i, &i.attrs, body_start);
+        self.push(SymbolKind::Module, i, &i.ident, &i.vis, &i.attrs, signature);
+        let outer_container = self.container.replace(ident_name(&i.ident));
+        syn::visit::visit_item_mod(self, i);
+        self.container = outer_container;
     }
 }
 
-pub struct ReferenceFinder {
+/// `"public"` for `pub` items, `"private"` for everything else (including `pub(crate)`).
+pub fn visibility_label(vis: &syn::Visibility) -> &'static str {
+    if matches!(vis, syn::Visibility::Public(_)) {
+        "public"
+    } else {
+        "private"
+    }
+}
+
+/// Joins `///` comments and `#[doc = "..."]` attributes into one markdown string.
+pub fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(doc), .. }),
+                ..
+            }) => Some(doc.value()),
+            _ => None,
+        })
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    // Like rustdoc, drop the single space that follows `///`
+    let docs = lines
+        .iter()
+        .flat_map(|line| line.split('\n'))
+        // Block doc comments keep the `\r` of CRLF files
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(docs.trim().to_string())
+}
+
+/// Finds the identifier under a cursor, given as a byte offset. A cursor just past the end of
+/// an identifier still counts, like in editors. Identifiers inside macro invocations are found
+/// by scanning their tokens, since syn leaves macro bodies unparsed.
+pub struct IdentAtOffset<'a> {
+    pub line_index: &'a LineIndex<'a>,
+    pub offset: usize,
+    pub found: Option<syn::Ident>,
+}
+
+impl IdentAtOffset<'_> {
+    fn check(&mut self, ident: &syn::Ident) {
+        let span = ident.span();
+        let (start, end) = (self.line_index.byte_offset(span.start()), self.line_index.byte_offset(span.end()));
+        if self.found.is_none() && start <= self.offset && self.offset <= end {
+            self.found = Some(ident.clone());
+        }
+    }
+
+    fn check_tokens(&mut self, tokens: proc_macro2::TokenStream) {
+        for token in tokens {
+            match token {
+                proc_macro2::TokenTree::Ident(ident) => self.check(&ident),
+                proc_macro2::TokenTree::Group(group) => self.check_tokens(group.stream()),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for IdentAtOffset<'_> {
+    fn visit_ident(&mut self, i: &'ast syn::Ident) {
+        self.check(i);
+    }
+
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        syn::visit::visit_macro(self, i);
+        self.check_tokens(i.tokens.clone());
+    }
+}
+
+pub struct ReferenceFinder<'a> {
     pub target_name: String,
     pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
     pub matches: Vec<ReferenceLocation>,
 }
 
-impl<'ast> Visit<'ast> for ReferenceFinder {
+impl<'ast> Visit<'ast> for ReferenceFinder<'_> {
     fn visit_ident(&mut self, i: &'ast syn::Ident) {
-        if i == &self.target_name {
+        if ident_name(i) == unraw_name(&self.target_name) {
             let span = i.span();
-            let start = span.start();
-            let end = span.end();
             self.matches.push(ReferenceLocation {
                 file: self.file.clone(),
-                range: Range {
-                    start: Position { line: start.line, character: start.column },
-                    end: Position { line: end.line, character: end.column },
-                },
+                range: span_to_range(span, self.line_index),
             });
         }
     }
     
     fn visit_type_path(&mut self, i: &'ast syn::TypePath) {
         if let Some(seg) = i.path.segments.last() {
-            if seg.ident.to_string() == self.target_name {
+            if ident_name(&seg.ident) == unraw_name(&self.target_name) {
                 let span = seg.ident.span();
-                let start = span.start();
-                let end = span.end();
                 self.matches.push(ReferenceLocation {
                     file: self.file.clone(),
-                    range: Range {
-                        start: Position { line: start.line, character: start.column },
-                        end: Position { line: end.line, character: end.column },
-                    },
+                    range: span_to_range(span, self.line_index),
                 });
             }
         }
@@ -118,224 +302,750 @@ impl<'ast> Visit<'ast> for ReferenceFinder {
     }
 }
 
-pub struct CallGraphCollector {
+pub struct PanicFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub sites: Vec<PanicSite>,
+}
+
+impl<'ast> Visit<'ast> for PanicFinder<'_> {
+    fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
+        let kind = match ident_name(&i.method).as_str() {
+            "unwrap" => Some(PanicKind::Unwrap),
+            "expect" => Some(PanicKind::Expect),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            self.sites.push(PanicSite {
+                kind,
+                file: self.file.clone(),
+                range: span_to_range(i.method.span(), self.line_index),
+            });
+        }
+        syn::visit::visit_expr_method_call(self, i);
+    }
+
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        if let Some(seg) = i.path.segments.last() {
+            let kind = match ident_name(&seg.ident).as_str() {
+                "panic" => Some(PanicKind::Panic),
+                "unreachable" => Some(PanicKind::Unreachable),
+                "todo" => Some(PanicKind::Todo),
+                "unimplemented" => Some(PanicKind::Unimplemented),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                self.sites.push(PanicSite {
+                    kind,
+                    file: self.file.clone(),
+                    range: span_to_range(seg.ident.span(), self.line_index),
+                });
+            }
+        }
+        syn::visit::visit_macro(self, i);
+    }
+}
+
+/// Collects functions whose body has no statements or only a `todo!()`/`unimplemented!()`,
+/// which usually mark stubs. Trait methods without a default body aren't functions here.
+pub struct EmptyFunctionCollector<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<EmptyFunction>,
+}
+
+impl EmptyFunctionCollector<'_> {
+    fn check(&mut self, ident: &syn::Ident, block: &syn::Block) {
+        let kind = match block.stmts.as_slice() {
+            [] => EmptyFunctionKind::Empty,
+            [syn::Stmt::Macro(syn::StmtMacro { mac, .. })]
+            | [syn::Stmt::Expr(syn::Expr::Macro(syn::ExprMacro { mac, .. }), _)]
+                if mac.path.segments.last().is_some_and(|seg| seg.ident == "todo" || seg.ident == "unimplemented") =>
+            {
+                EmptyFunctionKind::Placeholder
+            }
+            _ => return,
+        };
+        self.out.push(EmptyFunction {
+            name: ident_name(ident),
+            kind,
+            file: self.file.clone(),
+            range: span_to_range(ident.span(), self.line_index),
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for EmptyFunctionCollector<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.check(&i.sig.ident, &i.block);
+        syn::visit::visit_item_fn(self, i);
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.check(&i.sig.ident, &i.block);
+        syn::visit::visit_impl_item_fn(self, i);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        if let Some(block) = &i.default {
+            self.check(&i.sig.ident, block);
+        }
+        syn::visit::visit_trait_item_fn(self, i);
+    }
+}
+
+pub struct CallGraphCollector<'a> {
     pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
     pub current_function: Option<String>,
-    pub calls: HashMap<String, Vec<String>>,
-    pub function_info: HashMap<String, crate::models::FunctionInfo>,
+    /// Inline `mod` names enclosing the current item, used to qualify caller keys.
+    pub module_path: Vec<String>,
+    /// Self type of the enclosing `impl` block, used to resolve `self.method()` and `Self::f()`.
+    pub current_impl: Option<String>,
+    /// Control-flow depth at the current point of the walk, relative to the function body.
+    pub nesting_depth: usize,
+    /// Deepest `nesting_depth` reached in the function currently being walked.
+    pub max_nesting_depth: usize,
+    pub calls: HashMap<String, Vec<String>>, // qualified caller -> callee paths as written
+    pub function_info: HashMap<String, crate::models::FunctionInfo>, // keyed by qualified name
+    /// Every call in `calls`, with the location of the callee name at the call site.
+    pub call_sites: Vec<CallSite>,
+    /// Qualified caller -> callee path of the call its body ends in, for divergence propagation.
+    pub tail_calls: HashMap<String, String>,
 }
 
-impl<'ast> Visit<'ast> for CallGraphCollector {
+impl<'ast> Visit<'ast> for CallGraphCollector<'_> {
+    fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+        self.module_path.push(ident_name(&i.ident));
+        syn::visit::visit_item_mod(self, i);
+        self.module_path.pop();
+    }
+
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let impl_type = match &*i.self_ty {
+            syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| ident_name(&seg.ident)),
+            _ => None,
+        };
+        let outer_impl = std::mem::replace(&mut self.current_impl, impl_type);
+        syn::visit::visit_item_impl(self, i);
+        self.current_impl = outer_impl;
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        // Methods are only tracked as callers so calls made inside them are attributed
+        let method_name = ident_name(&i.sig.ident);
+        let caller = match &self.current_impl {
+            Some(impl_type) => self.qualify(&format!("{}::{}", impl_type, method_name)),
+            None => self.qualify(&method_name),
+        };
+        self.calls.entry(caller.clone()).or_default();
+
+        let outer_function = self.current_function.replace(caller);
+        syn::visit::visit_impl_item_fn(self, i);
+        self.current_function = outer_function;
+    }
+
     fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
-        let fn_name = i.sig.ident.to_string();
-        self.current_function = Some(fn_name.clone());
+        let fn_name = ident_name(&i.sig.ident);
+        let qualified_name = self.qualify(&fn_name);
+        let outer_function = self.current_function.replace(qualified_name.clone());
         
-        // Calculate function metrics
+        // Calculate function metrics; the length runs from the signature to the closing brace
         let span = i.sig.ident.span();
-        let start_line = span.start().line;
-        let end_line = span.end().line;
+        let start_line = i.sig.span().start().line;
+        let end_line = i.block.brace_token.span.close().end().line;
         let line_count = end_line - start_line + 1;
         
-        // Calculate complexity (simplified cyclomatic complexity)
-        let mut complexity = 1; // base complexity
-        self.calculate_complexity(&i.block, &mut complexity);
+        let complexity = CyclomaticComplexityVisitor::score_block(&i.block);
         
+        let cognitive_complexity = CognitiveComplexityVisitor::score_block(&i.block);
+
         let param_count = i.sig.inputs.len();
-        
-        let visibility = if matches!(i.vis, syn::Visibility::Public(_)) {
-            "public"
-        } else {
-            "private"
+        let generics = &i.sig.generics;
+        let generic_param_count = generics.params.iter().filter(|param| !matches!(param, syn::GenericParam::Lifetime(_))).count();
+        let where_predicate_count = generics.where_clause.as_ref().map_or(0, |clause| clause.predicates.len());
+        let return_type = return_type(&i.sig);
+        let return_name = match return_type {
+            Some(syn::Type::Path(type_path)) => type_path.path.segments.last().map(|seg| ident_name(&seg.ident)),
+            _ => None,
         };
         
+        let visibility = visibility_label(&i.vis);
+
+        let diverges = matches!(i.sig.output, syn::ReturnType::Type(_, ref ty) if matches!(**ty, syn::Type::Never(_)))
+            || block_diverges(&i.block);
+        if let Some(callee) = tail_call(&i.block) {
+            self.tail_calls.insert(qualified_name.clone(), self.callee_path(callee));
+        }
+        
         let info = FunctionInfo {
             name: fn_name.clone(),
             line_count,
             complexity,
+            cognitive_complexity,
+            max_nesting_depth: 0,
             param_count,
+            generic_param_count,
+            where_predicate_count,
+            trait_bounds: trait_bounds(generics),
+            return_type: return_type.map(|ty| tokens_text(ty.to_token_stream())),
+            returns_result: return_name.as_deref() == Some("Result"),
+            returns_option: return_name.as_deref() == Some("Option"),
             visibility: visibility.to_string(),
+            qualified_name: qualified_name.clone(),
+            fan_in: 0,
+            fan_out: 0,
+            diverges,
             file: self.file.clone(),
-            range: Range {
-                start: Position { line: start_line, character: span.start().column },
-                end: Position { line: end_line, character: span.end().column },
-            },
+            range: span_to_range(span, self.line_index),
+            full_range: span_to_range(i.span(), self.line_index),
         };
         
-        self.function_info.insert(fn_name.clone(), info);
-        self.calls.entry(fn_name).or_insert(Vec::new());
-        
+        self.calls.entry(qualified_name.clone()).or_default();
+
+        // Nested fn items are measured on their own, so give each function a fresh depth
+        let outer_depth = std::mem::take(&mut self.nesting_depth);
+        let outer_max_depth = std::mem::take(&mut self.max_nesting_depth);
         syn::visit::visit_item_fn(self, i);
-        self.current_function = None;
+        let max_nesting_depth = std::mem::replace(&mut self.max_nesting_depth, outer_max_depth);
+        self.nesting_depth = outer_depth;
+
+        self.function_info.insert(qualified_name, FunctionInfo { max_nesting_depth, ..info });
+        self.current_function = outer_function;
+    }
+
+    fn visit_expr_if(&mut self, i: &'ast syn::ExprIf) {
+        self.visit_expr(&i.cond);
+        self.nested(|this| this.visit_block(&i.then_branch));
+        match i.else_branch.as_ref().map(|(_, expr)| &**expr) {
+            // `else if` continues the chain at the same depth rather than nesting deeper
+            Some(syn::Expr::If(else_if)) => self.visit_expr_if(else_if),
+            Some(syn::Expr::Block(else_block)) => self.nested(|this| this.visit_block(&else_block.block)),
+            Some(other) => self.visit_expr(other),
+            None => {}
+        }
+    }
+
+    fn visit_expr_match(&mut self, i: &'ast syn::ExprMatch) {
+        self.nested(|this| syn::visit::visit_expr_match(this, i));
+    }
+
+    fn visit_expr_loop(&mut self, i: &'ast syn::ExprLoop) {
+        self.nested(|this| syn::visit::visit_expr_loop(this, i));
+    }
+
+    fn visit_expr_while(&mut self, i: &'ast syn::ExprWhile) {
+        self.nested(|this| syn::visit::visit_expr_while(this, i));
+    }
+
+    fn visit_expr_for_loop(&mut self, i: &'ast syn::ExprForLoop) {
+        self.nested(|this| syn::visit::visit_expr_for_loop(this, i));
+    }
+
+    fn visit_expr_block(&mut self, i: &'ast syn::ExprBlock) {
+        self.nested(|this| syn::visit::visit_expr_block(this, i));
     }
 
     fn visit_expr_call(&mut self, i: &'ast syn::ExprCall) {
         if let syn::Expr::Path(path) = &*i.func {
-            if let Some(segment) = path.path.segments.last() {
-                let callee = segment.ident.to_string();
-                if let Some(caller) = &self.current_function {
-                    self.calls.entry(caller.clone()).or_insert(Vec::new()).push(callee);
-                }
+            if !path.path.segments.is_empty() {
+                let callee = self.callee_path(&path.path);
+                let span = path.path.segments.last().map(|seg| seg.ident.span()).unwrap_or_else(|| i.func.span());
+                self.record_call(callee, span);
             }
         }
         syn::visit::visit_expr_call(self, i);
     }
     
     fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
-        let method_name = i.method.to_string();
-        if let Some(caller) = &self.current_function {
-            self.calls.entry(caller.clone()).or_insert(Vec::new()).push(method_name);
-        }
+        let method_name = ident_name(&i.method);
+        // Only `self` receivers have a type we can infer without type checking
+        let is_self_receiver = matches!(&*i.receiver, syn::Expr::Path(path) if path.path.is_ident("self"));
+        let callee = match (&self.current_impl, is_self_receiver) {
+            (Some(impl_type), true) => format!("{}::{}", impl_type, method_name),
+            _ => method_name,
+        };
+        self.record_call(callee, i.method.span());
         syn::visit::visit_expr_method_call(self, i);
     }
 }
 
-impl CallGraphCollector {
-    fn calculate_complexity(&mut self, block: &syn::Block, complexity: &mut usize) {
-        for stmt in &block.stmts {
-            match stmt {
-                syn::Stmt::Expr(expr, _) => {
-                    self.calculate_expr_complexity(expr, complexity);
-                }
-                syn::Stmt::Local(local) => {
-                    if let Some(init) = &local.init {
-                        self.calculate_expr_complexity(&init.expr, complexity);
-                    }
-                }
-                _ => {}
-            }
-        }
+impl CallGraphCollector<'_> {
+    /// Attributes a call to the current function, if any, keeping its call-site location.
+    fn record_call(&mut self, callee: String, span: Span) {
+        let Some(caller) = &self.current_function else { return };
+        self.call_sites.push(CallSite {
+            caller: caller.clone(),
+            callee: callee.clone(),
+            file: self.file.clone(),
+            range: span_to_range(span, self.line_index),
+        });
+        self.calls.entry(caller.clone()).or_default().push(callee);
     }
-    
-    fn calculate_expr_complexity(&mut self, expr: &syn::Expr, complexity: &mut usize) {
-        match expr {
-            syn::Expr::If(_) | syn::Expr::Match(_) => *complexity += 1,
-            syn::Expr::Loop(_) | syn::Expr::While(_) | syn::Expr::ForLoop(_) => *complexity += 1,
-            syn::Expr::Binary(bin) if matches!(bin.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) => *complexity += 1,
-            syn::Expr::Block(block) => self.calculate_complexity(&block.block, complexity),
-            _ => {}
+
+    /// Walks `f` one nesting level deeper, recording the deepest level reached.
+    fn nested(&mut self, f: impl FnOnce(&mut Self)) {
+        self.nesting_depth += 1;
+        self.max_nesting_depth = self.max_nesting_depth.max(self.nesting_depth);
+        f(self);
+        self.nesting_depth -= 1;
+    }
+
+    /// Prefixes a name with the enclosing inline modules, e.g. `foo` inside `mod a` -> `a::foo`.
+    fn qualify(&self, name: &str) -> String {
+        self.module_path.iter().map(String::as_str).chain(std::iter::once(name)).collect::<Vec<_>>().join("::")
+    }
+
+    /// Joins a call path's segments, substituting the enclosing impl type for a leading `Self`.
+    fn callee_path(&self, path: &syn::Path) -> String {
+        path.segments
+            .iter()
+            .map(|seg| match (&self.current_impl, seg.ident == "Self") {
+                (Some(impl_type), true) => impl_type.clone(),
+                _ => ident_name(&seg.ident),
+            })
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+}
+
+/// Whether control never reaches the end of the block: its last statement is a `loop` with no
+/// `break` out of it, a `panic!`-style macro, `std::process::exit`/`abort`, or an `if`/`match`
+/// whose every branch diverges. Calls to other diverging functions are resolved workspace-wide.
+pub fn block_diverges(block: &syn::Block) -> bool {
+    match block.stmts.last() {
+        Some(syn::Stmt::Expr(expr, _)) => expr_diverges(expr),
+        Some(syn::Stmt::Macro(stmt)) => is_panic_macro(&stmt.mac),
+        _ => false,
+    }
+}
+
+fn expr_diverges(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Loop(expr_loop) => {
+            let mut breaks = BreakFinder { label: expr_loop.label.as_ref().map(|label| ident_name(&label.name.ident)), loop_depth: 0, found: false };
+            breaks.visit_block(&expr_loop.body);
+            !breaks.found
         }
-        syn::visit::visit_expr(self, expr);
+        syn::Expr::Macro(expr) => is_panic_macro(&expr.mac),
+        syn::Expr::Call(call) => matches!(&*call.func, syn::Expr::Path(path) if {
+            let segments: Vec<String> = path.path.segments.iter().map(|seg| ident_name(&seg.ident)).collect();
+            segments.ends_with(&["process".to_string(), "exit".to_string()])
+                || segments.ends_with(&["process".to_string(), "abort".to_string()])
+        }),
+        syn::Expr::Block(expr) => block_diverges(&expr.block),
+        syn::Expr::Unsafe(expr) => block_diverges(&expr.block),
+        syn::Expr::Paren(expr) => expr_diverges(&expr.expr),
+        syn::Expr::If(expr) => match &expr.else_branch {
+            Some((_, else_branch)) => block_diverges(&expr.then_branch) && expr_diverges(else_branch),
+            None => false,
+        },
+        syn::Expr::Match(expr) => !expr.arms.is_empty() && expr.arms.iter().all(|arm| expr_diverges(&arm.body)),
+        _ => false,
     }
 }
 
-pub struct TypeUsageCollector {
-    pub file: String,
-    pub usages: HashMap<String, Vec<ReferenceLocation>>,
-    pub struct_info: HashMap<String, crate::models::StructInfo>,
-    pub enum_info: HashMap<String, crate::models::EnumInfo>,
+/// Path of the function called by the block's last statement, e.g. `fail` in `fn f() { fail() }`.
+fn tail_call(block: &syn::Block) -> Option<&syn::Path> {
+    match block.stmts.last()? {
+        syn::Stmt::Expr(syn::Expr::Call(call), _) => match &*call.func {
+            syn::Expr::Path(path) => Some(&path.path),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
-impl<'ast> Visit<'ast> for TypeUsageCollector {
-    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
-        let struct_name = i.ident.to_string();
-        let span = i.ident.span();
-        let start = span.start();
-        let end = span.end();
-        
-        let field_count = match &i.fields {
-            syn::Fields::Named(fields) => fields.named.len(),
-            syn::Fields::Unnamed(fields) => fields.unnamed.len(),
-            syn::Fields::Unit => 0,
-        };
-        
-        let info = StructInfo {
-            name: struct_name.clone(),
-            field_count,
-            file: self.file.clone(),
-            range: Range {
-                start: Position { line: start.line, character: start.column },
-                end: Position { line: end.line, character: end.column },
-            },
+/// Looks for a way out of the loop being checked: a `break` that leaves it (an unlabeled one
+/// outside nested loops, or one naming the loop's label), a `return`, or a `?`. Closures and
+/// async blocks can't leave it.
+struct BreakFinder {
+    label: Option<String>,
+    loop_depth: usize,
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for BreakFinder {
+    fn visit_expr_break(&mut self, i: &'ast syn::ExprBreak) {
+        let leaves = match &i.label {
+            Some(label) => self.label.as_deref() == Some(ident_name(&label.ident).as_str()),
+            None => self.loop_depth == 0,
         };
-        
-        self.struct_info.insert(struct_name, info);
-        syn::visit::visit_item_struct(self, i);
+        self.found |= leaves;
+        syn::visit::visit_expr_break(self, i);
     }
-    
-    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
-        let enum_name = i.ident.to_string();
-        let span = i.ident.span();
-        let start = span.start();
-        let end = span.end();
-        
-        let variant_count = i.variants.len();
-        
-        let info = EnumInfo {
-            name: enum_name.clone(),
-            variant_count,
-            file: self.file.clone(),
-            range: Range {
-                start: Position { line: start.line, character: start.column },
-                end: Position { line: end.line, character: end.column },
-            },
-        };
-        
-        self.enum_info.insert(enum_name, info);
-        syn::visit::visit_item_enum(self, i);
+
+    fn visit_expr_return(&mut self, _i: &'ast syn::ExprReturn) {
+        self.found = true;
     }
 
-    fn visit_type_path(&mut self, i: &'ast syn::TypePath) {
-        if let Some(seg) = i.path.segments.last() {
-            let type_name = seg.ident.to_string();
-            let span = seg.ident.span();
-            let start = span.start();
-            let end = span.end();
-            self.usages.entry(type_name).or_insert(Vec::new()).push(ReferenceLocation {
-                file: self.file.clone(),
-                range: Range {
-                    start: Position { line: start.line, character: start.column },
-                    end: Position { line: end.line, character: end.column },
-                },
-            });
-        }
-        syn::visit::visit_type_path(self, i);
+    fn visit_expr_try(&mut self, _i: &'ast syn::ExprTry) {
+        self.found = true;
     }
-    
-    fn visit_path(&mut self, i: &'ast syn::Path) {
-        if let Some(seg) = i.segments.last() {
-            let type_name = seg.ident.to_string();
-            let span = seg.ident.span();
-            let start = span.start();
-            let end = span.end();
-            self.usages.entry(type_name).or_insert(Vec::new()).push(ReferenceLocation {
-                file: self.file.clone(),
-                range: Range {
-                    start: Position { line: start.line, character: start.column },
-                    end: Position { line: end.line, character: end.column },
-                },
-            });
-        }
-        syn::visit::visit_path(self, i);
+
+    fn visit_expr_loop(&mut self, i: &'ast syn::ExprLoop) {
+        self.loop_depth += 1;
+        syn::visit::visit_expr_loop(self, i);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_expr_while(&mut self, i: &'ast syn::ExprWhile) {
+        self.loop_depth += 1;
+        syn::visit::visit_expr_while(self, i);
+        self.loop_depth -= 1;
+    }
+
+    fn visit_expr_for_loop(&mut self, i: &'ast syn::ExprForLoop) {
+        self.loop_depth += 1;
+        syn::visit::visit_expr_for_loop(self, i);
+        self.loop_depth -= 1;
     }
+
+    fn visit_expr_closure(&mut self, _i: &'ast syn::ExprClosure) {}
+
+    fn visit_expr_async(&mut self, _i: &'ast syn::ExprAsync) {}
+
+    fn visit_item(&mut self, _i: &'ast syn::Item) {}
 }
 
-pub struct ModuleDependencyCollector {
-    pub file: String,
-    pub dependencies: HashMap<String, Vec<String>>,
+/// Computes a cyclomatic complexity count for a single function body, starting from 1.
+///
+/// Each branch point adds 1: `if`/`if let`, loops including `while let`, `let ... else`,
+/// `&&`/`||`, every `?`, and a `match` plus one per arm beyond the first. Nested `fn` items
+/// are skipped since they are measured as functions of their own.
+pub struct CyclomaticComplexityVisitor {
+    pub score: usize,
 }
 
-impl<'ast> Visit<'ast> for ModuleDependencyCollector {
+impl CyclomaticComplexityVisitor {
+    pub fn score_block(block: &syn::Block) -> usize {
+        let mut visitor = Self { score: 1 };
+        visitor.visit_block(block);
+        visitor.score
+    }
+}
+
+impl<'ast> Visit<'ast> for CyclomaticComplexityVisitor {
+    fn visit_item_fn(&mut self, _i: &'ast syn::ItemFn) {}
+
+    fn visit_expr(&mut self, i: &'ast syn::Expr) {
+        match i {
+            syn::Expr::If(_) | syn::Expr::Try(_) => self.score += 1,
+            syn::Expr::Loop(_) | syn::Expr::While(_) | syn::Expr::ForLoop(_) => self.score += 1,
+            syn::Expr::Match(expr_match) => self.score += expr_match.arms.len().max(1),
+            syn::Expr::Binary(bin) if matches!(bin.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) => self.score += 1,
+            _ => {}
+        }
+        syn::visit::visit_expr(self, i);
+    }
+
+    fn visit_local(&mut self, i: &'ast syn::Local) {
+        if i.init.as_ref().is_some_and(|init| init.diverge.is_some()) {
+            self.score += 1;
+        }
+        syn::visit::visit_local(self, i);
+    }
+}
+
+/// Computes SonarSource-style cognitive complexity for a single function body.
+///
+/// Control-flow structures add 1 plus the current nesting level, `else`/`else if` add a flat 1,
+/// and each run of like logical operators adds 1. Closures deepen nesting without scoring, and
+/// nested `fn` items are skipped since they are measured as functions of their own.
+#[derive(Default)]
+pub struct CognitiveComplexityVisitor {
+    pub score: usize,
+    nesting: usize,
+    logical_parent: Option<LogicalOp>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LogicalOp {
+    And,
+    Or,
+}
+
+impl CognitiveComplexityVisitor {
+    pub fn score_block(block: &syn::Block) -> usize {
+        let mut visitor = Self::default();
+        visitor.visit_block(block);
+        visitor.score
+    }
+
+    fn visit_nested_block(&mut self, block: &syn::Block) {
+        self.nesting += 1;
+        self.visit_block(block);
+        self.nesting -= 1;
+    }
+
+    fn visit_logical_operand(&mut self, operand: &syn::Expr, op: Option<LogicalOp>) {
+        match operand {
+            syn::Expr::Binary(binary) => {
+                // Direct children continue the current operator sequence
+                let outer = std::mem::replace(&mut self.logical_parent, op);
+                self.visit_expr_binary(binary);
+                self.logical_parent = outer;
+            }
+            _ => {
+                let outer = self.logical_parent.take();
+                self.visit_expr(operand);
+                self.logical_parent = outer;
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CognitiveComplexityVisitor {
+    fn visit_item_fn(&mut self, _i: &'ast syn::ItemFn) {}
+
+    fn visit_expr_if(&mut self, i: &'ast syn::ExprIf) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&i.cond);
+        self.visit_nested_block(&i.then_branch);
+
+        // `else if` chains score a flat increment and stay at the same nesting level
+        let mut else_branch = i.else_branch.as_ref().map(|(_, expr)| &**expr);
+        while let Some(expr) = else_branch {
+            self.score += 1;
+            match expr {
+                syn::Expr::If(else_if) => {
+                    self.visit_expr(&else_if.cond);
+                    self.visit_nested_block(&else_if.then_branch);
+                    else_branch = else_if.else_branch.as_ref().map(|(_, expr)| &**expr);
+                }
+                other => {
+                    self.nesting += 1;
+                    self.visit_expr(other);
+                    self.nesting -= 1;
+                    else_branch = None;
+                }
+            }
+        }
+    }
+
+    fn visit_expr_match(&mut self, i: &'ast syn::ExprMatch) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&i.expr);
+        self.nesting += 1;
+        for arm in &i.arms {
+            self.visit_arm(arm);
+        }
+        self.nesting -= 1;
+    }
+
+    fn visit_expr_for_loop(&mut self, i: &'ast syn::ExprForLoop) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&i.expr);
+        self.visit_nested_block(&i.body);
+    }
+
+    fn visit_expr_while(&mut self, i: &'ast syn::ExprWhile) {
+        self.score += 1 + self.nesting;
+        self.visit_expr(&i.cond);
+        self.visit_nested_block(&i.body);
+    }
+
+    fn visit_expr_loop(&mut self, i: &'ast syn::ExprLoop) {
+        self.score += 1 + self.nesting;
+        self.visit_nested_block(&i.body);
+    }
+
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        self.nesting += 1;
+        syn::visit::visit_expr_closure(self, i);
+        self.nesting -= 1;
+    }
+
+    fn visit_expr_break(&mut self, i: &'ast syn::ExprBreak) {
+        if i.label.is_some() {
+            self.score += 1;
+        }
+        syn::visit::visit_expr_break(self, i);
+    }
+
+    fn visit_expr_continue(&mut self, i: &'ast syn::ExprContinue) {
+        if i.label.is_some() {
+            self.score += 1;
+        }
+    }
+
+    fn visit_expr_binary(&mut self, i: &'ast syn::ExprBinary) {
+        let op = match i.op {
+            syn::BinOp::And(_) => Some(LogicalOp::And),
+            syn::BinOp::Or(_) => Some(LogicalOp::Or),
+            _ => None,
+        };
+        if op.is_some() && op != self.logical_parent {
+            self.score += 1;
+        }
+        self.visit_logical_operand(&i.left, op);
+        self.visit_logical_operand(&i.right, op);
+    }
+}
+
+pub struct TypeUsageCollector<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub definitions: HashMap<String, Vec<ReferenceLocation>>,
+    pub usages: HashMap<String, Vec<ReferenceLocation>>,
+    pub struct_info: HashMap<String, crate::models::StructInfo>,
+    pub enum_info: HashMap<String, crate::models::EnumInfo>,
+}
+
+impl<'ast> Visit<'ast> for TypeUsageCollector<'_> {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        let struct_name = ident_name(&i.ident);
+        let span = i.ident.span();
+        
+        let field_count = match &i.fields {
+            syn::Fields::Named(fields) => fields.named.len(),
+            syn::Fields::Unnamed(fields) => fields.unnamed.len(),
+            syn::Fields::Unit => 0,
+        };
+        
+        let info = StructInfo {
+            name: struct_name.clone(),
+            field_count,
+            file: self.file.clone(),
+            range: span_to_range(span, self.line_index),
+        };
+        
+        self.definitions.entry(struct_name.clone()).or_default().push(ReferenceLocation {
+            file: self.file.clone(),
+            range: info.range.clone(),
+        });
+        self.struct_info.insert(struct_name, info);
+        syn::visit::visit_item_struct(self, i);
+    }
+    
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        let enum_name = ident_name(&i.ident);
+        let span = i.ident.span();
+        
+        let variant_count = i.variants.len();
+        
+        let info = EnumInfo {
+            name: enum_name.clone(),
+            variant_count,
+            file: self.file.clone(),
+            range: span_to_range(span, self.line_index),
+        };
+        
+        self.definitions.entry(enum_name.clone()).or_default().push(ReferenceLocation {
+            file: self.file.clone(),
+            range: info.range.clone(),
+        });
+        self.enum_info.insert(enum_name, info);
+        syn::visit::visit_item_enum(self, i);
+    }
+
+    // Type paths reach this through `visit_type_path`, so recording here alone counts each
+    // occurrence exactly once. Definition idents aren't paths and are never counted.
+    fn visit_path(&mut self, i: &'ast syn::Path) {
+        if let Some(seg) = i.segments.last() {
+            let type_name = ident_name(&seg.ident);
+            let span = seg.ident.span();
+            self.usages.entry(type_name).or_insert(Vec::new()).push(ReferenceLocation {
+                file: self.file.clone(),
+                range: span_to_range(span, self.line_index),
+            });
+        }
+        syn::visit::visit_path(self, i);
+    }
+}
+
+pub struct ModuleDependencyCollector {
+    pub file: String,
+    pub dependencies: HashMap<String, Vec<String>>,
+    /// Every imported path in full, e.g. `crate::models::Range`; globs end at their module.
+    pub use_paths: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for ModuleDependencyCollector {
     fn visit_item_use(&mut self, i: &'ast syn::ItemUse) {
         // Simple extraction of use statements
         let mut deps = Vec::new();
         extract_use_paths(&i.tree, &mut deps);
+        collect_full_use_paths(&i.tree, &mut Vec::new(), &mut self.use_paths);
         let module_name = self.file.clone(); // or extract module name
         self.dependencies.entry(module_name).or_insert(Vec::new()).extend(deps);
         syn::visit::visit_item_use(self, i);
     }
 }
 
+/// Collects the first segment of every multi-segment path, `use` and `extern crate`, i.e.
+/// the crates (or local modules) a file refers to. Macro bodies and attribute arguments are
+/// plain tokens to syn, so they're scanned for `name::` as well.
+#[derive(Default)]
+pub struct CrateReferenceCollector {
+    pub roots: HashSet<String>,
+}
+
+impl CrateReferenceCollector {
+    fn scan_tokens(&mut self, tokens: proc_macro2::TokenStream) {
+        let mut previous: Option<proc_macro2::Ident> = None;
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                proc_macro2::TokenTree::Ident(ident) => {
+                    previous = Some(ident);
+                    continue;
+                }
+                proc_macro2::TokenTree::Punct(punct) if punct.as_char() == ':' && punct.spacing() == proc_macro2::Spacing::Joint => {
+                    let is_path_separator = matches!(tokens.peek(), Some(proc_macro2::TokenTree::Punct(next)) if next.as_char() == ':');
+                    if let (true, Some(ident)) = (is_path_separator, &previous) {
+                        self.roots.insert(ident_name(ident));
+                    }
+                }
+                proc_macro2::TokenTree::Group(group) => self.scan_tokens(group.stream()),
+                _ => {}
+            }
+            previous = None;
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CrateReferenceCollector {
+    fn visit_item_use(&mut self, i: &'ast syn::ItemUse) {
+        let mut paths = Vec::new();
+        collect_full_use_paths(&i.tree, &mut Vec::new(), &mut paths);
+        self.roots.extend(paths.iter().filter_map(|path| path.split("::").next()).map(str::to_string));
+    }
+
+    fn visit_item_extern_crate(&mut self, i: &'ast syn::ItemExternCrate) {
+        self.roots.insert(ident_name(&i.ident));
+        syn::visit::visit_item_extern_crate(self, i);
+    }
+
+    fn visit_path(&mut self, i: &'ast syn::Path) {
+        if i.segments.len() > 1 || i.leading_colon.is_some() {
+            self.roots.insert(ident_name(&i.segments[0].ident));
+        }
+        syn::visit::visit_path(self, i);
+    }
+
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        self.scan_tokens(i.tokens.clone());
+        syn::visit::visit_macro(self, i);
+    }
+
+    fn visit_meta_list(&mut self, i: &'ast syn::MetaList) {
+        self.scan_tokens(i.tokens.clone());
+        syn::visit::visit_meta_list(self, i);
+    }
+}
+
 fn extract_use_paths(tree: &syn::UseTree, deps: &mut Vec<String>) {
     match tree {
         syn::UseTree::Path(path) => {
-            deps.push(path.ident.to_string());
+            deps.push(ident_name(&path.ident));
             extract_use_paths(&path.tree, deps);
         }
         syn::UseTree::Name(name) => {
-            deps.push(name.ident.to_string());
+            deps.push(ident_name(&name.ident));
         }
         syn::UseTree::Rename(rename) => {
-            deps.push(rename.ident.to_string());
+            deps.push(ident_name(&rename.ident));
         }
         syn::UseTree::Glob(_) => {
             // For glob, we might not extract specific names
@@ -346,4 +1056,2280 @@ fn extract_use_paths(tree: &syn::UseTree, deps: &mut Vec<String>) {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn collect_full_use_paths(tree: &syn::UseTree, prefix: &mut Vec<String>, out: &mut Vec<String>) {
+    match tree {
+        syn::UseTree::Path(path) => {
+            prefix.push(ident_name(&path.ident));
+            collect_full_use_paths(&path.tree, prefix, out);
+            prefix.pop();
+        }
+        syn::UseTree::Name(syn::UseName { ident }) | syn::UseTree::Rename(syn::UseRename { ident, .. }) => {
+            let mut path = prefix.clone();
+            // `use foo::{self}` imports `foo` itself
+            if ident != "self" {
+                path.push(ident_name(ident));
+            }
+            out.push(path.join("::"));
+        }
+        syn::UseTree::Glob(_) => out.push(prefix.join("::")),
+        syn::UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_full_use_paths(tree, prefix, out);
+            }
+        }
+    }
+}
+
+/// Classifies identifiers for syntax highlighting.
+///
+/// Declarations are classified by the item they name. References are resolved against
+/// `definitions`, the file's own symbols, and otherwise guessed from context and casing:
+/// `Foo::bar()` is a method, a capitalized path expression like `None` an enum member.
+/// Macro arguments are classified when they parse as comma-separated expressions.
+/// Tokens are pushed in visiting order and may repeat; sort and dedup by position afterwards.
+pub struct SemanticTokenCollector<'a> {
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub definitions: HashMap<String, SymbolKind>,
+    pub out: Vec<SemanticToken>,
+    /// Parameter names of the enclosing functions and closures.
+    params: HashSet<String>,
+    in_params: bool,
+}
+
+impl<'a> SemanticTokenCollector<'a> {
+    pub fn new(line_index: Option<&'a LineIndex<'a>>, definitions: HashMap<String, SymbolKind>) -> Self {
+        Self { line_index, definitions, out: Vec::new(), params: HashSet::new(), in_params: false }
+    }
+
+    fn push(&mut self, ident: &syn::Ident, token_type: SemanticTokenType) {
+        self.out.push(SemanticToken { range: span_to_range(ident.span(), self.line_index), token_type });
+    }
+
+    fn defined(&self, ident: &syn::Ident) -> Option<SemanticTokenType> {
+        self.definitions.get(&ident_name(ident)).map(|kind| match kind {
+            SymbolKind::Fn => SemanticTokenType::Function,
+            SymbolKind::Method => SemanticTokenType::Method,
+            SymbolKind::Struct | SymbolKind::Union => SemanticTokenType::Struct,
+            SymbolKind::Enum => SemanticTokenType::Enum,
+            SymbolKind::Trait => SemanticTokenType::Interface,
+            SymbolKind::TypeAlias => SemanticTokenType::Type,
+            SymbolKind::Const | SymbolKind::Static => SemanticTokenType::Variable,
+            SymbolKind::Module => SemanticTokenType::Namespace,
+            SymbolKind::Macro => SemanticTokenType::Macro,
+            SymbolKind::Variant => SemanticTokenType::EnumMember,
+            SymbolKind::Field => SemanticTokenType::Property,
+        })
+    }
+
+    /// Classifies every segment but the last: types when capitalized, modules otherwise.
+    fn push_path_prefix(&mut self, path: &syn::Path) {
+        for segment in path.segments.iter().rev().skip(1) {
+            let ident = &segment.ident;
+            if is_path_keyword(ident) {
+                continue;
+            }
+            let token_type = self.defined(ident).unwrap_or(if starts_uppercase(ident) {
+                SemanticTokenType::Type
+            } else {
+                SemanticTokenType::Namespace
+            });
+            self.push(ident, token_type);
+        }
+    }
+
+    fn push_path(&mut self, path: &syn::Path, guess: impl FnOnce(&Self, &syn::Ident) -> SemanticTokenType) {
+        self.push_path_prefix(path);
+        if let Some(last) = path.segments.last() {
+            if !is_path_keyword(&last.ident) {
+                let token_type = self.defined(&last.ident).unwrap_or_else(|| guess(self, &last.ident));
+                self.push(&last.ident, token_type);
+            }
+        }
+    }
+}
+
+impl PerFunction for SemanticTokenCollector<'_> {
+    type FnState = HashSet<String>;
+
+    fn fn_state(&mut self) -> &mut HashSet<String> {
+        &mut self.params
+    }
+
+    fn start_fn(&mut self, _sig: &syn::Signature) -> HashSet<String> {
+        HashSet::new()
+    }
+}
+
+fn starts_uppercase(ident: &syn::Ident) -> bool {
+    ident_name(ident).starts_with(char::is_uppercase)
+}
+
+fn is_path_keyword(ident: &syn::Ident) -> bool {
+    ident == "self" || ident == "Self" || ident == "crate" || ident == "super"
+}
+
+impl<'ast> Visit<'ast> for SemanticTokenCollector<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.push(&i.sig.ident, SemanticTokenType::Function);
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.push(&i.sig.ident, SemanticTokenType::Method);
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.push(&i.sig.ident, SemanticTokenType::Method);
+        self.visit_fn(&i.sig, i.default.as_ref(), |this| syn::visit::visit_trait_item_fn(this, i));
+    }
+
+    fn visit_fn_arg(&mut self, i: &'ast syn::FnArg) {
+        self.in_params = true;
+        syn::visit::visit_fn_arg(self, i);
+        self.in_params = false;
+    }
+
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        for input in &i.inputs {
+            self.in_params = true;
+            self.visit_pat(input);
+            self.in_params = false;
+        }
+        self.visit_return_type(&i.output);
+        self.visit_expr(&i.body);
+    }
+
+    fn visit_pat_ident(&mut self, i: &'ast syn::PatIdent) {
+        if self.in_params {
+            self.params.insert(ident_name(&i.ident));
+            self.push(&i.ident, SemanticTokenType::Parameter);
+        } else {
+            self.push(&i.ident, SemanticTokenType::Variable);
+        }
+        syn::visit::visit_pat_ident(self, i);
+    }
+
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.push(&i.ident, SemanticTokenType::Struct);
+        syn::visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        self.push(&i.ident, SemanticTokenType::Struct);
+        syn::visit::visit_item_union(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        self.push(&i.ident, SemanticTokenType::Enum);
+        syn::visit::visit_item_enum(self, i);
+    }
+
+    fn visit_variant(&mut self, i: &'ast syn::Variant) {
+        self.push(&i.ident, SemanticTokenType::EnumMember);
+        syn::visit::visit_variant(self, i);
+    }
+
+    fn visit_field(&mut self, i: &'ast syn::Field) {
+        if let Some(ident) = &i.ident {
+            self.push(ident, SemanticTokenType::Property);
+        }
+        syn::visit::visit_field(self, i);
+    }
+
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        self.push(&i.ident, SemanticTokenType::Interface);
+        syn::visit::visit_item_trait(self, i);
+    }
+
+    fn visit_item_type(&mut self, i: &'ast syn::ItemType) {
+        self.push(&i.ident, SemanticTokenType::Type);
+        syn::visit::visit_item_type(self, i);
+    }
+
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
+        self.push(&i.ident, SemanticTokenType::Variable);
+        syn::visit::visit_item_const(self, i);
+    }
+
+    fn visit_item_static(&mut self, i: &'ast syn::ItemStatic) {
+        self.push(&i.ident, SemanticTokenType::Variable);
+        syn::visit::visit_item_static(self, i);
+    }
+
+    fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+        self.push(&i.ident, SemanticTokenType::Namespace);
+        syn::visit::visit_item_mod(self, i);
+    }
+
+    fn visit_item_macro(&mut self, i: &'ast syn::ItemMacro) {
+        if let Some(ident) = &i.ident {
+            self.push(ident, SemanticTokenType::Macro);
+        }
+        syn::visit::visit_item_macro(self, i);
+    }
+
+    fn visit_type_param(&mut self, i: &'ast syn::TypeParam) {
+        self.push(&i.ident, SemanticTokenType::TypeParameter);
+        syn::visit::visit_type_param(self, i);
+    }
+
+    fn visit_type_path(&mut self, i: &'ast syn::TypePath) {
+        self.push_path(&i.path, |_, _| SemanticTokenType::Type);
+        syn::visit::visit_type_path(self, i);
+    }
+
+    fn visit_expr_call(&mut self, i: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(func) = &*i.func {
+            let has_type_prefix = func.path.segments.len() > 1
+                && starts_uppercase(&func.path.segments[func.path.segments.len() - 2].ident);
+            self.push_path(&func.path, |_, ident| {
+                if starts_uppercase(ident) {
+                    SemanticTokenType::EnumMember
+                } else if has_type_prefix {
+                    SemanticTokenType::Method
+                } else {
+                    SemanticTokenType::Function
+                }
+            });
+        }
+        syn::visit::visit_expr_call(self, i);
+    }
+
+    fn visit_expr_path(&mut self, i: &'ast syn::ExprPath) {
+        self.push_path(&i.path, |this, ident| {
+            let name = ident_name(ident);
+            if name.chars().all(|c| c.is_uppercase() || c.is_ascii_digit() || c == '_') {
+                SemanticTokenType::Variable
+            } else if starts_uppercase(ident) {
+                SemanticTokenType::EnumMember
+            } else if this.params.contains(&name) {
+                SemanticTokenType::Parameter
+            } else {
+                SemanticTokenType::Variable
+            }
+        });
+        syn::visit::visit_expr_path(self, i);
+    }
+
+    fn visit_expr_struct(&mut self, i: &'ast syn::ExprStruct) {
+        self.push_path(&i.path, |_, _| SemanticTokenType::Struct);
+        syn::visit::visit_expr_struct(self, i);
+    }
+
+    fn visit_pat_struct(&mut self, i: &'ast syn::PatStruct) {
+        self.push_path(&i.path, |_, _| SemanticTokenType::Struct);
+        syn::visit::visit_pat_struct(self, i);
+    }
+
+    fn visit_pat_tuple_struct(&mut self, i: &'ast syn::PatTupleStruct) {
+        self.push_path(&i.path, |_, _| SemanticTokenType::EnumMember);
+        syn::visit::visit_pat_tuple_struct(self, i);
+    }
+
+    fn visit_member(&mut self, i: &'ast syn::Member) {
+        if let syn::Member::Named(ident) = i {
+            self.push(ident, SemanticTokenType::Property);
+        }
+    }
+
+    fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
+        self.push(&i.method, SemanticTokenType::Method);
+        syn::visit::visit_expr_method_call(self, i);
+    }
+
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        self.push_path(&i.path, |_, _| SemanticTokenType::Macro);
+        // Arguments of `println!`, `vec!`, `assert_eq!` and the like are plain expressions
+        let parser = syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+        if let Ok(args) = i.parse_body_with(parser) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+        syn::visit::visit_macro(self, i);
+    }
+
+    fn visit_use_tree(&mut self, i: &'ast syn::UseTree) {
+        match i {
+            syn::UseTree::Path(path) if !is_path_keyword(&path.ident) => {
+                let token_type = self.defined(&path.ident).unwrap_or(SemanticTokenType::Namespace);
+                self.push(&path.ident, token_type);
+            }
+            syn::UseTree::Name(syn::UseName { ident }) | syn::UseTree::Rename(syn::UseRename { ident, .. })
+                if !is_path_keyword(ident) =>
+            {
+                // Lowercase imports may be modules or functions, so only known or capitalized names are classified
+                if let Some(token_type) = self.defined(ident).or(starts_uppercase(ident).then_some(SemanticTokenType::Type)) {
+                    self.push(ident, token_type);
+                }
+            }
+            _ => {}
+        }
+        syn::visit::visit_use_tree(self, i);
+    }
+}
+
+/// What a finder built on `ScopedBindingWalker` records for each binding, and does as
+/// bindings are made, read, and go out of scope.
+pub trait BindingTracker {
+    type Binding;
+
+    /// Records `ident`, bound by a `let` when `is_let` and by a parameter or other pattern
+    /// otherwise. `ty` is its written or evident type, and `scopes` the enclosing scopes of
+    /// `function`, innermost last.
+    fn bind(
+        &mut self,
+        ident: &syn::Ident,
+        ty: Option<String>,
+        is_let: bool,
+        function: &str,
+        scopes: &[HashMap<String, Self::Binding>],
+    ) -> Self::Binding;
+
+    /// `binding` ended: its scope closed, or a later binding in the same scope shadowed it.
+    fn release(&mut self, _name: String, _binding: Self::Binding, _function: &str) {}
+
+    /// A path expression or macro body read the binding.
+    fn read(_binding: &mut Self::Binding) {}
+
+    /// Text of a type written on a parameter or pattern.
+    fn type_text(&self, _ty: &syn::Type) -> Option<String> {
+        None
+    }
+
+    /// The type of a `let` initializer, when it's evident without inference.
+    fn init_type(&self, _expr: &syn::Expr) -> Option<String> {
+        None
+    }
+}
+
+/// Walks every function keeping track of the bindings in scope: parameters, `let`s, and the
+/// patterns of closures, match arms, `for` loops and `if let`/`while let`, each scoped the way
+/// rustc scopes them. Nested functions start from scratch.
+pub struct ScopedBindingWalker<T: BindingTracker> {
+    pub tracker: T,
+    current: FnScopes<T::Binding>,
+}
+
+/// The function `ScopedBindingWalker` is in, and its scopes, innermost last.
+pub struct FnScopes<B> {
+    function: String,
+    scopes: Vec<HashMap<String, B>>,
+}
+
+impl<T: BindingTracker> ScopedBindingWalker<T> {
+    pub fn new(tracker: T) -> Self {
+        Self { tracker, current: FnScopes { function: String::new(), scopes: Vec::new() } }
+    }
+
+    fn release(&mut self, scope: HashMap<String, T::Binding>, function: &str) {
+        for (name, binding) in scope {
+            self.tracker.release(name, binding, function);
+        }
+    }
+
+    fn in_scope(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.current.scopes.push(HashMap::new());
+        visit(self);
+        if let Some(scope) = self.current.scopes.pop() {
+            let function = self.current.function.clone();
+            self.release(scope, &function);
+        }
+    }
+
+    /// Binds the parameters, then visits the body in the same scope.
+    fn visit_fn_body(&mut self, sig: &syn::Signature, body: &syn::Block) {
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(pat_type) = input {
+                let ty = self.tracker.type_text(&pat_type.ty);
+                self.bind(&pat_type.pat, ty, false);
+            }
+        }
+        self.visit_block(body);
+    }
+
+    /// Binds every identifier in `pat` in the innermost scope. A type annotation on the
+    /// pattern wins over `ty`, which only applies to a bare identifier pattern.
+    fn bind(&mut self, pat: &syn::Pat, mut ty: Option<String>, is_let: bool) {
+        let mut outer = pat;
+        loop {
+            match outer {
+                syn::Pat::Type(pat_type) => {
+                    ty = self.tracker.type_text(&pat_type.ty);
+                    outer = &pat_type.pat;
+                }
+                syn::Pat::Paren(paren) => outer = &paren.pat,
+                _ => break,
+            }
+        }
+        if !matches!(outer, syn::Pat::Ident(_)) {
+            ty = None;
+        }
+
+        let mut idents = Vec::new();
+        pattern_idents(pat, &mut idents);
+        for ident in idents {
+            let binding = self.tracker.bind(ident, ty.take(), is_let, &self.current.function, &self.current.scopes);
+            // Shadowing in the same scope ends the earlier binding's life
+            let replaced = self.current.scopes.last_mut().and_then(|scope| scope.insert(ident_name(ident), binding));
+            if let Some(replaced) = replaced {
+                self.tracker.release(ident_name(ident), replaced, &self.current.function);
+            }
+        }
+    }
+
+    fn read(&mut self, name: &str) {
+        if let Some(binding) = self.current.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name)) {
+            T::read(binding);
+        }
+    }
+
+    fn read_tokens(&mut self, tokens: proc_macro2::TokenStream) {
+        for token in tokens {
+            match token {
+                proc_macro2::TokenTree::Ident(ident) => self.read(&ident_name(&ident)),
+                proc_macro2::TokenTree::Group(group) => self.read_tokens(group.stream()),
+                proc_macro2::TokenTree::Literal(literal) => {
+                    for capture in FORMAT_CAPTURE.captures_iter(&literal.to_string()) {
+                        self.read(&capture[1]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<T: BindingTracker> PerFunction for ScopedBindingWalker<T> {
+    type FnState = FnScopes<T::Binding>;
+
+    fn fn_state(&mut self) -> &mut FnScopes<T::Binding> {
+        &mut self.current
+    }
+
+    fn start_fn(&mut self, sig: &syn::Signature) -> FnScopes<T::Binding> {
+        // The parameters' scope, which the body's block nests in
+        FnScopes { function: ident_name(&sig.ident), scopes: vec![HashMap::new()] }
+    }
+
+    fn finish_fn(&mut self, _sig: &syn::Signature, _body: Option<&syn::Block>, state: FnScopes<T::Binding>) {
+        for scope in state.scopes {
+            self.release(scope, &state.function);
+        }
+    }
+}
+
+impl<'ast, T: BindingTracker> Visit<'ast> for ScopedBindingWalker<T> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| this.visit_fn_body(&i.sig, &i.block));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| this.visit_fn_body(&i.sig, &i.block));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        if let Some(block) = &i.default {
+            self.visit_fn(&i.sig, Some(block), |this| this.visit_fn_body(&i.sig, block));
+        }
+    }
+
+    fn visit_block(&mut self, i: &'ast syn::Block) {
+        self.in_scope(|this| syn::visit::visit_block(this, i));
+    }
+
+    fn visit_local(&mut self, i: &'ast syn::Local) {
+        // The initializer still sees the outer binding
+        let mut ty = None;
+        if let Some(init) = &i.init {
+            self.visit_expr(&init.expr);
+            if let Some((_, diverge)) = &init.diverge {
+                self.visit_expr(diverge);
+            }
+            ty = self.tracker.init_type(&init.expr);
+        }
+        self.bind(&i.pat, ty, true);
+    }
+
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        self.in_scope(|this| {
+            for input in &i.inputs {
+                this.bind(input, None, false);
+            }
+            this.visit_expr(&i.body);
+        });
+    }
+
+    fn visit_arm(&mut self, i: &'ast syn::Arm) {
+        self.in_scope(|this| {
+            this.bind(&i.pat, None, false);
+            if let Some((_, guard)) = &i.guard {
+                this.visit_expr(guard);
+            }
+            this.visit_expr(&i.body);
+        });
+    }
+
+    fn visit_expr_for_loop(&mut self, i: &'ast syn::ExprForLoop) {
+        self.visit_expr(&i.expr);
+        self.in_scope(|this| {
+            this.bind(&i.pat, None, false);
+            this.visit_block(&i.body);
+        });
+    }
+
+    fn visit_expr_if(&mut self, i: &'ast syn::ExprIf) {
+        // `if let` bindings are only visible in the then-branch
+        self.in_scope(|this| {
+            this.visit_expr(&i.cond);
+            this.visit_block(&i.then_branch);
+        });
+        if let Some((_, else_branch)) = &i.else_branch {
+            self.visit_expr(else_branch);
+        }
+    }
+
+    fn visit_expr_while(&mut self, i: &'ast syn::ExprWhile) {
+        self.in_scope(|this| {
+            this.visit_expr(&i.cond);
+            this.visit_block(&i.body);
+        });
+    }
+
+    fn visit_expr_let(&mut self, i: &'ast syn::ExprLet) {
+        self.visit_expr(&i.expr);
+        self.bind(&i.pat, None, false);
+    }
+
+    fn visit_expr_assign(&mut self, i: &'ast syn::ExprAssign) {
+        // A plain `x = ..` writes `x` without reading it
+        if !matches!(&*i.left, syn::Expr::Path(path) if path.path.get_ident().is_some()) {
+            self.visit_expr(&i.left);
+        }
+        self.visit_expr(&i.right);
+    }
+
+    fn visit_expr_path(&mut self, i: &'ast syn::ExprPath) {
+        if let Some(ident) = i.path.get_ident() {
+            self.read(&ident_name(ident));
+        }
+        syn::visit::visit_expr_path(self, i);
+    }
+
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        // Macro bodies are opaque; any identifier in them may be a read
+        self.read_tokens(i.tokens.clone());
+        syn::visit::visit_macro(self, i);
+    }
+}
+
+/// Every identifier a pattern binds, in source order.
+fn pattern_idents<'p>(pat: &'p syn::Pat, out: &mut Vec<&'p syn::Ident>) {
+    match pat {
+        syn::Pat::Ident(pat_ident) => {
+            out.push(&pat_ident.ident);
+            if let Some((_, subpat)) = &pat_ident.subpat {
+                pattern_idents(subpat, out);
+            }
+        }
+        syn::Pat::Type(pat_type) => pattern_idents(&pat_type.pat, out),
+        syn::Pat::Tuple(tuple) => tuple.elems.iter().for_each(|pat| pattern_idents(pat, out)),
+        syn::Pat::TupleStruct(tuple) => tuple.elems.iter().for_each(|pat| pattern_idents(pat, out)),
+        syn::Pat::Slice(slice) => slice.elems.iter().for_each(|pat| pattern_idents(pat, out)),
+        syn::Pat::Struct(pat_struct) => pat_struct.fields.iter().for_each(|field| pattern_idents(&field.pat, out)),
+        syn::Pat::Reference(reference) => pattern_idents(&reference.pat, out),
+        syn::Pat::Paren(paren) => pattern_idents(&paren.pat, out),
+        // Every alternative binds the same names
+        syn::Pat::Or(or) => or.cases.iter().take(1).for_each(|pat| pattern_idents(pat, out)),
+        _ => {}
+    }
+}
+
+/// `{name}` and `{name:?}` captures in format strings.
+static FORMAT_CAPTURE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)[}:]").unwrap());
+
+/// A binding `ShadowingFinder` tracks, with its written or evident type.
+pub struct Binding {
+    range: Range,
+    ty: Option<String>,
+}
+
+/// Finds `let` bindings that shadow a parameter, earlier `let`, or pattern binding of the same
+/// name that's still in scope in the same function. Run it in a `ScopedBindingWalker`.
+pub struct ShadowingFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    /// Source lookups for type text; independent of the position options.
+    pub source: &'a LineIndex<'a>,
+    pub out: Vec<ShadowedVariable>,
+}
+
+impl<'a> ShadowingFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, source: &'a LineIndex<'a>) -> Self {
+        Self { file, line_index, source, out: Vec::new() }
+    }
+
+    fn text(&self, node: &impl Spanned) -> String {
+        let span = node.span();
+        self.source.text(span.start(), span.end()).to_string()
+    }
+
+    /// The type of `expr` when it's evident without inference, e.g. a cast or a constructor.
+    fn evident_type(&self, expr: &syn::Expr) -> Option<String> {
+        match expr {
+            syn::Expr::Lit(lit) => match &lit.lit {
+                syn::Lit::Str(_) => Some("&str".to_string()),
+                syn::Lit::Bool(_) => Some("bool".to_string()),
+                syn::Lit::Char(_) => Some("char".to_string()),
+                syn::Lit::Int(int) if !int.suffix().is_empty() => Some(int.suffix().to_string()),
+                syn::Lit::Float(float) if !float.suffix().is_empty() => Some(float.suffix().to_string()),
+                _ => None,
+            },
+            syn::Expr::Cast(cast) => Some(self.text(&cast.ty)),
+            syn::Expr::Struct(expr_struct) => Some(self.text(&expr_struct.path)),
+            syn::Expr::Paren(paren) => self.evident_type(&paren.expr),
+            syn::Expr::Call(call) => {
+                // `Type::new(..)` and friends
+                let syn::Expr::Path(func) = &*call.func else { return None };
+                let segments = &func.path.segments;
+                let constructor = segments.last().map(|segment| ident_name(&segment.ident))?;
+                let is_constructor = matches!(constructor.as_str(), "new" | "default" | "from" | "with_capacity");
+                (segments.len() == 2 && is_constructor && starts_uppercase(&segments[0].ident) && segments[0].ident != "Self")
+                    .then(|| ident_name(&segments[0].ident))
+            }
+            syn::Expr::MethodCall(call) => match ident_name(&call.method).as_str() {
+                "to_string" => Some("String".to_string()),
+                "to_owned" if matches!(&*call.receiver, syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(_), .. })) => {
+                    Some("String".to_string())
+                }
+                "len" | "count" => Some("usize".to_string()),
+                _ => None,
+            },
+            syn::Expr::Macro(mac) if mac.mac.path.is_ident("format") => Some("String".to_string()),
+            _ => None,
+        }
+    }
+}
+
+fn same_type(a: &str, b: &str) -> bool {
+    a.split_whitespace().eq(b.split_whitespace())
+}
+
+impl BindingTracker for ShadowingFinder<'_> {
+    type Binding = Binding;
+
+    fn bind(
+        &mut self,
+        ident: &syn::Ident,
+        ty: Option<String>,
+        is_let: bool,
+        function: &str,
+        scopes: &[HashMap<String, Binding>],
+    ) -> Binding {
+        let name = ident_name(ident);
+        let range = span_to_range(ident.span(), self.line_index);
+        let outer = scopes.iter().rev().find_map(|scope| scope.get(&name));
+        if let Some(outer) = outer.filter(|_| is_let) {
+            let type_changed = match (&outer.ty, &ty) {
+                (Some(outer), Some(inner)) => Some(!same_type(outer, inner)),
+                _ => None,
+            };
+            self.out.push(ShadowedVariable {
+                name,
+                function: function.to_string(),
+                file: self.file.clone(),
+                outer: outer.range.clone(),
+                inner: range.clone(),
+                outer_type: outer.ty.clone(),
+                inner_type: ty.clone(),
+                type_changed,
+            });
+        }
+        Binding { range, ty }
+    }
+
+    fn type_text(&self, ty: &syn::Type) -> Option<String> {
+        Some(self.text(ty))
+    }
+
+    fn init_type(&self, expr: &syn::Expr) -> Option<String> {
+        self.evident_type(expr)
+    }
+}
+
+/// A binding `UnusedVariableFinder` tracks, and whether anything read it.
+pub struct Local {
+    range: Range,
+    used: bool,
+    /// Only `let` bindings are reported; parameters and pattern bindings are tracked for scoping.
+    report: bool,
+}
+
+/// Finds `let` bindings that are never read before they go out of scope or are shadowed,
+/// like rustc's `unused_variables`. Names starting with `_` are skipped, and assigning to a
+/// variable doesn't count as reading it. Run it in a `ScopedBindingWalker`.
+pub struct UnusedVariableFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<UnusedVariable>,
+}
+
+impl<'a> UnusedVariableFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>) -> Self {
+        Self { file, line_index, out: Vec::new() }
+    }
+}
+
+impl BindingTracker for UnusedVariableFinder<'_> {
+    type Binding = Local;
+
+    fn bind(&mut self, ident: &syn::Ident, _ty: Option<String>, is_let: bool, _function: &str, _scopes: &[HashMap<String, Local>]) -> Local {
+        Local { range: span_to_range(ident.span(), self.line_index), used: false, report: is_let }
+    }
+
+    fn release(&mut self, name: String, local: Local, function: &str) {
+        if local.report && !local.used && !name.starts_with('_') {
+            self.out.push(UnusedVariable { name, function: function.to_string(), file: self.file.clone(), range: local.range });
+        }
+    }
+
+    fn read(local: &mut Local) {
+        local.used = true;
+    }
+}
+
+/// Flags statements that follow an unconditional `return`, `break`, `continue`, or a
+/// panicking macro (`panic!`, `unreachable!`, `todo!`, `unimplemented!`) in the same block.
+/// Nested item declarations aren't code that runs, so they're never flagged.
+pub struct UnreachableCodeFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<UnreachableCode>,
+}
+
+fn is_panic_macro(mac: &syn::Macro) -> bool {
+    mac.path.segments.last().is_some_and(|seg| {
+        matches!(ident_name(&seg.ident).as_str(), "panic" | "unreachable" | "todo" | "unimplemented")
+    })
+}
+
+fn diverges(stmt: &syn::Stmt) -> bool {
+    match stmt {
+        syn::Stmt::Expr(expr, _) => match expr {
+            syn::Expr::Return(_) | syn::Expr::Break(_) | syn::Expr::Continue(_) => true,
+            syn::Expr::Macro(expr_macro) => is_panic_macro(&expr_macro.mac),
+            _ => false,
+        },
+        syn::Stmt::Macro(stmt_macro) => is_panic_macro(&stmt_macro.mac),
+        _ => false,
+    }
+}
+
+impl<'ast> Visit<'ast> for UnreachableCodeFinder<'_> {
+    fn visit_block(&mut self, i: &'ast syn::Block) {
+        if let Some(first) = i.stmts.iter().position(diverges) {
+            for stmt in &i.stmts[first + 1..] {
+                if !matches!(stmt, syn::Stmt::Item(_)) {
+                    self.out.push(UnreachableCode {
+                        file: self.file.clone(),
+                        range: span_to_range(stmt.span(), self.line_index),
+                    });
+                }
+            }
+        }
+        syn::visit::visit_block(self, i);
+    }
+}
+
+/// The test attribute on a function, if any: any attribute whose last path segment is
+/// `test` or `test_case`, which covers `#[test]`, `#[tokio::test]` and `#[test_case(..)]`.
+pub fn test_attribute(attrs: &[syn::Attribute]) -> Option<String> {
+    attrs.iter().map(|attr| attr.path()).find_map(|path| {
+        let last = path.segments.last()?;
+        matches!(ident_name(&last.ident).as_str(), "test" | "test_case").then(|| {
+            path.segments.iter().map(|seg| ident_name(&seg.ident)).collect::<Vec<_>>().join("::")
+        })
+    })
+}
+
+/// Looks for anything that can make a test fail: `assert*!`/`debug_assert*!`, the panicking
+/// macros, `?`, and `unwrap`/`expect`. Macro bodies are scanned token by token, so an
+/// assertion inside e.g. `tokio::join!` still counts.
+#[derive(Default)]
+struct AssertionFinder {
+    found: bool,
+}
+
+fn is_failing_name(name: &str) -> bool {
+    name.starts_with("assert")
+        || name.starts_with("debug_assert")
+        || matches!(name, "panic" | "unreachable" | "todo" | "unimplemented" | "unwrap" | "expect")
+}
+
+impl AssertionFinder {
+    fn scan_tokens(&mut self, tokens: proc_macro2::TokenStream) {
+        for token in tokens {
+            match token {
+                proc_macro2::TokenTree::Ident(ident) if is_failing_name(&ident_name(&ident)) => self.found = true,
+                proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '?' => self.found = true,
+                proc_macro2::TokenTree::Group(group) => self.scan_tokens(group.stream()),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for AssertionFinder {
+    fn visit_macro(&mut self, i: &'ast syn::Macro) {
+        if i.path.segments.last().is_some_and(|seg| is_failing_name(&ident_name(&seg.ident))) {
+            self.found = true;
+        }
+        self.scan_tokens(i.tokens.clone());
+    }
+
+    fn visit_expr_try(&mut self, _i: &'ast syn::ExprTry) {
+        self.found = true;
+    }
+
+    fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
+        if matches!(ident_name(&i.method).as_str(), "unwrap" | "expect") {
+            self.found = true;
+        }
+        syn::visit::visit_expr_method_call(self, i);
+    }
+}
+
+pub struct TestCollector<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<TestInfo>,
+    modules: Vec<String>,
+}
+
+impl<'a> TestCollector<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>) -> Self {
+        Self { file, line_index, out: Vec::new(), modules: Vec::new() }
+    }
+}
+
+impl<'ast> Visit<'ast> for TestCollector<'_> {
+    fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+        self.modules.push(ident_name(&i.ident));
+        syn::visit::visit_item_mod(self, i);
+        self.modules.pop();
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        if let Some(attribute) = test_attribute(&i.attrs) {
+            let name = ident_name(&i.sig.ident);
+            let path = self.modules.iter().chain(std::iter::once(&name)).cloned().collect::<Vec<_>>().join("::");
+            let mut assertions = AssertionFinder::default();
+            assertions.visit_block(&i.block);
+            self.out.push(TestInfo {
+                path,
+                name,
+                attribute,
+                ignored: i.attrs.iter().any(|attr| attr.path().is_ident("ignore")),
+                has_assertions: assertions.found || i.attrs.iter().any(|attr| attr.path().is_ident("should_panic")),
+                file: self.file.clone(),
+                range: span_to_range(i.sig.ident.span(), self.line_index),
+            });
+        }
+        syn::visit::visit_item_fn(self, i);
+    }
+}
+
+/// Blocking std APIs that `find_blocking_in_async` always looks for; callers can add more.
+pub const DEFAULT_BLOCKING_CALLS: &[&str] = &[
+    "std::thread::sleep",
+    "std::fs::read",
+    "std::fs::read_to_string",
+    "std::fs::read_dir",
+    "std::fs::write",
+    "std::fs::copy",
+    "std::fs::rename",
+    "std::fs::metadata",
+    "std::fs::create_dir",
+    "std::fs::create_dir_all",
+    "std::fs::remove_file",
+    "std::fs::remove_dir",
+    "std::fs::remove_dir_all",
+    "std::fs::File::open",
+    "std::fs::File::create",
+    "std::net::TcpStream::connect",
+    "std::net::TcpListener::bind",
+    "std::net::UdpSocket::bind",
+    "std::io::stdin",
+];
+
+/// Finds calls to blocking functions from `async fn` bodies and `async` blocks.
+///
+/// Call paths are resolved through the file's `use` items, so `thread::sleep` after
+/// `use std::thread;` and a bare `sleep` after `use std::thread::sleep;` both match
+/// `std::thread::sleep`. Unresolved multi-segment paths match by suffix. Arguments of
+/// `spawn_blocking` and `block_in_place`, and nested non-async functions, are skipped.
+pub struct BlockingCallFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub blocking_calls: Vec<String>,
+    pub out: Vec<BlockingCall>,
+    imports: HashMap<String, String>,
+    current: AsyncContext,
+}
+
+/// The function `BlockingCallFinder` is in, and whether the code at hand runs async.
+#[derive(Default)]
+pub struct AsyncContext {
+    function: String,
+    in_async: bool,
+}
+
+impl<'a> BlockingCallFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, blocking_calls: Vec<String>, ast: &syn::File) -> Self {
+        let mut use_paths = Vec::new();
+        for item in &ast.items {
+            collect_file_use_paths(item, &mut use_paths);
+        }
+        let imports = use_paths
+            .into_iter()
+            .filter_map(|path| Some((path.rsplit("::").next()?.to_string(), path)))
+            .collect();
+        Self { file, line_index, blocking_calls, out: Vec::new(), imports, current: AsyncContext::default() }
+    }
+
+    /// The configured blocking API `path` refers to, if any.
+    fn resolve(&self, path: &syn::Path) -> Option<&str> {
+        let mut segments: Vec<String> = path.segments.iter().map(|seg| ident_name(&seg.ident)).collect();
+        let imported = self.imports.get(&segments[0]).cloned();
+        if let Some(full) = imported {
+            segments.splice(0..1, full.split("::").map(str::to_string));
+        }
+        let resolved = segments.join("::");
+        let suffix = format!("::{resolved}");
+        self.blocking_calls
+            .iter()
+            .find(|call| **call == resolved || (segments.len() > 1 && call.ends_with(&suffix)))
+            .map(String::as_str)
+    }
+}
+
+impl PerFunction for BlockingCallFinder<'_> {
+    type FnState = AsyncContext;
+
+    fn fn_state(&mut self) -> &mut AsyncContext {
+        &mut self.current
+    }
+
+    fn start_fn(&mut self, sig: &syn::Signature) -> AsyncContext {
+        AsyncContext { function: ident_name(&sig.ident), in_async: sig.asyncness.is_some() }
+    }
+}
+
+fn collect_file_use_paths(item: &syn::Item, out: &mut Vec<String>) {
+    match item {
+        syn::Item::Use(item_use) => collect_full_use_paths(&item_use.tree, &mut Vec::new(), out),
+        syn::Item::Mod(syn::ItemMod { content: Some((_, items)), .. }) => {
+            items.iter().for_each(|item| collect_file_use_paths(item, out));
+        }
+        _ => {}
+    }
+}
+
+impl<'ast> Visit<'ast> for BlockingCallFinder<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.visit_fn(&i.sig, i.default.as_ref(), |this| syn::visit::visit_trait_item_fn(this, i));
+    }
+
+    fn visit_expr_async(&mut self, i: &'ast syn::ExprAsync) {
+        let outer_async = std::mem::replace(&mut self.current.in_async, true);
+        syn::visit::visit_expr_async(self, i);
+        self.current.in_async = outer_async;
+    }
+
+    fn visit_expr_call(&mut self, i: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(func) = &*i.func {
+            let name = func.path.segments.last().map(|seg| ident_name(&seg.ident));
+            // Offloading to a blocking-friendly thread is the fix, not the problem
+            if matches!(name.as_deref(), Some("spawn_blocking" | "block_in_place")) {
+                return;
+            }
+            if self.current.in_async {
+                if let Some(blocking_api) = self.resolve(&func.path).map(str::to_string) {
+                    self.out.push(BlockingCall {
+                        call: func.path.segments.iter().map(|seg| ident_name(&seg.ident)).collect::<Vec<_>>().join("::"),
+                        blocking_api,
+                        function: self.current.function.clone(),
+                        file: self.file.clone(),
+                        range: span_to_range(func.span(), self.line_index),
+                    });
+                }
+            }
+        }
+        syn::visit::visit_expr_call(self, i);
+    }
+}
+
+/// Finds `.await`s inside the body of a `for`, `while` or `loop` in the same function.
+///
+/// A `for` loop's iterator and a `while` condition (e.g. `while let Some(x) = s.next().await`)
+/// aren't flagged, and `async` blocks and closures start afresh since the futures they build
+/// may well be awaited concurrently.
+pub struct AwaitInLoopFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<AwaitInLoop>,
+    current: LoopContext,
+}
+
+/// The function `AwaitInLoopFinder` is in, and the loops around the code at hand.
+#[derive(Default)]
+pub struct LoopContext {
+    function: String,
+    loops: Vec<LoopKind>,
+}
+
+impl<'a> AwaitInLoopFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>) -> Self {
+        Self { file, line_index, out: Vec::new(), current: LoopContext::default() }
+    }
+
+    fn with_loops(&mut self, loops: Vec<LoopKind>, visit: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.current.loops, loops);
+        visit(self);
+        self.current.loops = outer;
+    }
+
+    fn in_loop(&mut self, kind: LoopKind, visit: impl FnOnce(&mut Self)) {
+        self.current.loops.push(kind);
+        visit(self);
+        self.current.loops.pop();
+    }
+}
+
+impl PerFunction for AwaitInLoopFinder<'_> {
+    type FnState = LoopContext;
+
+    fn fn_state(&mut self) -> &mut LoopContext {
+        &mut self.current
+    }
+
+    fn start_fn(&mut self, sig: &syn::Signature) -> LoopContext {
+        LoopContext { function: ident_name(&sig.ident), loops: Vec::new() }
+    }
+}
+
+impl<'ast> Visit<'ast> for AwaitInLoopFinder<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.visit_fn(&i.sig, i.default.as_ref(), |this| syn::visit::visit_trait_item_fn(this, i));
+    }
+
+    fn visit_expr_for_loop(&mut self, i: &'ast syn::ExprForLoop) {
+        self.visit_expr(&i.expr);
+        self.in_loop(LoopKind::For, |this| this.visit_block(&i.body));
+    }
+
+    fn visit_expr_while(&mut self, i: &'ast syn::ExprWhile) {
+        self.visit_expr(&i.cond);
+        self.in_loop(LoopKind::While, |this| this.visit_block(&i.body));
+    }
+
+    fn visit_expr_loop(&mut self, i: &'ast syn::ExprLoop) {
+        self.in_loop(LoopKind::Loop, |this| this.visit_block(&i.body));
+    }
+
+    fn visit_expr_async(&mut self, i: &'ast syn::ExprAsync) {
+        self.with_loops(Vec::new(), |this| syn::visit::visit_expr_async(this, i));
+    }
+
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        self.with_loops(Vec::new(), |this| syn::visit::visit_expr_closure(this, i));
+    }
+
+    fn visit_expr_await(&mut self, i: &'ast syn::ExprAwait) {
+        if let Some(&loop_kind) = self.current.loops.last() {
+            self.out.push(AwaitInLoop {
+                function: self.current.function.clone(),
+                loop_kind,
+                file: self.file.clone(),
+                range: span_to_range(i.await_token.span, self.line_index),
+            });
+        }
+        syn::visit::visit_expr_await(self, i);
+    }
+}
+
+/// Flags `match` expressions with more than `max_arms` arms, and those over enum variants
+/// that end in a `_ => {}` catch-all.
+pub struct LargeMatchFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<MatchIssue>,
+    max_arms: usize,
+    function: String,
+}
+
+impl<'a> LargeMatchFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, max_arms: usize) -> Self {
+        Self { file, line_index, out: Vec::new(), max_arms, function: String::new() }
+    }
+
+    fn report(&mut self, kind: MatchIssueKind, i: &syn::ExprMatch) {
+        self.out.push(MatchIssue {
+            kind,
+            function: self.function.clone(),
+            arms: i.arms.len(),
+            file: self.file.clone(),
+            range: span_to_range(i.match_token.span, self.line_index),
+        });
+    }
+}
+
+impl PerFunction for LargeMatchFinder<'_> {
+    type FnState = String;
+
+    fn fn_state(&mut self) -> &mut String {
+        &mut self.function
+    }
+
+    fn start_fn(&mut self, sig: &syn::Signature) -> String {
+        ident_name(&sig.ident)
+    }
+}
+
+/// `_ => {}` or `_ => ()` without a guard.
+fn is_empty_catch_all(arm: &syn::Arm) -> bool {
+    let empty_body = match &*arm.body {
+        syn::Expr::Block(block) => block.block.stmts.is_empty(),
+        syn::Expr::Tuple(tuple) => tuple.elems.is_empty(),
+        _ => false,
+    };
+    matches!(arm.pat, syn::Pat::Wild(_)) && arm.guard.is_none() && empty_body
+}
+
+/// Whether the pattern names an enum variant by path (`Shape::Circle`, `Self::Empty { .. }`).
+/// Single-segment paths are left out since they can't be told from structs and bindings.
+fn is_variant_pattern(pat: &syn::Pat) -> bool {
+    let path = match pat {
+        syn::Pat::Path(pat) => &pat.path,
+        syn::Pat::TupleStruct(pat) => &pat.path,
+        syn::Pat::Struct(pat) => &pat.path,
+        syn::Pat::Or(pat) => return pat.cases.iter().any(is_variant_pattern),
+        syn::Pat::Reference(pat) => return is_variant_pattern(&pat.pat),
+        _ => return false,
+    };
+    path.segments.len() >= 2 || path.segments.first().is_some_and(|seg| seg.ident == "Self")
+}
+
+impl<'ast> Visit<'ast> for LargeMatchFinder<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.visit_fn(&i.sig, i.default.as_ref(), |this| syn::visit::visit_trait_item_fn(this, i));
+    }
+
+    fn visit_expr_match(&mut self, i: &'ast syn::ExprMatch) {
+        if i.arms.len() > self.max_arms {
+            self.report(MatchIssueKind::TooManyArms, i);
+        }
+        if i.arms.iter().any(is_empty_catch_all) && i.arms.iter().any(|arm| is_variant_pattern(&arm.pat)) {
+            self.report(MatchIssueKind::EmptyCatchAll, i);
+        }
+        syn::visit::visit_expr_match(self, i);
+    }
+}
+
+/// Finds uses of one enum variant written as `Enum::Variant` (possibly behind a longer path
+/// like `crate::shapes::Shape::Circle`) in expressions, patterns, and `use` items, plus
+/// `Self::Variant` inside `impl Enum`. A bare `Variant` is not matched.
+pub struct VariantReferenceFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub matches: Vec<ReferenceLocation>,
+    enum_name: String,
+    variant_name: String,
+    in_enum_impl: bool,
+}
+
+impl<'a> VariantReferenceFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, enum_name: &str, variant_name: &str) -> Self {
+        Self {
+            file,
+            line_index,
+            matches: Vec::new(),
+            enum_name: unraw_name(enum_name).to_string(),
+            variant_name: unraw_name(variant_name).to_string(),
+            in_enum_impl: false,
+        }
+    }
+
+    fn push(&mut self, variant: &syn::Ident) {
+        self.matches.push(ReferenceLocation {
+            file: self.file.clone(),
+            range: span_to_range(variant.span(), self.line_index),
+        });
+    }
+
+    fn is_enum(&self, ident: &syn::Ident) -> bool {
+        let name = ident_name(ident);
+        name == self.enum_name || (self.in_enum_impl && name == "Self")
+    }
+}
+
+impl<'ast> Visit<'ast> for VariantReferenceFinder<'_> {
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let is_enum_impl = matches!(&*i.self_ty, syn::Type::Path(type_path)
+            if type_path.path.segments.last().is_some_and(|seg| ident_name(&seg.ident) == self.enum_name));
+        let outer = std::mem::replace(&mut self.in_enum_impl, is_enum_impl);
+        syn::visit::visit_item_impl(self, i);
+        self.in_enum_impl = outer;
+    }
+
+    fn visit_path(&mut self, i: &'ast syn::Path) {
+        let segments: Vec<&syn::PathSegment> = i.segments.iter().collect();
+        if let [.., enum_seg, variant_seg] = segments[..] {
+            if self.is_enum(&enum_seg.ident) && ident_name(&variant_seg.ident) == self.variant_name {
+                self.push(&variant_seg.ident);
+            }
+        }
+        syn::visit::visit_path(self, i);
+    }
+
+    fn visit_use_path(&mut self, i: &'ast syn::UsePath) {
+        if ident_name(&i.ident) == self.enum_name {
+            let trees: Vec<&syn::UseTree> = match &*i.tree {
+                syn::UseTree::Group(group) => group.items.iter().collect(),
+                tree => vec![tree],
+            };
+            for tree in trees {
+                let variant = match tree {
+                    syn::UseTree::Name(name) => &name.ident,
+                    syn::UseTree::Rename(rename) => &rename.ident,
+                    _ => continue,
+                };
+                if ident_name(variant) == self.variant_name {
+                    self.push(variant);
+                }
+            }
+        }
+        syn::visit::visit_use_path(self, i);
+    }
+}
+
+/// Finds uses of one struct field: `value.field` accesses, struct literal inits, and
+/// destructuring patterns. Literals and patterns name their struct, so they match exactly.
+/// For accesses the receiver's type is looked up when it is `self` in an impl or a local
+/// with a type annotation or struct literal initializer; other receivers match by name.
+pub struct FieldReferenceFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub matches: Vec<FieldReference>,
+    struct_name: String,
+    field: String,
+    impl_type: Option<String>,
+    /// Type of each local in the current function, `None` when it isn't known.
+    bindings: HashMap<String, Option<String>>,
+}
+
+impl<'a> FieldReferenceFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, struct_name: &str, field: &str) -> Self {
+        Self {
+            file,
+            line_index,
+            matches: Vec::new(),
+            struct_name: unraw_name(struct_name).to_string(),
+            field: unraw_name(field).to_string(),
+            impl_type: None,
+            bindings: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, kind: FieldReferenceKind, exact: bool, member: &syn::Member) {
+        self.matches.push(FieldReference {
+            kind,
+            exact,
+            file: self.file.clone(),
+            range: span_to_range(member.span(), self.line_index),
+        });
+    }
+
+    fn is_field(&self, member: &syn::Member) -> bool {
+        match member {
+            syn::Member::Named(ident) => ident_name(ident) == self.field,
+            syn::Member::Unnamed(index) => index.index.to_string() == self.field,
+        }
+    }
+
+    /// Whether a struct literal or pattern path names the target struct.
+    fn is_struct_path(&self, path: &syn::Path) -> bool {
+        path.segments.last().is_some_and(|seg| {
+            let name = ident_name(&seg.ident);
+            name == self.struct_name || (name == "Self" && self.impl_type.as_deref() == Some(self.struct_name.as_str()))
+        })
+    }
+
+    /// The struct a type names, or `None` when that can't be told (e.g. `Box<T>` derefs).
+    fn type_name(&self, ty: &syn::Type) -> Option<String> {
+        match ty {
+            syn::Type::Reference(reference) => self.type_name(&reference.elem),
+            syn::Type::Paren(paren) => self.type_name(&paren.elem),
+            syn::Type::Path(type_path) => {
+                let seg = type_path.path.segments.last()?;
+                let name = ident_name(&seg.ident);
+                let known = name == self.struct_name || matches!(seg.arguments, syn::PathArguments::None);
+                known.then_some(name)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl PerFunction for FieldReferenceFinder<'_> {
+    type FnState = HashMap<String, Option<String>>;
+
+    fn fn_state(&mut self) -> &mut HashMap<String, Option<String>> {
+        &mut self.bindings
+    }
+
+    fn start_fn(&mut self, sig: &syn::Signature) -> HashMap<String, Option<String>> {
+        let mut bindings = HashMap::new();
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(pat_type) = input {
+                if let syn::Pat::Ident(pat_ident) = &*pat_type.pat {
+                    bindings.insert(ident_name(&pat_ident.ident), self.type_name(&pat_type.ty));
+                }
+            }
+        }
+        bindings
+    }
+}
+
+impl<'ast> Visit<'ast> for FieldReferenceFinder<'_> {
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let impl_type = self.type_name(&i.self_ty);
+        let outer = std::mem::replace(&mut self.impl_type, impl_type);
+        syn::visit::visit_item_impl(self, i);
+        self.impl_type = outer;
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_local(&mut self, i: &'ast syn::Local) {
+        // Visit the initializer first; it can't see the binding it initializes
+        syn::visit::visit_local(self, i);
+        let (name, ty) = match &i.pat {
+            syn::Pat::Type(pat_type) => match &*pat_type.pat {
+                syn::Pat::Ident(pat_ident) => (ident_name(&pat_ident.ident), self.type_name(&pat_type.ty)),
+                _ => return,
+            },
+            syn::Pat::Ident(pat_ident) => {
+                let ty = match i.init.as_ref().map(|init| &*init.expr) {
+                    Some(syn::Expr::Struct(expr)) if self.is_struct_path(&expr.path) => Some(self.struct_name.clone()),
+                    Some(syn::Expr::Struct(expr)) => expr.path.segments.last().map(|seg| ident_name(&seg.ident)),
+                    _ => None,
+                };
+                (ident_name(&pat_ident.ident), ty)
+            }
+            _ => return,
+        };
+        self.bindings.insert(name, ty);
+    }
+
+    fn visit_expr_field(&mut self, i: &'ast syn::ExprField) {
+        if self.is_field(&i.member) {
+            let receiver_type = match &*i.base {
+                syn::Expr::Path(path) => match path.path.get_ident().map(ident_name).as_deref() {
+                    Some("self") => self.impl_type.clone(),
+                    Some(name) => self.bindings.get(name).cloned().flatten(),
+                    None => None,
+                },
+                _ => None,
+            };
+            match receiver_type {
+                Some(ty) if ty != self.struct_name => {}
+                Some(_) => self.push(FieldReferenceKind::Access, true, &i.member),
+                None => self.push(FieldReferenceKind::Access, false, &i.member),
+            }
+        }
+        syn::visit::visit_expr_field(self, i);
+    }
+
+    fn visit_expr_struct(&mut self, i: &'ast syn::ExprStruct) {
+        if self.is_struct_path(&i.path) {
+            for field in &i.fields {
+                if self.is_field(&field.member) {
+                    self.push(FieldReferenceKind::Init, true, &field.member);
+                }
+            }
+        }
+        syn::visit::visit_expr_struct(self, i);
+    }
+
+    fn visit_pat_struct(&mut self, i: &'ast syn::PatStruct) {
+        if self.is_struct_path(&i.path) {
+            for field in &i.fields {
+                if self.is_field(&field.member) {
+                    self.push(FieldReferenceKind::Pattern, true, &field.member);
+                }
+            }
+        }
+        syn::visit::visit_pat_struct(self, i);
+    }
+}
+
+/// Collects the `#[derive(...)]` traits and other attributes of every struct, enum, and union.
+pub struct DeriveCollector<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<TypeDerives>,
+}
+
+impl DeriveCollector<'_> {
+    fn push(&mut self, kind: SymbolKind, ident: &syn::Ident, attrs: &[syn::Attribute]) {
+        let attributes = attrs
+            .iter()
+            .filter(|attr| !attr.path().is_ident("derive") && !attr.path().is_ident("doc"))
+            .map(|attr| path_text(attr.path()))
+            .collect();
+
+        self.out.push(TypeDerives {
+            name: ident_name(ident),
+            kind,
+            derives: derived_traits(attrs),
+            attributes,
+            file: self.file.clone(),
+            range: span_to_range(ident.span(), self.line_index),
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for DeriveCollector<'_> {
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.push(SymbolKind::Struct, &i.ident, &i.attrs);
+        syn::visit::visit_item_struct(self, i);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        self.push(SymbolKind::Enum, &i.ident, &i.attrs);
+        syn::visit::visit_item_enum(self, i);
+    }
+
+    fn visit_item_union(&mut self, i: &'ast syn::ItemUnion) {
+        self.push(SymbolKind::Union, &i.ident, &i.attrs);
+        syn::visit::visit_item_union(self, i);
+    }
+}
+
+/// Paths in the `#[derive(...)]` attributes, as written.
+pub fn derived_traits(attrs: &[syn::Attribute]) -> Vec<String> {
+    let mut derives = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        if let Ok(paths) = attr.parse_args_with(syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated) {
+            derives.extend(paths.iter().map(path_text));
+        }
+    }
+    derives
+}
+
+/// The declared return type, or `None` for functions returning `()`, spelled out or not.
+pub fn return_type(sig: &syn::Signature) -> Option<&syn::Type> {
+    match &sig.output {
+        syn::ReturnType::Type(_, ty) if !matches!(&**ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty()) => Some(ty),
+        _ => None,
+    }
+}
+
+/// Trait bounds of the generic parameters and `where` predicates, as `Type: Trait`.
+fn trait_bounds(generics: &syn::Generics) -> Vec<String> {
+    let mut bounds = Vec::new();
+    let mut push = |bounded: String, param_bounds: &syn::punctuated::Punctuated<syn::TypeParamBound, syn::Token![+]>| {
+        for bound in param_bounds {
+            if let syn::TypeParamBound::Trait(bound) = bound {
+                let maybe = if matches!(bound.modifier, syn::TraitBoundModifier::Maybe(_)) { "?" } else { "" };
+                bounds.push(format!("{}: {}{}", bounded, maybe, path_text(&bound.path)));
+            }
+        }
+    };
+    for param in generics.type_params() {
+        push(ident_name(&param.ident), &param.bounds);
+    }
+    for predicate in generics.where_clause.iter().flat_map(|clause| &clause.predicates) {
+        if let syn::WherePredicate::Type(predicate) = predicate {
+            let bounded = match &predicate.bounded_ty {
+                syn::Type::Path(type_path) if type_path.qself.is_none() => path_text(&type_path.path),
+                other => tokens_text(other.to_token_stream()),
+            };
+            push(bounded, &predicate.bounds);
+        }
+    }
+    bounds
+}
+
+/// Renders type tokens with rustfmt-like spacing, e.g. `Result<Vec<u8>, io::Error>` or
+/// `&'a mut dyn Fn(u8) -> bool` rather than the spaced-out `TokenStream::to_string`.
+pub fn tokens_text(tokens: proc_macro2::TokenStream) -> String {
+    let mut out = String::new();
+    write_tokens(tokens, &mut out);
+    out.trim().to_string()
+}
+
+fn write_tokens(tokens: proc_macro2::TokenStream, out: &mut String) {
+    use proc_macro2::{Delimiter, TokenTree};
+    // Words (identifiers and literals) in a row need a space between them
+    let mut after_word = false;
+    for token in tokens {
+        match token {
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if after_word {
+                    out.push(' ');
+                }
+                out.push_str(&token.to_string());
+                after_word = true;
+                continue;
+            }
+            TokenTree::Punct(punct) => match punct.as_char() {
+                ',' | ';' => {
+                    out.push(punct.as_char());
+                    out.push(' ');
+                }
+                '+' | '=' => {
+                    let trimmed = out.trim_end().len();
+                    out.truncate(trimmed);
+                    out.push(' ');
+                    out.push(punct.as_char());
+                    out.push(' ');
+                }
+                '-' => out.push_str(" -"),
+                '>' if out.ends_with(" -") => out.push_str("> "),
+                '>' => {
+                    let trimmed = out.trim_end().len();
+                    out.truncate(trimmed);
+                    out.push('>');
+                }
+                c => out.push(c),
+            },
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                out.push_str(open);
+                write_tokens(group.stream(), out);
+                let trimmed = out.trim_end().len();
+                out.truncate(trimmed);
+                out.push_str(close);
+            }
+        }
+        after_word = false;
+    }
+}
+
+/// A path as written, e.g. `serde::Serialize`, without generic arguments.
+fn path_text(path: &syn::Path) -> String {
+    let segments: Vec<String> = path.segments.iter().map(|seg| ident_name(&seg.ident)).collect();
+    let joined = segments.join("::");
+    if path.leading_colon.is_some() { format!("::{}", joined) } else { joined }
+}
+
+/// Collects every item, method, and variant annotated `#[deprecated]`, with its `since` and
+/// `note` when given.
+pub struct DeprecatedCollector<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<DeprecatedItem>,
+    /// Names of the items, methods and variants declared without `#[deprecated]`; trait impl
+    /// methods are left out, since they implement their trait's item.
+    pub undeprecated: HashSet<String>,
+    container: Option<String>,
+    in_trait_impl: bool,
+}
+
+impl<'a> DeprecatedCollector<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>) -> Self {
+        Self { file, line_index, out: Vec::new(), undeprecated: HashSet::new(), container: None, in_trait_impl: false }
+    }
+
+    fn check(&mut self, ident: &syn::Ident, attrs: &[syn::Attribute], container: Option<String>) {
+        let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("deprecated")) else {
+            if !self.in_trait_impl {
+                self.undeprecated.insert(ident_name(ident));
+            }
+            return;
+        };
+        let (mut since, mut note) = (None, None);
+        match &attr.meta {
+            // `#[deprecated = "note"]`
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(text), .. }),
+                ..
+            }) => note = Some(text.value()),
+            // `#[deprecated(since = "1.2", note = "...")]`
+            syn::Meta::List(_) => {
+                let _ = attr.parse_nested_meta(|meta| {
+                    let value = meta.value()?.parse::<syn::LitStr>()?.value();
+                    if meta.path.is_ident("since") {
+                        since = Some(value);
+                    } else if meta.path.is_ident("note") {
+                        note = Some(value);
+                    }
+                    Ok(())
+                });
+            }
+            _ => {}
+        }
+
+        self.out.push(DeprecatedItem {
+            name: ident_name(ident),
+            container,
+            since,
+            note,
+            file: self.file.clone(),
+            range: span_to_range(ident.span(), self.line_index),
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for DeprecatedCollector<'_> {
+    fn visit_item(&mut self, i: &'ast syn::Item) {
+        let named = match i {
+            syn::Item::Fn(item) => Some((&item.sig.ident, &item.attrs)),
+            syn::Item::Struct(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Enum(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Union(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Trait(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Type(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Const(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Static(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Mod(item) => Some((&item.ident, &item.attrs)),
+            syn::Item::Macro(item) => item.ident.as_ref().map(|ident| (ident, &item.attrs)),
+            _ => None,
+        };
+        if let Some((ident, attrs)) = named {
+            self.check(ident, attrs, None);
+        }
+
+        // Methods belong to the impl'd type or the trait, variants to their enum
+        let container = match i {
+            syn::Item::Impl(item) => match &*item.self_ty {
+                syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| ident_name(&seg.ident)),
+                _ => None,
+            },
+            syn::Item::Trait(item) => Some(ident_name(&item.ident)),
+            syn::Item::Enum(item) => Some(ident_name(&item.ident)),
+            _ => None,
+        };
+        let in_trait_impl = matches!(i, syn::Item::Impl(item) if item.trait_.is_some());
+        let outer_container = std::mem::replace(&mut self.container, container);
+        let outer_in_trait_impl = std::mem::replace(&mut self.in_trait_impl, in_trait_impl);
+        syn::visit::visit_item(self, i);
+        self.container = outer_container;
+        self.in_trait_impl = outer_in_trait_impl;
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.check(&i.sig.ident, &i.attrs, self.container.clone());
+        syn::visit::visit_impl_item_fn(self, i);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.check(&i.sig.ident, &i.attrs, self.container.clone());
+        syn::visit::visit_trait_item_fn(self, i);
+    }
+
+    fn visit_variant(&mut self, i: &'ast syn::Variant) {
+        self.check(&i.ident, &i.attrs, self.container.clone());
+        syn::visit::visit_variant(self, i);
+    }
+}
+
+/// Finds the uses of `items` in one file: path segments, method calls and `use` trees naming
+/// them. Declarations aren't paths, so they aren't reported.
+///
+/// A use qualified by another segment (`Conn::new`, `net::open`) only counts when that segment
+/// names the item's container or module. An unqualified one counts when no undeprecated item
+/// shares the name, or when it's in the item's own file (or impl, for methods).
+pub struct DeprecatedUseFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    /// Index into `items` of the item used, with where.
+    pub out: Vec<(usize, Range)>,
+    items: &'a [DeprecatedItem],
+    by_name: HashMap<&'a str, Vec<usize>>,
+    /// Last segment of the module path of each item's file.
+    item_modules: Vec<&'a str>,
+    undeprecated: &'a HashSet<String>,
+    impl_type: Option<String>,
+    use_qualifier: Option<String>,
+}
+
+impl<'a> DeprecatedUseFinder<'a> {
+    pub fn new(
+        file: String,
+        line_index: Option<&'a LineIndex<'a>>,
+        items: &'a [DeprecatedItem],
+        module_paths: &'a std::collections::BTreeMap<String, String>,
+        undeprecated: &'a HashSet<String>,
+    ) -> Self {
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, item) in items.iter().enumerate() {
+            by_name.entry(item.name.as_str()).or_default().push(index);
+        }
+        let item_modules = items
+            .iter()
+            .map(|item| module_paths.get(&item.file).and_then(|path| path.rsplit("::").next()).unwrap_or("crate"))
+            .collect();
+        Self { file, line_index, out: Vec::new(), items, by_name, item_modules, undeprecated, impl_type: None, use_qualifier: None }
+    }
+
+    fn check(&mut self, ident: &syn::Ident, qualifier: Option<&str>) {
+        let name = ident_name(ident);
+        let Some(candidates) = self.by_name.get(name.as_str()) else { return };
+        for &index in candidates {
+            let item = &self.items[index];
+            let used = match (qualifier, &item.container) {
+                (Some(qualifier), Some(container)) => {
+                    qualifier == container || (qualifier == "Self" && self.impl_type.as_ref() == Some(container))
+                }
+                (Some(qualifier), None) => {
+                    qualifier == self.item_modules[index]
+                        || (qualifier == "self" && self.file == item.file)
+                        || (matches!(qualifier, "crate" | "self" | "super") && !self.undeprecated.contains(&name))
+                }
+                (None, Some(container)) => !self.undeprecated.contains(&name) || self.impl_type.as_ref() == Some(container),
+                (None, None) => !self.undeprecated.contains(&name) || self.file == item.file,
+            };
+            if used {
+                self.out.push((index, span_to_range(ident.span(), self.line_index)));
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for DeprecatedUseFinder<'_> {
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let impl_type = match &*i.self_ty {
+            syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| ident_name(&seg.ident)),
+            _ => None,
+        };
+        let outer_impl = std::mem::replace(&mut self.impl_type, impl_type);
+        syn::visit::visit_item_impl(self, i);
+        self.impl_type = outer_impl;
+    }
+
+    fn visit_path(&mut self, i: &'ast syn::Path) {
+        let mut qualifier = None;
+        for segment in &i.segments {
+            self.check(&segment.ident, qualifier.as_deref());
+            qualifier = Some(ident_name(&segment.ident));
+        }
+        syn::visit::visit_path(self, i);
+    }
+
+    fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
+        self.check(&i.method, None);
+        syn::visit::visit_expr_method_call(self, i);
+    }
+
+    fn visit_use_tree(&mut self, i: &'ast syn::UseTree) {
+        match i {
+            syn::UseTree::Path(path) => {
+                self.check(&path.ident, self.use_qualifier.clone().as_deref());
+                let outer = self.use_qualifier.replace(ident_name(&path.ident));
+                self.visit_use_tree(&path.tree);
+                self.use_qualifier = outer;
+            }
+            syn::UseTree::Name(name) => self.check(&name.ident, self.use_qualifier.clone().as_deref()),
+            syn::UseTree::Rename(rename) => self.check(&rename.ident, self.use_qualifier.clone().as_deref()),
+            syn::UseTree::Group(group) => group.items.iter().for_each(|tree| self.visit_use_tree(tree)),
+            syn::UseTree::Glob(_) => {}
+        }
+    }
+}
+
+/// Counts `.clone()` calls per function and reports functions with more than `max_clones`.
+pub struct CloneHotspotFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<CloneHotspot>,
+    max_clones: usize,
+    sites: Vec<Range>,
+}
+
+impl<'a> CloneHotspotFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, max_clones: usize) -> Self {
+        Self { file, line_index, out: Vec::new(), max_clones, sites: Vec::new() }
+    }
+}
+
+impl PerFunction for CloneHotspotFinder<'_> {
+    type FnState = Vec<Range>;
+
+    fn fn_state(&mut self) -> &mut Vec<Range> {
+        &mut self.sites
+    }
+
+    fn start_fn(&mut self, _sig: &syn::Signature) -> Vec<Range> {
+        Vec::new()
+    }
+
+    fn finish_fn(&mut self, sig: &syn::Signature, _body: Option<&syn::Block>, sites: Vec<Range>) {
+        if sites.len() > self.max_clones {
+            self.out.push(CloneHotspot {
+                function: ident_name(&sig.ident),
+                clones: sites.len(),
+                file: self.file.clone(),
+                range: span_to_range(sig.ident.span(), self.line_index),
+                sites,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for CloneHotspotFinder<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.visit_fn(&i.sig, i.default.as_ref(), |this| syn::visit::visit_trait_item_fn(this, i));
+    }
+
+    fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
+        if i.method == "clone" && i.args.is_empty() {
+            self.sites.push(span_to_range(i.method.span(), self.line_index));
+        }
+        syn::visit::visit_expr_method_call(self, i);
+    }
+}
+
+/// Reports method chains (`a.b().c().d()`) with more calls than `max_length`. Each chain is
+/// reported once, at its outermost call; `?` and `.await` between calls don't break it.
+pub struct LongChainFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<MethodChain>,
+    max_length: usize,
+    function: String,
+}
+
+impl<'a> LongChainFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, max_length: usize) -> Self {
+        Self { file, line_index, out: Vec::new(), max_length, function: String::new() }
+    }
+}
+
+impl PerFunction for LongChainFinder<'_> {
+    type FnState = String;
+
+    fn fn_state(&mut self) -> &mut String {
+        &mut self.function
+    }
+
+    fn start_fn(&mut self, sig: &syn::Signature) -> String {
+        ident_name(&sig.ident)
+    }
+}
+
+/// The receiver a method chain continues from, looking through `?`, `.await`, and parentheses.
+fn chain_receiver(expr: &syn::Expr) -> Option<&syn::ExprMethodCall> {
+    match expr {
+        syn::Expr::MethodCall(call) => Some(call),
+        syn::Expr::Try(try_expr) => chain_receiver(&try_expr.expr),
+        syn::Expr::Await(await_expr) => chain_receiver(&await_expr.base),
+        syn::Expr::Paren(paren) => chain_receiver(&paren.expr),
+        _ => None,
+    }
+}
+
+impl<'ast> Visit<'ast> for LongChainFinder<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.visit_fn(&i.sig, i.default.as_ref(), |this| syn::visit::visit_trait_item_fn(this, i));
+    }
+
+    fn visit_expr_method_call(&mut self, i: &'ast syn::ExprMethodCall) {
+        // Walk down to the start of the chain, visiting each call's arguments on the way so
+        // chains nested inside them are found, but not the links of this chain again
+        let mut methods = Vec::new();
+        let mut call = i;
+        loop {
+            methods.push(ident_name(&call.method));
+            for arg in &call.args {
+                self.visit_expr(arg);
+            }
+            match chain_receiver(&call.receiver) {
+                Some(receiver) => call = receiver,
+                None => break,
+            }
+        }
+        self.visit_expr(&call.receiver);
+
+        if methods.len() > self.max_length {
+            methods.reverse();
+            self.out.push(MethodChain {
+                function: self.function.clone(),
+                length: methods.len(),
+                methods,
+                file: self.file.clone(),
+                range: span_to_range(i.span(), self.line_index),
+            });
+        }
+    }
+}
+
+const OWNED_COLLECTIONS: &[&str] = &["Vec", "VecDeque", "HashMap", "HashSet", "BTreeMap", "BTreeSet", "BinaryHeap"];
+
+/// Reports functions and methods returning an owned collection, or a struct with at least
+/// `min_struct_fields` fields, by value. `Result` and `Option` are looked through, since the
+/// value inside is still moved out.
+pub struct LargeReturnFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<LargeReturn>,
+    /// Field count of every struct in the workspace, by name.
+    struct_fields: &'a HashMap<String, usize>,
+    min_struct_fields: usize,
+    current_impl: Option<String>,
+}
+
+impl<'a> LargeReturnFinder<'a> {
+    pub fn new(
+        file: String,
+        line_index: Option<&'a LineIndex<'a>>,
+        struct_fields: &'a HashMap<String, usize>,
+        min_struct_fields: usize,
+    ) -> Self {
+        Self { file, line_index, out: Vec::new(), struct_fields, min_struct_fields, current_impl: None }
+    }
+
+    fn check(&mut self, sig: &syn::Signature) {
+        let Some(ty) = return_type(sig) else { return };
+        let Some(name) = self.returned_name(ty) else { return };
+        let kind = if OWNED_COLLECTIONS.contains(&name.as_str()) {
+            LargeReturnKind::Collection
+        } else if self.struct_fields.get(&name).is_some_and(|&fields| fields >= self.min_struct_fields) {
+            LargeReturnKind::LargeStruct
+        } else {
+            return;
+        };
+
+        let borrows_input = sig.inputs.iter().any(|input| match input {
+            syn::FnArg::Receiver(receiver) => receiver.reference.is_some(),
+            syn::FnArg::Typed(pat_type) => matches!(*pat_type.ty, syn::Type::Reference(_)),
+        });
+        self.out.push(LargeReturn {
+            function: ident_name(&sig.ident),
+            container: self.current_impl.clone(),
+            return_type: tokens_text(ty.to_token_stream()),
+            kind,
+            borrows_input,
+            file: self.file.clone(),
+            range: span_to_range(sig.ident.span(), self.line_index),
+        });
+    }
+
+    /// Name of the type moved out to the caller: the last path segment, looking through
+    /// `Result` and `Option` and resolving `Self` to the impl type.
+    fn returned_name(&self, ty: &syn::Type) -> Option<String> {
+        let syn::Type::Path(type_path) = ty else { return None };
+        let segment = type_path.path.segments.last()?;
+        let name = ident_name(&segment.ident);
+        if matches!(name.as_str(), "Result" | "Option") {
+            if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+                    return self.returned_name(inner);
+                }
+            }
+            return None;
+        }
+        if name == "Self" {
+            return self.current_impl.clone();
+        }
+        Some(name)
+    }
+}
+
+impl<'ast> Visit<'ast> for LargeReturnFinder<'_> {
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let impl_type = match &*i.self_ty {
+            syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| ident_name(&seg.ident)),
+            _ => None,
+        };
+        let outer_impl = std::mem::replace(&mut self.current_impl, impl_type);
+        syn::visit::visit_item_impl(self, i);
+        self.current_impl = outer_impl;
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        // A free function nested in a method isn't part of the impl
+        let outer_impl = self.current_impl.take();
+        self.check(&i.sig);
+        syn::visit::visit_item_fn(self, i);
+        self.current_impl = outer_impl;
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.check(&i.sig);
+        syn::visit::visit_impl_item_fn(self, i);
+    }
+}
+
+/// Reports functions and methods with more explicit `return`s than `max_returns`. A `return`
+/// that is the body's last statement is the tail return and isn't counted.
+pub struct EarlyReturnFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<EarlyReturns>,
+    max_returns: usize,
+    returns: Vec<Range>,
+}
+
+impl<'a> EarlyReturnFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, max_returns: usize) -> Self {
+        Self { file, line_index, out: Vec::new(), max_returns, returns: Vec::new() }
+    }
+
+    /// Visits a closure or async block, whose `return`s don't leave the enclosing function.
+    fn visit_nested_body(&mut self, visit_body: impl FnOnce(&mut Self)) {
+        let outer_returns = std::mem::take(&mut self.returns);
+        visit_body(self);
+        self.returns = outer_returns;
+    }
+}
+
+impl PerFunction for EarlyReturnFinder<'_> {
+    type FnState = Vec<Range>;
+
+    fn fn_state(&mut self) -> &mut Vec<Range> {
+        &mut self.returns
+    }
+
+    fn start_fn(&mut self, _sig: &syn::Signature) -> Vec<Range> {
+        Vec::new()
+    }
+
+    fn finish_fn(&mut self, sig: &syn::Signature, body: Option<&syn::Block>, mut returns: Vec<Range>) {
+        let Some(body) = body else { return };
+        if let Some(syn::Stmt::Expr(tail @ syn::Expr::Return(_), _)) = body.stmts.last() {
+            let tail = span_to_range(tail.span(), self.line_index);
+            returns.retain(|range| *range != tail);
+        }
+        if returns.len() > self.max_returns {
+            self.out.push(EarlyReturns {
+                function: ident_name(&sig.ident),
+                count: returns.len(),
+                file: self.file.clone(),
+                range: span_to_range(sig.ident.span(), self.line_index),
+                returns,
+            });
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for EarlyReturnFinder<'_> {
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_item_fn(this, i));
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.visit_fn(&i.sig, Some(&i.block), |this| syn::visit::visit_impl_item_fn(this, i));
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.visit_fn(&i.sig, i.default.as_ref(), |this| syn::visit::visit_trait_item_fn(this, i));
+    }
+
+    fn visit_expr_closure(&mut self, i: &'ast syn::ExprClosure) {
+        self.visit_nested_body(|this| syn::visit::visit_expr_closure(this, i));
+    }
+
+    fn visit_expr_async(&mut self, i: &'ast syn::ExprAsync) {
+        self.visit_nested_body(|this| syn::visit::visit_expr_async(this, i));
+    }
+
+    fn visit_expr_return(&mut self, i: &'ast syn::ExprReturn) {
+        self.returns.push(span_to_range(i.span(), self.line_index));
+        syn::visit::visit_expr_return(self, i);
+    }
+}
+
+/// Reports parameter, return, and field types nesting `Result` and `Option` more than
+/// `max_depth` deep. Each nesting is reported once, at its outermost wrapper.
+pub struct NestedWrapperFinder<'a> {
+    pub file: String,
+    pub line_index: Option<&'a LineIndex<'a>>,
+    pub out: Vec<NestedWrapper>,
+    max_depth: usize,
+    current_impl: Option<String>,
+}
+
+impl<'a> NestedWrapperFinder<'a> {
+    pub fn new(file: String, line_index: Option<&'a LineIndex<'a>>, max_depth: usize) -> Self {
+        Self { file, line_index, out: Vec::new(), max_depth, current_impl: None }
+    }
+
+    fn check_signature(&mut self, sig: &syn::Signature) {
+        let name = ident_name(&sig.ident);
+        for input in &sig.inputs {
+            if let syn::FnArg::Typed(pat_type) = input {
+                self.check(&pat_type.ty, &name, self.current_impl.clone(), WrapperSite::Parameter);
+            }
+        }
+        if let Some(ty) = return_type(sig) {
+            self.check(ty, &name, self.current_impl.clone(), WrapperSite::Return);
+        }
+    }
+
+    fn check_fields(&mut self, fields: &syn::Fields, container: String) {
+        for (index, field) in fields.iter().enumerate() {
+            let item = field.ident.as_ref().map_or_else(|| index.to_string(), ident_name);
+            self.check(&field.ty, &item, Some(container.clone()), WrapperSite::Field);
+        }
+    }
+
+    fn check(&mut self, ty: &syn::Type, item: &str, container: Option<String>, site: WrapperSite) {
+        let mut nested = NestedWrappers { max_depth: self.max_depth, found: Vec::new() };
+        nested.visit_type(ty);
+        for (ty, depth) in nested.found {
+            self.out.push(NestedWrapper {
+                item: item.to_string(),
+                container: container.clone(),
+                site,
+                depth,
+                type_text: tokens_text(ty.to_token_stream()),
+                file: self.file.clone(),
+                range: span_to_range(ty.span(), self.line_index),
+            });
+        }
+    }
+}
+
+/// Outermost wrappers nested deeper than `max_depth` within one type.
+struct NestedWrappers<'ast> {
+    max_depth: usize,
+    found: Vec<(&'ast syn::Type, usize)>,
+}
+
+impl<'ast> Visit<'ast> for NestedWrappers<'ast> {
+    fn visit_type(&mut self, ty: &'ast syn::Type) {
+        let depth = wrapper_depth(ty);
+        if depth > self.max_depth {
+            self.found.push((ty, depth));
+        } else {
+            syn::visit::visit_type(self, ty);
+        }
+    }
+}
+
+/// How many `Result`s and `Option`s are directly nested at the top of `ty`, looking through
+/// references and parentheses.
+fn wrapper_depth(ty: &syn::Type) -> usize {
+    match ty {
+        syn::Type::Reference(reference) => wrapper_depth(&reference.elem),
+        syn::Type::Paren(paren) => wrapper_depth(&paren.elem),
+        syn::Type::Group(group) => wrapper_depth(&group.elem),
+        syn::Type::Path(type_path) if type_path.qself.is_none() => {
+            let Some(segment) = type_path.path.segments.last() else { return 0 };
+            if segment.ident != "Result" && segment.ident != "Option" {
+                return 0;
+            }
+            let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return 1 };
+            let inner = args.args.iter().filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(wrapper_depth(ty)),
+                _ => None,
+            });
+            1 + inner.max().unwrap_or(0)
+        }
+        _ => 0,
+    }
+}
+
+impl<'ast> Visit<'ast> for NestedWrapperFinder<'_> {
+    fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+        let impl_type = match &*i.self_ty {
+            syn::Type::Path(type_path) => type_path.path.segments.last().map(|seg| ident_name(&seg.ident)),
+            _ => None,
+        };
+        let outer_impl = std::mem::replace(&mut self.current_impl, impl_type);
+        syn::visit::visit_item_impl(self, i);
+        self.current_impl = outer_impl;
+    }
+
+    fn visit_item_trait(&mut self, i: &'ast syn::ItemTrait) {
+        let outer_impl = self.current_impl.replace(ident_name(&i.ident));
+        syn::visit::visit_item_trait(self, i);
+        self.current_impl = outer_impl;
+    }
+
+    fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+        // A free function nested in a method isn't part of the impl
+        let outer_impl = self.current_impl.take();
+        self.check_signature(&i.sig);
+        syn::visit::visit_item_fn(self, i);
+        self.current_impl = outer_impl;
+    }
+
+    fn visit_impl_item_fn(&mut self, i: &'ast syn::ImplItemFn) {
+        self.check_signature(&i.sig);
+        syn::visit::visit_impl_item_fn(self, i);
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast syn::TraitItemFn) {
+        self.check_signature(&i.sig);
+        syn::visit::visit_trait_item_fn(self, i);
+    }
+
+    fn visit_item_struct(&mut self, i: &'ast syn::ItemStruct) {
+        self.check_fields(&i.fields, ident_name(&i.ident));
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
+        let name = ident_name(&i.ident);
+        for variant in &i.variants {
+            self.check_fields(&variant.fields, format!("{}::{}", name, ident_name(&variant.ident)));
+        }
+    }
+}